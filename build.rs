@@ -1,4 +1,8 @@
 fn main() {
+    // the system libraries are only required for the bindings to libbgpstream.
+    if std::env::var_os("CARGO_FEATURE_FFI").is_none() {
+        return;
+    }
     println!("cargo:rustc-link-lib=rdkafka");
     println!("cargo:rustc-link-lib=parsebgp");
     println!("cargo:rustc-link-lib=bgpstream");
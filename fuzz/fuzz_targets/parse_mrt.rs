@@ -0,0 +1,45 @@
+//! Feed arbitrary bytes to libbgpstream as an MRT file, and parse everything it produces. The
+//! stream must only ever yield errors for malformed input, never panic or crash.
+//!
+//! Run with `cargo +nightly fuzz run parse_mrt` from the root of the repository.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use routeviews::{stream::RecordType, Query};
+
+fuzz_target!(|data: &[u8]| {
+    // the first byte selects the record type, the rest is the MRT data.
+    let Some((kind, data)) = data.split_first() else {
+        return;
+    };
+    let record_type = if kind & 1 == 0 {
+        RecordType::RIBs
+    } else {
+        RecordType::Updates
+    };
+    let Ok(mut stream) = Query::new().from_bytes(record_type, data).run() else {
+        return;
+    };
+
+    loop {
+        let mut record = match stream.next_record() {
+            Ok(Some(record)) => record,
+            Ok(None) | Err(_) => break,
+        };
+        let _ = record.collector_name_lossy();
+        let _ = record.router_name_lossy();
+        // walk each element twice: once borrowed, and once parsed into an owned element.
+        while let Ok(Some(element)) = record.next_element_ref() {
+            let _ = element.as_path().count();
+            let _ = element.communities().count();
+            let _ = element.to_element();
+        }
+    }
+
+    // the iterator parses all elements into owned values.
+    let Ok(stream) = Query::new().from_bytes(record_type, data).run() else {
+        return;
+    };
+    stream.for_each(drop);
+});
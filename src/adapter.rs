@@ -0,0 +1,329 @@
+//! Iterator adapters over streams of elements.
+//!
+//! All adapters are available on any iterator over `Result<Element, BgpStreamError>` (such as
+//! [`crate::stream::BgpStream`]) by importing [`ElementIteratorExt`].
+
+use std::collections::VecDeque;
+
+use time::{Duration, OffsetDateTime};
+
+use ipnet::IpNet;
+
+use crate::{
+    analysis::RibTable,
+    element::{Element, ElementType, IpVersion, PeerState},
+    reorder::ReorderBuffer,
+    watch::PrefixWatch,
+    BgpStreamError,
+};
+
+/// Extension trait providing adapters for iterators over elements.
+pub trait ElementIteratorExt: Iterator<Item = Result<Element, BgpStreamError>> + Sized {
+    /// Group the elements into buckets of fixed width `width`, yielding `(bucket_start, elements)`
+    /// whenever the stream crosses a bucket boundary. Buckets are aligned to the Unix epoch, i.e.,
+    /// with a width of one minute, each bucket starts at a full minute. The last (partial) bucket
+    /// is yielded once the underlying iterator is exhausted. Errors are passed through immediately,
+    /// without flushing the current bucket.
+    ///
+    /// This adapter assumes that the elements are (roughly) ordered in time. Each element is
+    /// placed into the bucket of its own timestamp. If an element belongs to a different bucket
+    /// than the current one, the current bucket is yielded and a new one is started. Hence, an
+    /// out-of-order element causes its bucket to be yielded multiple times, and buckets without
+    /// any elements are never yielded.
+    ///
+    /// # Panics
+    /// Panics if `width` is not positive.
+    fn time_buckets(self, width: Duration) -> TimeBuckets<Self> {
+        assert!(width.is_positive(), "The bucket width must be positive");
+        TimeBuckets {
+            iter: self,
+            width,
+            current: None,
+        }
+    }
+
+    /// Restore the time order of slightly out-of-order elements (e.g., from RIS Live), like
+    /// [`crate::stream::Query::reorder_window`] does for a [`crate::stream::BgpStream`]. Each
+    /// element is held back until an element at least `window` newer was seen, and elements are
+    /// released in time order. Elements with
+    /// the same time keep their relative order. Elements that arrive more than `window` late are
+    /// still yielded, but out of order. The remaining elements are yielded in order once the
+    /// underlying iterator is exhausted, and errors are passed through immediately.
+    ///
+    /// # Panics
+    /// Panics if `window` is negative.
+    fn reorder(self, window: Duration) -> Reorder<Self> {
+        assert!(
+            !window.is_negative(),
+            "The reorder window must not be negative"
+        );
+        Reorder {
+            iter: self,
+            buffer: ReorderBuffer::new(window),
+        }
+    }
+
+    /// Keep only elements whose prefix overlaps with an entry of `watch`, and annotate each of
+    /// them with all overlapping entries (see [`PrefixWatch::matches`]). The annotation is never
+    /// empty. Peer state changes have no prefix and are dropped. Errors are passed through.
+    ///
+    /// ```no_run
+    /// use routeviews::{adapter::ElementIteratorExt, stream::*, watch::PrefixWatch};
+    ///
+    /// # fn main() -> Result<(), routeviews::BgpStreamError> {
+    /// let watch: PrefixWatch = ["192.0.2.0/24".parse().unwrap()].into_iter().collect();
+    /// let stream = Query::new()
+    ///     .collector(Collector::RouteView(RouteView::Amsix))
+    ///     .record_type(RecordType::Updates)
+    ///     .live()
+    ///     .run()?;
+    /// for item in stream.watch_prefixes(&watch) {
+    ///     let (element, matches) = item?;
+    ///     println!("{:?} touches {matches:?}", element.prefix());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn watch_prefixes(self, watch: &PrefixWatch) -> WatchPrefixes<'_, Self> {
+        WatchPrefixes { iter: self, watch }
+    }
+
+    /// Annotate each element with the address family of its prefix, e.g., to route IPv4 and IPv6
+    /// elements to different sinks. Peer state changes have no prefix and are dropped. Errors are
+    /// passed through.
+    fn with_ip_version(self) -> WithIpVersion<Self> {
+        WithIpVersion { iter: self }
+    }
+
+    /// Annotate each element with the libbgpstream filters of `query` that it satisfies (see
+    /// [`crate::stream::Query::matched_filters`]), e.g., to debug a query with many filters of the
+    /// same type. Record-level filters and AS path expressions are never included. Errors are
+    /// passed through.
+    #[cfg(feature = "ffi")]
+    fn with_matched_filters(self, query: &crate::stream::Query) -> WithMatchedFilters<Self> {
+        WithMatchedFilters {
+            iter: self,
+            filters: query.describe().filters,
+        }
+    }
+
+    /// Follow the routes of each peer in `table`, and emit a synthetic withdrawal for every prefix
+    /// of a peer whose session goes down (i.e., a peer state change to anything other than
+    /// `Established`). The withdrawals are yielded right after the peer state change, with its
+    /// time and peer, in the order of the prefixes. All elements of the stream are passed through
+    /// and applied to the table, and errors are passed through.
+    ///
+    /// The adapter assumes that `table` holds the current routes at the start of the stream
+    /// (e.g., loaded from a RIB dump with [`crate::analysis::ContinuousRib`], or empty if the
+    /// stream itself starts with a RIB dump), and that it is fed every element of the peers it
+    /// tracks. Otherwise, withdrawals are missing for routes the table does not know about.
+    fn synthetic_withdrawals(self, table: RibTable) -> SyntheticWithdrawals<Self> {
+        SyntheticWithdrawals {
+            iter: self,
+            table,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<I> ElementIteratorExt for I where I: Iterator<Item = Result<Element, BgpStreamError>> {}
+
+/// Iterator returned by [`ElementIteratorExt::time_buckets`].
+pub struct TimeBuckets<I> {
+    iter: I,
+    width: Duration,
+    current: Option<(OffsetDateTime, Vec<Element>)>,
+}
+
+impl<I> TimeBuckets<I> {
+    fn bucket_start(&self, time: OffsetDateTime) -> OffsetDateTime {
+        let width = self.width.whole_nanoseconds();
+        let start = time.unix_timestamp_nanos().div_euclid(width) * width;
+        OffsetDateTime::from_unix_timestamp_nanos(start).expect("bucket starts before the element")
+    }
+}
+
+impl<I> Iterator for TimeBuckets<I>
+where
+    I: Iterator<Item = Result<Element, BgpStreamError>>,
+{
+    type Item = Result<(OffsetDateTime, Vec<Element>), BgpStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let element = match self.iter.next() {
+                Some(Ok(e)) => e,
+                Some(Err(e)) => return Some(Err(e)),
+                None => return self.current.take().map(Ok),
+            };
+            let start = self.bucket_start(element.time);
+            match self.current.as_mut() {
+                Some((current_start, elements)) if *current_start == start => {
+                    elements.push(element)
+                }
+                _ => {
+                    let done = self.current.replace((start, vec![element]));
+                    if let Some(done) = done {
+                        return Some(Ok(done));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Iterator returned by [`ElementIteratorExt::reorder`].
+pub struct Reorder<I> {
+    iter: I,
+    buffer: ReorderBuffer<Element>,
+}
+
+impl<I> Iterator for Reorder<I>
+where
+    I: Iterator<Item = Result<Element, BgpStreamError>>,
+{
+    type Item = Result<Element, BgpStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(element) = self.buffer.pop_ready() {
+                return Some(Ok(element));
+            }
+            match self.iter.next() {
+                Some(Ok(element)) => self.buffer.push(element.time, element),
+                Some(Err(e)) => return Some(Err(e)),
+                None => return self.buffer.pop().map(Ok),
+            }
+        }
+    }
+}
+
+/// Iterator returned by [`ElementIteratorExt::watch_prefixes`].
+pub struct WatchPrefixes<'a, I> {
+    iter: I,
+    watch: &'a PrefixWatch,
+}
+
+impl<I> Iterator for WatchPrefixes<'_, I>
+where
+    I: Iterator<Item = Result<Element, BgpStreamError>>,
+{
+    type Item = Result<(Element, Vec<IpNet>), BgpStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let element = match self.iter.next()? {
+                Ok(e) => e,
+                Err(e) => return Some(Err(e)),
+            };
+            let Some(prefix) = element.prefix() else {
+                continue;
+            };
+            let matches = self.watch.matches(&prefix);
+            if !matches.is_empty() {
+                return Some(Ok((element, matches)));
+            }
+        }
+    }
+}
+
+/// Iterator returned by [`ElementIteratorExt::with_ip_version`].
+pub struct WithIpVersion<I> {
+    iter: I,
+}
+
+impl<I> Iterator for WithIpVersion<I>
+where
+    I: Iterator<Item = Result<Element, BgpStreamError>>,
+{
+    type Item = Result<(IpVersion, Element), BgpStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.iter.next()? {
+                Ok(element) => {
+                    if let Some(version) = element.ip_version() {
+                        return Some(Ok((version, element)));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Iterator returned by [`ElementIteratorExt::with_matched_filters`].
+#[cfg(feature = "ffi")]
+pub struct WithMatchedFilters<I> {
+    iter: I,
+    filters: Vec<(&'static str, String)>,
+}
+
+#[cfg(feature = "ffi")]
+impl<I> Iterator for WithMatchedFilters<I>
+where
+    I: Iterator<Item = Result<Element, BgpStreamError>>,
+{
+    type Item = Result<(Element, Vec<(&'static str, String)>), BgpStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.iter.next()?.map(|element| {
+            let matched = crate::filter::satisfied_filters(&self.filters, &element);
+            (element, matched)
+        }))
+    }
+}
+
+/// Iterator returned by [`ElementIteratorExt::synthetic_withdrawals`].
+pub struct SyntheticWithdrawals<I> {
+    iter: I,
+    table: RibTable,
+    // synthetic withdrawals not yet yielded.
+    pending: VecDeque<Element>,
+}
+
+impl<I> SyntheticWithdrawals<I> {
+    /// The current routes of all peers.
+    pub fn table(&self) -> &RibTable {
+        &self.table
+    }
+
+    /// Get back the table.
+    pub fn into_table(self) -> RibTable {
+        self.table
+    }
+}
+
+impl<I> Iterator for SyntheticWithdrawals<I>
+where
+    I: Iterator<Item = Result<Element, BgpStreamError>>,
+{
+    type Item = Result<Element, BgpStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(withdrawal) = self.pending.pop_front() {
+            return Some(Ok(withdrawal));
+        }
+        let element = match self.iter.next()? {
+            Ok(element) => element,
+            Err(e) => return Some(Err(e)),
+        };
+        if let ElementType::PeerState { to, .. } = element.e {
+            if to != PeerState::Established {
+                let peer = element.peer();
+                let mut prefixes: Vec<IpNet> =
+                    self.table.peer_routes(&peer).map(|u| u.prefix).collect();
+                prefixes.sort();
+                self.pending
+                    .extend(prefixes.into_iter().map(|prefix| Element {
+                        time: element.time,
+                        peer_ip: element.peer_ip,
+                        peer_asn: element.peer_asn,
+                        e: ElementType::Withdrawal(prefix),
+                    }));
+            }
+        }
+        self.table.apply(&element);
+        Some(Ok(element))
+    }
+}
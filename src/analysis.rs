@@ -0,0 +1,825 @@
+//! Aggregators that build up state while consuming elements of a stream.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    hash::Hash,
+    net::IpAddr,
+};
+
+use ipnet::IpNet;
+#[cfg(feature = "rand")]
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use time::{Duration, OffsetDateTime};
+
+use crate::element::{AsSegment, Element, ElementType, Peer, PeerState, Update};
+#[cfg(feature = "ffi")]
+use crate::{
+    stream::{FilterInterval, Query, RecordType},
+    BgpStreamError,
+};
+
+/// How to treat routes whose origin is an AS set.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum AsSetPolicy {
+    /// Treat each member of the AS set as an origin.
+    #[default]
+    Flatten,
+    /// Ignore routes originated by an AS set.
+    Ignore,
+}
+
+/// Map from each prefix to the set of all origin AS numbers seen for it. Feed it with RIB entries
+/// or announcements using [`PrefixOriginMap::record`]. Withdrawals do not remove any origins.
+///
+/// ```no_run
+/// use routeviews::{analysis::PrefixOriginMap, stream::*};
+///
+/// # fn main() -> Result<(), routeviews::BgpStreamError> {
+/// let stream = Query::new()
+///     .collector(Collector::RouteView(RouteView::Amsix))
+///     .record_type(RecordType::RIBs)
+///     .run()?;
+///
+/// let mut map = PrefixOriginMap::default();
+/// for element in stream {
+///     map.record(&element?);
+/// }
+///
+/// for (prefix, origins) in map.moas_prefixes() {
+///     println!("{prefix} is originated by {origins:?}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct PrefixOriginMap {
+    policy: AsSetPolicy,
+    origins: HashMap<IpNet, HashSet<u32>>,
+}
+
+impl PrefixOriginMap {
+    /// Create an empty map, treating AS-set origins according to `policy`.
+    pub fn new(policy: AsSetPolicy) -> Self {
+        Self {
+            policy,
+            origins: HashMap::new(),
+        }
+    }
+
+    /// Record the origin of an element. Elements other than RIB entries and announcements, and
+    /// elements with an empty AS path are ignored.
+    pub fn record(&mut self, element: &Element) {
+        let (ElementType::RIB(update) | ElementType::Announcement(update)) = &element.e else {
+            return;
+        };
+        let origins: &[u32] = match update.origin() {
+            Some(AsSegment::Num(asn)) => std::slice::from_ref(asn),
+            Some(AsSegment::Set(set)) if self.policy == AsSetPolicy::Flatten => set,
+            _ => return,
+        };
+        self.origins
+            .entry(update.prefix)
+            .or_default()
+            .extend(origins.iter().copied());
+    }
+
+    /// Get all origins seen for `prefix`.
+    pub fn origins(&self, prefix: &IpNet) -> Option<&HashSet<u32>> {
+        self.origins.get(prefix)
+    }
+
+    /// Iterate over all prefixes and their origins.
+    pub fn iter(&self) -> impl Iterator<Item = (&IpNet, &HashSet<u32>)> {
+        self.origins.iter()
+    }
+
+    /// Iterate over all prefixes with multiple origins (MOAS).
+    pub fn moas_prefixes(&self) -> impl Iterator<Item = (&IpNet, &HashSet<u32>)> {
+        self.origins.iter().filter(|(_, origins)| origins.len() > 1)
+    }
+
+    /// Number of prefixes in the map.
+    pub fn len(&self) -> usize {
+        self.origins.len()
+    }
+
+    /// Whether the map contains no prefixes.
+    pub fn is_empty(&self) -> bool {
+        self.origins.is_empty()
+    }
+}
+
+/// Classification of an announcement by [`ChurnDetector::classify`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum ChurnKind {
+    /// The peer did not have a route for the prefix.
+    New,
+    /// The peer re-announced the prefix with identical attributes.
+    Duplicate,
+    /// The peer re-announced the prefix with different attributes (an implicit withdrawal).
+    Changed,
+}
+
+/// Detect duplicate announcements and implicit withdrawals by tracking the last route of each peer
+/// for each prefix. A peer is identified by its IP address and AS number.
+///
+/// Two routes are considered identical if all attributes of their [`Update`] are equal, i.e., the
+/// next hop, the AS path, the communities (in the same order), the origin type, the MED, and the
+/// local preference. The time of the element is not compared.
+///
+/// RIB entries update the tracked state without being classified, so a RIB dump can be used to
+/// initialize the detector before processing updates. Withdrawals remove the route of the peer,
+/// and a peer state change to anything other than `Established` removes all routes of that peer.
+#[derive(Debug, Default, Clone)]
+pub struct ChurnDetector {
+    routes: HashMap<(IpAddr, u32, IpNet), Update>,
+}
+
+impl ChurnDetector {
+    /// Create an empty detector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process an element, and classify it if it is an announcement. Returns `None` for all other
+    /// elements.
+    pub fn classify(&mut self, element: &Element) -> Option<ChurnKind> {
+        let peer = (element.peer_ip, element.peer_asn);
+        match &element.e {
+            ElementType::Announcement(update) => {
+                let key = (peer.0, peer.1, update.prefix);
+                match self.routes.get_mut(&key) {
+                    None => {
+                        self.routes.insert(key, update.clone());
+                        Some(ChurnKind::New)
+                    }
+                    Some(last) if last == update => Some(ChurnKind::Duplicate),
+                    Some(last) => {
+                        *last = update.clone();
+                        Some(ChurnKind::Changed)
+                    }
+                }
+            }
+            ElementType::RIB(update) => {
+                self.routes
+                    .insert((peer.0, peer.1, update.prefix), update.clone());
+                None
+            }
+            ElementType::Withdrawal(prefix) => {
+                self.routes.remove(&(peer.0, peer.1, *prefix));
+                None
+            }
+            ElementType::PeerState { to, .. } => {
+                if *to != PeerState::Established {
+                    self.routes.retain(|(ip, asn, _), _| (*ip, *asn) != peer);
+                }
+                None
+            }
+        }
+    }
+
+    /// The last route of a peer for `prefix`, if any.
+    pub fn route(&self, peer_ip: IpAddr, peer_asn: u32, prefix: &IpNet) -> Option<&Update> {
+        self.routes.get(&(peer_ip, peer_asn, *prefix))
+    }
+
+    /// Number of routes currently tracked, across all peers.
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Whether no routes are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+}
+
+/// The distinct values of the optional attributes `MULTI_EXIT_DISC` and `LOCAL_PREF` across a
+/// set of routes, returned by [`attribute_spread`]. `None` stands for routes without the
+/// attribute.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AttrSpread {
+    /// All distinct MED values.
+    pub meds: BTreeSet<Option<u32>>,
+    /// All distinct local preference values.
+    pub local_prefs: BTreeSet<Option<u32>>,
+    /// The number of routes compared.
+    pub routes: usize,
+}
+
+impl AttrSpread {
+    /// Whether all routes agree on both attributes (including their presence).
+    pub fn is_consistent(&self) -> bool {
+        self.meds.len() <= 1 && self.local_prefs.len() <= 1
+    }
+
+    /// Whether some routes carry the MED and others do not.
+    pub fn med_presence_mismatch(&self) -> bool {
+        self.meds.contains(&None) && self.meds.len() > 1
+    }
+
+    /// Whether some routes carry the local preference and others do not.
+    pub fn local_pref_presence_mismatch(&self) -> bool {
+        self.local_prefs.contains(&None) && self.local_prefs.len() > 1
+    }
+}
+
+/// Compare the `MULTI_EXIT_DISC` and `LOCAL_PREF` attributes of the RIB entries and announcements
+/// in `elements`, e.g., to spot inconsistent policies across the peers that announced a prefix.
+/// Withdrawals and peer state changes are ignored. The elements are not grouped in any way, so
+/// pass the elements of a single prefix (e.g., a group of
+/// [`crate::record::Record::elements_by_prefix`]).
+///
+/// ```
+/// use routeviews::{analysis::attribute_spread, element::*};
+///
+/// let peer = |asn, med| Element {
+///     time: time::OffsetDateTime::UNIX_EPOCH,
+///     peer_ip: "198.51.100.1".parse().unwrap(),
+///     peer_asn: asn,
+///     e: ElementType::Announcement(Update {
+///         med,
+///         ..Update::builder("192.0.2.0/24".parse().unwrap(), "198.51.100.1".parse().unwrap())
+///             .build()
+///     }),
+/// };
+///
+/// let spread = attribute_spread(&[peer(3356, Some(10)), peer(174, None), peer(1299, Some(10))]);
+/// assert_eq!(spread.meds.len(), 2);
+/// assert!(spread.med_presence_mismatch());
+/// assert!(!spread.local_pref_presence_mismatch());
+/// ```
+pub fn attribute_spread(elements: &[Element]) -> AttrSpread {
+    let mut spread = AttrSpread::default();
+    for update in elements.iter().filter_map(Element::as_update) {
+        spread.meds.insert(update.med);
+        spread.local_prefs.insert(update.local_pref);
+        spread.routes += 1;
+    }
+    spread
+}
+
+/// Approximate top-N counts of a key extracted from each element (e.g., the origin AS, the peer,
+/// or the prefix), computed in one pass with the space-saving algorithm (Metwally et al., 2005).
+/// At most `capacity` keys are tracked at once, so memory stays bounded on huge streams. Once all
+/// counters are in use, a new key replaces the key with the smallest count and inherits that
+/// count. Hence, counts are overestimated by at most `total / capacity`, and every key that occurs
+/// more often than that is guaranteed to be tracked.
+///
+/// Call [`TopN::clear`] to start a new window.
+///
+/// ```no_run
+/// use routeviews::{analysis::TopN, stream::*};
+///
+/// # fn main() -> Result<(), routeviews::BgpStreamError> {
+/// let stream = Query::new()
+///     .collector(Collector::RouteView(RouteView::Amsix))
+///     .record_type(RecordType::Updates)
+///     .run()?;
+///
+/// let mut top = TopN::origin_asn(10);
+/// for element in stream {
+///     top.record(&element?);
+/// }
+/// for (asn, count) in top.results() {
+///     println!("AS{asn}: {count} elements");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TopN<K, F = fn(&Element) -> Option<K>> {
+    n: usize,
+    capacity: usize,
+    key: F,
+    total: usize,
+    counts: HashMap<K, usize>,
+    // all tracked keys, grouped by their count.
+    buckets: BTreeMap<usize, HashSet<K>>,
+}
+
+impl TopN<u32> {
+    /// Count the origin AS of RIB entries and announcements. Routes originated by an AS set are
+    /// ignored.
+    pub fn origin_asn(n: usize) -> Self {
+        Self::new(n, |e| match e.as_update()?.origin()? {
+            AsSegment::Num(asn) => Some(*asn),
+            AsSegment::Set(_) => None,
+        })
+    }
+}
+
+impl TopN<Peer> {
+    /// Count the peer of all elements.
+    pub fn peers(n: usize) -> Self {
+        Self::new(n, |e| Some(e.peer()))
+    }
+}
+
+impl TopN<IpNet> {
+    /// Count the prefix of RIB entries, announcements, and withdrawals.
+    pub fn prefixes(n: usize) -> Self {
+        Self::new(n, Element::prefix)
+    }
+}
+
+impl<K: Hash + Eq + Clone, F: Fn(&Element) -> Option<K>> TopN<K, F> {
+    /// Report the `n` most frequent keys, tracking `10 * n` keys. Elements for which `key`
+    /// returns `None` are ignored.
+    pub fn new(n: usize, key: F) -> Self {
+        Self::with_capacity(n, n.saturating_mul(10), key)
+    }
+
+    /// Report the `n` most frequent keys, tracking `capacity` keys (at least `n`). A larger
+    /// capacity makes the counts more accurate.
+    pub fn with_capacity(n: usize, capacity: usize, key: F) -> Self {
+        Self {
+            n,
+            capacity: capacity.max(n),
+            key,
+            total: 0,
+            counts: HashMap::new(),
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    /// Record the key of a single element.
+    pub fn record(&mut self, element: &Element) {
+        if let Some(key) = (self.key)(element) {
+            self.record_key(key);
+        }
+    }
+
+    /// Record a key directly, without extracting it from an element.
+    pub fn record_key(&mut self, key: K) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.total += 1;
+        let count = match self.counts.get(&key) {
+            Some(&count) => {
+                self.remove_from_bucket(&key, count);
+                count
+            }
+            None if self.counts.len() < self.capacity => 0,
+            None => {
+                // replace the key with the smallest count.
+                let (&count, keys) = self.buckets.first_key_value().expect("capacity is reached");
+                let evicted = keys.iter().next().cloned().expect("buckets are non-empty");
+                self.counts.remove(&evicted);
+                self.remove_from_bucket(&evicted, count);
+                count
+            }
+        };
+        self.counts.insert(key.clone(), count + 1);
+        self.buckets.entry(count + 1).or_default().insert(key);
+    }
+
+    fn remove_from_bucket(&mut self, key: &K, count: usize) {
+        if let Some(bucket) = self.buckets.get_mut(&count) {
+            bucket.remove(key);
+            if bucket.is_empty() {
+                self.buckets.remove(&count);
+            }
+        }
+    }
+
+    /// The (at most) `n` keys with the highest counts, in descending order of their count. The
+    /// order of keys with equal counts is unspecified.
+    pub fn results(&self) -> Vec<(K, usize)> {
+        self.buckets
+            .iter()
+            .rev()
+            .flat_map(|(count, keys)| keys.iter().map(|k| (k.clone(), *count)))
+            .take(self.n)
+            .collect()
+    }
+
+    /// The number of keys recorded so far (including keys that are no longer tracked).
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Forget all counts, e.g., to start a new window.
+    pub fn clear(&mut self) {
+        self.total = 0;
+        self.counts.clear();
+        self.buckets.clear();
+    }
+}
+
+/// Histogram of durations with exponentially growing buckets. Bucket 0 counts all durations below
+/// one millisecond, and bucket `i > 0` counts durations in `[2^(i-1), 2^i)` milliseconds. Hence,
+/// the relative resolution is the same across all scales, from milliseconds to days. Negative
+/// durations are counted in bucket 0, and durations of `2^64` milliseconds or more (about 585
+/// million years) in the last bucket `64`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExpHistogram {
+    buckets: Vec<u64>,
+    count: u64,
+}
+
+impl ExpHistogram {
+    /// Create an empty histogram.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a single duration.
+    pub fn record(&mut self, d: Duration) {
+        let millis = d.whole_milliseconds().clamp(0, u64::MAX.into()) as u64;
+        let bucket = (u64::BITS - millis.leading_zeros()) as usize;
+        if self.buckets.len() <= bucket {
+            self.buckets.resize(bucket + 1, 0);
+        }
+        self.buckets[bucket] += 1;
+        self.count += 1;
+    }
+
+    /// Add all counts of `other` to `self`.
+    pub fn merge(&mut self, other: &ExpHistogram) {
+        if self.buckets.len() < other.buckets.len() {
+            self.buckets.resize(other.buckets.len(), 0);
+        }
+        for (a, b) in self.buckets.iter_mut().zip(&other.buckets) {
+            *a += b;
+        }
+        self.count += other.count;
+    }
+
+    /// Total number of durations recorded.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Iterate over all buckets up to the largest non-empty one as `(lower, upper, count)`, where
+    /// `lower` is inclusive and `upper` exclusive.
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, Duration, u64)> + '_ {
+        // The bounds of the last bucket exceed `i64` milliseconds, so compute them in `u128`.
+        let millis = |ms: u128| Duration::new((ms / 1000) as i64, (ms % 1000) as i32 * 1_000_000);
+        self.buckets.iter().enumerate().map(move |(i, count)| {
+            let lower = if i == 0 { 0 } else { 1u128 << (i - 1) };
+            let upper = 1u128 << i;
+            (millis(lower), millis(upper), *count)
+        })
+    }
+}
+
+/// Distribution of the time between consecutive updates (announcements and withdrawals) of each
+/// peer, recorded in an [`ExpHistogram`]. RIB entries and peer state changes are ignored. The gap
+/// is computed from the element timestamps in the order in which elements are recorded, so
+/// elements that arrive out of order produce a gap of zero.
+#[derive(Debug, Default, Clone)]
+pub struct InterArrival {
+    last: HashMap<Peer, OffsetDateTime>,
+    histograms: HashMap<Peer, ExpHistogram>,
+}
+
+impl InterArrival {
+    /// Create an empty aggregator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the gap between `element` and the previous update of the same peer.
+    pub fn record(&mut self, element: &Element) {
+        if !matches!(
+            element.e,
+            ElementType::Announcement(_) | ElementType::Withdrawal(_)
+        ) {
+            return;
+        }
+        let peer = element.peer();
+        if let Some(last) = self.last.insert(peer, element.time) {
+            self.histograms
+                .entry(peer)
+                .or_default()
+                .record(element.time - last);
+        }
+    }
+
+    /// The histogram of a single peer, or `None` if fewer than two updates were recorded for it.
+    pub fn histogram(&self, peer: &Peer) -> Option<&ExpHistogram> {
+        self.histograms.get(peer)
+    }
+
+    /// Iterate over the histograms of all peers.
+    pub fn iter(&self) -> impl Iterator<Item = (&Peer, &ExpHistogram)> {
+        self.histograms.iter()
+    }
+
+    /// The histogram of all gaps of all peers.
+    pub fn merged(&self) -> ExpHistogram {
+        let mut merged = ExpHistogram::new();
+        self.histograms.values().for_each(|h| merged.merge(h));
+        merged
+    }
+}
+
+/// A change of a [`RibTable`], returned by [`RibTable::apply`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RibChange {
+    /// The peer announced a route for a prefix it had no route for.
+    Added { peer: Peer, route: Update },
+    /// The peer replaced its route for a prefix with a different one.
+    Replaced {
+        peer: Peer,
+        old: Update,
+        new: Update,
+    },
+    /// The peer withdrew its route for a prefix.
+    Withdrawn { peer: Peer, route: Update },
+    /// The session of the peer went down, and all of its `routes` were removed.
+    PeerDown { peer: Peer, routes: usize },
+}
+
+/// Routing table of each peer, i.e., the current route of each peer for each prefix. Build it by
+/// applying RIB entries and updates with [`RibTable::apply`].
+///
+/// RIB entries and announcements add or replace the route of the peer, withdrawals remove it, and
+/// a peer state change to anything other than `Established` removes all routes of the peer.
+#[derive(Debug, Default, Clone)]
+pub struct RibTable {
+    routes: HashMap<Peer, HashMap<IpNet, Update>>,
+}
+
+impl RibTable {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply an element to the table, and return the change it caused. Returns `None` if the table
+    /// did not change, i.e., for an announcement identical to the current route (compared as in
+    /// [`ChurnDetector`]), or for a withdrawal of a prefix without a route.
+    pub fn apply(&mut self, element: &Element) -> Option<RibChange> {
+        let peer = element.peer();
+        match &element.e {
+            ElementType::RIB(update) | ElementType::Announcement(update) => {
+                let routes = self.routes.entry(peer).or_default();
+                match routes.insert(update.prefix, update.clone()) {
+                    None => Some(RibChange::Added {
+                        peer,
+                        route: update.clone(),
+                    }),
+                    Some(old) if old == *update => None,
+                    Some(old) => Some(RibChange::Replaced {
+                        peer,
+                        old,
+                        new: update.clone(),
+                    }),
+                }
+            }
+            ElementType::Withdrawal(prefix) => {
+                let route = self.routes.get_mut(&peer)?.remove(prefix)?;
+                Some(RibChange::Withdrawn { peer, route })
+            }
+            ElementType::PeerState { to, .. } if *to != PeerState::Established => {
+                let routes = self.routes.remove(&peer)?;
+                Some(RibChange::PeerDown {
+                    peer,
+                    routes: routes.len(),
+                })
+            }
+            ElementType::PeerState { .. } => None,
+        }
+    }
+
+    /// The route of `peer` for `prefix`.
+    pub fn route(&self, peer: &Peer, prefix: &IpNet) -> Option<&Update> {
+        self.routes.get(peer)?.get(prefix)
+    }
+
+    /// Iterate over the routes of all peers for `prefix`.
+    pub fn routes(&self, prefix: &IpNet) -> impl Iterator<Item = (&Peer, &Update)> {
+        let prefix = *prefix;
+        self.routes
+            .iter()
+            .filter_map(move |(peer, routes)| Some((peer, routes.get(&prefix)?)))
+    }
+
+    /// Iterate over all routes of `peer`.
+    pub fn peer_routes(&self, peer: &Peer) -> impl Iterator<Item = &Update> {
+        self.routes.get(peer).into_iter().flat_map(|r| r.values())
+    }
+
+    /// Iterate over all peers with at least one route.
+    pub fn peers(&self) -> impl Iterator<Item = &Peer> {
+        self.routes
+            .iter()
+            .filter(|(_, routes)| !routes.is_empty())
+            .map(|(peer, _)| peer)
+    }
+
+    /// Number of routes, across all peers.
+    pub fn len(&self) -> usize {
+        self.routes.values().map(|r| r.len()).sum()
+    }
+
+    /// Whether the table contains no routes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A [`RibTable`] that is initialized from a RIB dump and then kept up to date with updates, as
+/// one logical stream. Load the RIB with [`ContinuousRib::load`], and then follow the updates
+/// with [`ContinuousRib::follow`]. Requires the `ffi` feature.
+///
+/// ```no_run
+/// use routeviews::{analysis::ContinuousRib, stream::*};
+/// use time::{macros::datetime, Duration};
+///
+/// # fn main() -> Result<(), routeviews::BgpStreamError> {
+/// let collector = Collector::RouteView(RouteView::Amsix);
+/// let (start, stop) = collector.snap_interval(datetime!(2023-11-08 10:00 UTC));
+///
+/// let mut rib = ContinuousRib::load(Query::new().collector(collector).time_range(start..stop))?;
+/// rib.follow(
+///     Query::new()
+///         .collector(collector)
+///         .time_range(start..start + Duration::hours(1)),
+///     |table, change| println!("{change:?} ({} routes)", table.len()),
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "ffi")]
+#[derive(Debug, Clone)]
+pub struct ContinuousRib {
+    table: RibTable,
+    rib_time: OffsetDateTime,
+}
+
+#[cfg(feature = "ffi")]
+impl ContinuousRib {
+    /// Build the table from the RIB dump selected by `query`. The record type of the query is
+    /// replaced by RIBs, all other filters (including the interval) are kept. Make sure the
+    /// interval covers a single RIB dump (e.g., using [`crate::stream::Collector::snap_interval`]),
+    /// and use [`crate::stream::Query::tolerate_element_errors`] to skip malformed entries.
+    ///
+    /// Returns [`BgpStreamError::EmptyRib`] if the query yields no RIB entries (e.g., because the
+    /// interval contains no RIB dump), as the time of the RIB would be unknown.
+    pub fn load(query: &Query) -> Result<Self, BgpStreamError> {
+        let mut table = RibTable::new();
+        let mut rib_time: Option<OffsetDateTime> = None;
+        for element in query.clone().record_type(RecordType::RIBs).run()? {
+            let element = element?;
+            rib_time = Some(rib_time.map_or(element.time, |t| t.min(element.time)));
+            table.apply(&element);
+        }
+        let rib_time = rib_time.ok_or(BgpStreamError::EmptyRib)?;
+        Ok(Self { table, rib_time })
+    }
+
+    /// The current table.
+    pub fn table(&self) -> &RibTable {
+        &self.table
+    }
+
+    /// Take the current table.
+    pub fn into_table(self) -> RibTable {
+        self.table
+    }
+
+    /// The time of the RIB dump, i.e., the earliest time of all RIB entries.
+    pub fn rib_time(&self) -> OffsetDateTime {
+        self.rib_time
+    }
+
+    /// Apply the updates selected by `query` to the table, starting at the time of the RIB dump,
+    /// and call `on_change` with the updated table after each change. The record type of the
+    /// query is replaced by updates, and its interval starts at [`ContinuousRib::rib_time`]. The
+    /// end of the interval (and its live mode) is kept: an interval with a stop time ends there,
+    /// and [`FilterInterval::Since`] without live mode ends at the time of this call. With an open
+    /// interval or in live mode, this follows the live stream and never returns (unless an error
+    /// occurs or the limit is reached).
+    ///
+    /// The first update dumps overlap with the RIB dump, as collectors take several minutes to
+    /// write a RIB. Updates that are already reflected in the RIB re-announce the same route,
+    /// which does not change the table and is not reported. Updates older than the RIB are
+    /// skipped.
+    pub fn follow<F>(&mut self, query: &Query, mut on_change: F) -> Result<(), BgpStreamError>
+    where
+        F: FnMut(&RibTable, &RibChange),
+    {
+        let stop = match query.describe().interval {
+            FilterInterval::Interval { stop, .. } => stop,
+            FilterInterval::Since { live: false, .. } => Some(OffsetDateTime::now_utc()),
+            FilterInterval::Since { live: true, .. } | FilterInterval::Open => None,
+        };
+        let stream = query
+            .clone()
+            .record_type(RecordType::Updates)
+            .interval(FilterInterval::Interval {
+                start: self.rib_time,
+                stop,
+            })
+            .run()?;
+        for element in stream {
+            let element = element?;
+            if element.time < self.rib_time {
+                continue;
+            }
+            if let Some(change) = self.table.apply(&element) {
+                on_change(&self.table, &change);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Uniform random sample of fixed size over a stream of unknown length, using reservoir sampling
+/// (Algorithm R). After ingesting `k` items, each of them is part of the sample with probability
+/// `n / k` (or 1 if `k <= n`), while only `n` items are kept in memory. Requires the `rand`
+/// feature.
+///
+/// Use [`ReservoirSampler::seeded`] for reproducible samples.
+///
+/// ```no_run
+/// use routeviews::{analysis::ReservoirSampler, stream::*};
+///
+/// # fn main() -> Result<(), routeviews::BgpStreamError> {
+/// let stream = Query::new()
+///     .collector(Collector::RouteView(RouteView::Amsix))
+///     .record_type(RecordType::RIBs)
+///     .run()?;
+///
+/// let mut sampler = ReservoirSampler::seeded(1000, 42);
+/// for element in stream {
+///     sampler.ingest(element?);
+/// }
+/// let sample = sampler.into_sample();
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "rand")]
+#[derive(Debug, Clone)]
+pub struct ReservoirSampler<T = Element, R = StdRng> {
+    size: usize,
+    seen: usize,
+    sample: Vec<T>,
+    rng: R,
+}
+
+#[cfg(feature = "rand")]
+impl<T> ReservoirSampler<T, StdRng> {
+    /// Create a sampler retaining `size` items, seeded from the operating system.
+    pub fn new(size: usize) -> Self {
+        Self::with_rng(size, StdRng::from_os_rng())
+    }
+
+    /// Create a sampler retaining `size` items, seeded with `seed`. Two samplers with the same
+    /// seed that ingest the same items produce the same sample.
+    pub fn seeded(size: usize, seed: u64) -> Self {
+        Self::with_rng(size, StdRng::seed_from_u64(seed))
+    }
+}
+
+#[cfg(feature = "rand")]
+impl<T, R: Rng> ReservoirSampler<T, R> {
+    /// Create a sampler retaining `size` items, using the random number generator `rng`.
+    pub fn with_rng(size: usize, rng: R) -> Self {
+        Self {
+            size,
+            seen: 0,
+            sample: Vec::with_capacity(size),
+            rng,
+        }
+    }
+
+    /// Ingest a single item.
+    pub fn ingest(&mut self, item: T) {
+        if self.sample.len() < self.size {
+            self.sample.push(item);
+        } else {
+            let i = self.rng.random_range(0..=self.seen);
+            if i < self.size {
+                self.sample[i] = item;
+            }
+        }
+        self.seen += 1;
+    }
+
+    /// Ingest all items of `iter`.
+    pub fn ingest_all(&mut self, iter: impl IntoIterator<Item = T>) {
+        iter.into_iter().for_each(|item| self.ingest(item));
+    }
+
+    /// The number of items ingested so far.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    /// The current sample, in no particular order.
+    pub fn sample(&self) -> &[T] {
+        &self.sample
+    }
+
+    /// Return the sample, in no particular order. If fewer than `size` items were ingested, the
+    /// sample contains all of them.
+    pub fn into_sample(self) -> Vec<T> {
+        self.sample
+    }
+}
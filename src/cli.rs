@@ -0,0 +1,120 @@
+//! Command-line arguments for building a [`Query`], see [`QueryArgs`]. Requires the `clap`
+//! feature.
+
+use clap::Args;
+use ipnet::IpNet;
+
+use crate::{
+    element::Community,
+    stream::{PrefixMatchType, Project, Query, RecordType},
+    BgpStreamError,
+};
+
+/// The common arguments of a BGP command-line tool, to be flattened into the arguments of your own
+/// tool. Turn them into a [`Query`] with [`QueryArgs::into_query`] (or [`Query::from_args`]).
+///
+/// All values except the times are parsed by clap (using the `FromStr` implementations of this
+/// crate), so invalid values are reported like any other usage error. Arguments that can be given
+/// multiple times are combined like the corresponding [`Query`] methods, i.e., multiple values of
+/// the same argument are combined with a logical OR.
+///
+/// ```
+/// use clap::Parser;
+/// use routeviews::cli::QueryArgs;
+///
+/// #[derive(Parser)]
+/// struct Cli {
+///     #[command(flatten)]
+///     query: QueryArgs,
+/// }
+///
+/// # fn main() -> Result<(), routeviews::BgpStreamError> {
+/// let cli = Cli::parse_from([
+///     "bgp-tool",
+///     "--collector", "rrc0*",
+///     "--record-type", "updates",
+///     "--start", "2023-11-08 09:55",
+///     "--stop", "2023-11-08 10:05",
+///     "--origin-asn", "3356",
+/// ]);
+/// let query = cli.query.into_query()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Args)]
+pub struct QueryArgs {
+    /// Collector to read from (e.g., `route-views.amsix` or `rrc00`). A `*` matches any sequence
+    /// of characters (e.g., `rrc*`). Can be given multiple times.
+    #[arg(short, long = "collector", value_name = "NAME")]
+    pub collectors: Vec<String>,
+    /// Project to read from (`routeviews` or `ris`). Can be given multiple times.
+    #[arg(short, long = "project", value_name = "PROJECT")]
+    pub projects: Vec<Project>,
+    /// Only read records of this type (`updates` or `ribs`).
+    #[arg(short = 't', long, value_name = "TYPE")]
+    pub record_type: Option<RecordType>,
+    /// Start of the interval, as RFC 3339, `YYYY-MM-DD HH:MM[:SS]` (UTC), `YYYY-MM-DD`, or a Unix
+    /// timestamp.
+    #[arg(short, long, value_name = "TIME")]
+    pub start: Option<String>,
+    /// End of the interval, in the same formats as `--start`. Without it, the stream keeps waiting
+    /// for new data (live mode).
+    #[arg(short = 'e', long, value_name = "TIME", requires = "start")]
+    pub stop: Option<String>,
+    /// Only keep elements of this prefix (see `--prefix-match`). Can be given multiple times.
+    #[arg(long = "prefix", value_name = "PREFIX")]
+    pub prefixes: Vec<IpNet>,
+    /// How to match `--prefix` (`any`, `exact`, `less`, or `more`).
+    #[arg(long, value_name = "KIND", default_value = "any")]
+    pub prefix_match: PrefixMatchType,
+    /// Only keep elements from this peer AS. Can be given multiple times.
+    #[arg(long = "peer-asn", value_name = "ASN")]
+    pub peer_asns: Vec<u32>,
+    /// Only keep elements originated by this AS. Can be given multiple times.
+    #[arg(long = "origin-asn", value_name = "ASN")]
+    pub origin_asns: Vec<u32>,
+    /// Only keep elements with this community (`asn:value`). Can be given multiple times.
+    #[arg(long = "community", value_name = "COMMUNITY")]
+    pub communities: Vec<Community>,
+}
+
+impl QueryArgs {
+    /// Build the query selected by the arguments. Returns [`BgpStreamError::Parse`] if a
+    /// collector pattern matches no known collector (see [`Query::collector_pattern`]), or if a
+    /// time cannot be parsed (see [`crate::stream::parse_time`]).
+    pub fn into_query(self) -> Result<Query, BgpStreamError> {
+        let mut query = Query::new();
+        for pattern in &self.collectors {
+            query.collector_pattern(pattern)?;
+        }
+        for project in self.projects {
+            query.add_project(project);
+        }
+        if let Some(record_type) = self.record_type {
+            query.record_type(record_type);
+        }
+        if let Some(start) = &self.start {
+            query.interval_parse(start, self.stop.as_deref())?;
+        }
+        for prefix in self.prefixes {
+            query.prefix(self.prefix_match, prefix.to_string());
+        }
+        for asn in self.peer_asns {
+            query.peer_asn(asn.to_string());
+        }
+        for asn in self.origin_asns {
+            query.origin_asn(asn.to_string());
+        }
+        for community in self.communities {
+            query.community(community.to_string());
+        }
+        Ok(query)
+    }
+}
+
+impl Query {
+    /// Build a query from command-line arguments. Shortcut for [`QueryArgs::into_query`].
+    pub fn from_args(args: QueryArgs) -> Result<Self, BgpStreamError> {
+        args.into_query()
+    }
+}
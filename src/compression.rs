@@ -0,0 +1,51 @@
+//! Transparent decompression of local MRT files.
+//!
+//! libbgpstream may or may not decompress local files by itself, depending on how it was built.
+//! Therefore, compressed files are decompressed into a temporary file before passing them to the
+//! `singlefile` data interface. The following formats are supported, detected by their magic bytes
+//! (not the file extension):
+//!
+//! - gzip (`.gz`), starting with `1f 8b`.
+//! - bzip2 (`.bz2`), starting with `BZh`.
+//!
+//! All other files are passed to libbgpstream unchanged.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+
+use crate::create_temp_file;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+
+/// Decompress the file at `path` into a temporary file, and return the path to that temporary
+/// file. If the file is not compressed, return `None`. The caller is responsible for removing the
+/// temporary file.
+pub(crate) fn decompress(path: &Path) -> io::Result<Option<PathBuf>> {
+    let mut file = File::open(path)?;
+    let mut magic = Vec::with_capacity(3);
+    (&mut file).take(3).read_to_end(&mut magic)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    let mut reader: Box<dyn Read> = if magic.starts_with(GZIP_MAGIC) {
+        Box::new(MultiGzDecoder::new(file))
+    } else if magic.starts_with(BZIP2_MAGIC) {
+        Box::new(MultiBzDecoder::new(file))
+    } else {
+        return Ok(None);
+    };
+
+    let (tmp, mut out) = create_temp_file()?;
+    let result = io::copy(&mut reader, &mut out);
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    Ok(Some(tmp))
+}
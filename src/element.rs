@@ -1,41 +1,42 @@
 use std::{
+    collections::HashMap,
     fmt::Display,
-    net::IpAddr,
-    ptr::{addr_of, null_mut},
-    time::Duration,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
 };
 
-use ipnet::IpNet;
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
 use itertools::Itertools;
-use libbgpstream_sys::{
-    bgpstream_as_path_get_next_seg, bgpstream_as_path_iter, bgpstream_as_path_iter_reset,
-    bgpstream_as_path_seg_t,
-    bgpstream_as_path_seg_type_t_BGPSTREAM_AS_PATH_SEG_ASN as AS_PATH_SEG_ASN,
-    bgpstream_community_set_get,
-    bgpstream_elem_origin_type_t_BGPSTREAM_ELEM_BGP_UPDATE_ORIGIN_EGP as ORIGIN_EGP,
-    bgpstream_elem_origin_type_t_BGPSTREAM_ELEM_BGP_UPDATE_ORIGIN_IGP as ORIGIN_IGP,
-    bgpstream_elem_origin_type_t_BGPSTREAM_ELEM_BGP_UPDATE_ORIGIN_INCOMPLETE as ORIGIN_INCOMPLETE,
-    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_ACTIVE as ELEM_PEERSTATE_ACTIVE,
-    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_CLEARING as ELEM_PEERSTATE_CLEARING,
-    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_CONNECT as ELEM_PEERSTATE_CONNECT,
-    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_DELETED as ELEM_PEERSTATE_DELETED,
-    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_ESTABLISHED as ELEM_PEERSTATE_ESTABLISHED,
-    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_IDLE as ELEM_PEERSTATE_IDLE,
-    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_OPENCONFIRM as ELEM_PEERSTATE_OPENCONFIRM,
-    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_OPENSENT as ELEM_PEERSTATE_OPENSENT,
-    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_UNKNOWN as ELEM_PEERSTATE_UNKNOWN,
-    bgpstream_elem_t,
-    bgpstream_elem_type_t_BGPSTREAM_ELEM_TYPE_ANNOUNCEMENT as ELEM_TYPE_ANNOUNCEMENT,
-    bgpstream_elem_type_t_BGPSTREAM_ELEM_TYPE_PEERSTATE as ELEM_TYPE_PEERSTATE,
-    bgpstream_elem_type_t_BGPSTREAM_ELEM_TYPE_RIB as ELEM_TYPE_RIB,
-    bgpstream_elem_type_t_BGPSTREAM_ELEM_TYPE_WITHDRAWAL as ELEM_TYPE_WITHDRAWAL,
-    bgpstream_record_get_next_elem,
-};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
-use crate::{parse_bgpstream_ip, parse_bgpstream_prefix, record::Record, BgpStreamError};
+use crate::ParseError;
+
+/// Options controlling which fields of an [`Update`] are extracted from libbgpstream. Extracting
+/// the AS path and the communities requires allocating and iterating over each of them, which
+/// dominates the parsing cost for large RIB dumps. Skipped fields are left empty. By default, all
+/// fields are extracted.
+///
+/// Client-side filters that inspect skipped fields (e.g.,
+/// [`crate::stream::Query::origin_asn_range`] without `as_path`) see the empty field, and will drop
+/// the elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    /// Extract [`Update::as_path`].
+    pub as_path: bool,
+    /// Extract [`Update::communities`].
+    pub communities: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            as_path: true,
+            communities: true,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -46,87 +47,129 @@ pub struct Element {
     pub e: ElementType,
 }
 
+/// The address family of a prefix. Also available as [`crate::stream::IpVersion`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum IpVersion {
+    IPv4,
+    IPv6,
+}
+
+impl IpVersion {
+    /// The canonical name as used by libbgpstream (`4` or `6`). Parsing also accepts `ipv4`/`ipv6`
+    /// (case-insensitive).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IpVersion::IPv4 => "4",
+            IpVersion::IPv6 => "6",
+        }
+    }
+}
+
+impl Display for IpVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for IpVersion {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "4" | "ipv4" | "v4" => Ok(IpVersion::IPv4),
+            "6" | "ipv6" | "v6" => Ok(IpVersion::IPv6),
+            _ => Err(ParseError::new("IP version", s)),
+        }
+    }
+}
+
+/// Constructors to fabricate elements in tests, without libbgpstream. Requires the `testing`
+/// feature.
+///
+/// ```
+/// # use routeviews::element::*;
+/// use time::macros::datetime;
+///
+/// let peer = Peer { ip: "198.51.100.1".parse().unwrap(), asn: 3356 };
+/// let time = datetime!(2023-11-08 10:00 UTC);
+/// let prefix = "192.0.2.0/24".parse().unwrap();
+/// let update = Update::builder(prefix, peer.ip).as_path([AsSegment::Num(3356)]).build();
+///
+/// let elements = [
+///     Element::test_announcement(time, peer, update),
+///     Element::test_withdrawal(time, peer, prefix),
+///     Element::test_peer_state(time, peer, PeerState::Established, PeerState::Idle),
+/// ];
+/// assert!(elements.iter().all(|e| e.peer() == peer));
+/// ```
+#[cfg(feature = "testing")]
 impl Element {
-    pub(crate) fn new(record: &mut Record<'_>) -> Result<Option<Element>, BgpStreamError> {
-        unsafe {
-            let mut p_elem = null_mut::<bgpstream_elem_t>();
-            let p_p_elem: *mut *mut bgpstream_elem_t = &mut p_elem;
-            let res = bgpstream_record_get_next_elem(record.p_record, p_p_elem);
-
-            match res {
-                1 => {}
-                0 => return Ok(None),
-                _ => return Err(BgpStreamError::GetNextElement),
-            }
+    /// Create an announcement of `update`, received from `peer` at `time`.
+    pub fn test_announcement(time: OffsetDateTime, peer: Peer, update: Update) -> Element {
+        Self::test_element(time, peer, ElementType::Announcement(update))
+    }
 
-            // check that p_record is non-null
-            if p_elem.is_null() {
-                return Err(BgpStreamError::GetNextElementNull);
-            };
+    /// Create a RIB entry of `update`, received from `peer` at `time`.
+    pub fn test_rib(time: OffsetDateTime, peer: Peer, update: Update) -> Element {
+        Self::test_element(time, peer, ElementType::RIB(update))
+    }
 
-            let elem = &*p_elem;
+    /// Create a withdrawal of `prefix`, received from `peer` at `time`.
+    pub fn test_withdrawal(time: OffsetDateTime, peer: Peer, prefix: IpNet) -> Element {
+        Self::test_element(time, peer, ElementType::Withdrawal(prefix))
+    }
 
-            let time = if elem.orig_time_sec == 0 {
-                record.time
-            } else {
-                let secs = elem.orig_time_sec;
-                let micros = elem.orig_time_usec;
-                OffsetDateTime::from_unix_timestamp(secs as i64)?
-                    + Duration::from_micros(micros as u64)
-            };
+    /// Create a state change of the session with `peer` at `time`.
+    pub fn test_peer_state(
+        time: OffsetDateTime,
+        peer: Peer,
+        from: PeerState,
+        to: PeerState,
+    ) -> Element {
+        Self::test_element(time, peer, ElementType::PeerState { from, to })
+    }
 
-            let peer_ip = parse_bgpstream_ip(elem.peer_ip)?;
-            let peer_asn = elem.peer_asn;
-
-            let e = match elem.type_ {
-                ELEM_TYPE_ANNOUNCEMENT | ELEM_TYPE_RIB => {
-                    let update = Update {
-                        prefix: parse_bgpstream_prefix(elem.prefix)?,
-                        next_hop: parse_bgpstream_ip(elem.nexthop)?,
-                        as_path: extract_as_path(p_elem),
-                        communities: extract_communities(p_elem),
-                        origin_type: if elem.has_origin != 0 {
-                            Some(elem.origin.try_into()?)
-                        } else {
-                            None
-                        },
-                        med: if elem.has_med != 0 {
-                            Some(elem.med)
-                        } else {
-                            None
-                        },
-                        local_pref: if elem.has_local_pref != 0 {
-                            Some(elem.local_pref)
-                        } else {
-                            None
-                        },
-                    };
-
-                    if elem.type_ == ELEM_TYPE_ANNOUNCEMENT {
-                        ElementType::Announcement(update)
-                    } else {
-                        ElementType::RIB(update)
-                    }
-                }
-                ELEM_TYPE_PEERSTATE => ElementType::PeerState {
-                    from: elem.old_state.try_into()?,
-                    to: elem.new_state.try_into()?,
-                },
-                ELEM_TYPE_WITHDRAWAL => {
-                    ElementType::Withdrawal(parse_bgpstream_prefix(elem.prefix)?)
-                }
-                _ => return Err(BgpStreamError::UnknownElementType),
-            };
+    fn test_element(time: OffsetDateTime, peer: Peer, e: ElementType) -> Element {
+        Element {
+            time,
+            peer_ip: peer.ip,
+            peer_asn: peer.asn,
+            e,
+        }
+    }
+}
 
-            Ok(Some(Element {
-                time,
-                peer_ip,
-                peer_asn,
-                e,
-            }))
+/// A BGP peer of a collector, identified by its IP address and AS number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Peer {
+    pub ip: IpAddr,
+    pub asn: u32,
+}
+
+impl Display for Peer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AS{} ({})", self.asn, self.ip)
+    }
+}
+
+impl Element {
+    /// The peer from which the collector received the element.
+    pub fn peer(&self) -> Peer {
+        Peer {
+            ip: self.peer_ip,
+            asn: self.peer_asn,
         }
     }
 
+    /// Check whether the IP address of the peer lies within `net`. An address never lies within a
+    /// network of the other address family (e.g., an IPv4-mapped IPv6 address is not contained in
+    /// an IPv4 network).
+    pub fn peer_in(&self, net: IpNet) -> bool {
+        net.contains(&self.peer_ip)
+    }
+
     pub fn prefix(&self) -> Option<IpNet> {
         match &self.e {
             ElementType::RIB(u) | ElementType::Announcement(u) => Some(u.prefix),
@@ -134,6 +177,48 @@ impl Element {
             ElementType::PeerState { .. } => None,
         }
     }
+
+    /// The address family of the prefix, or `None` for peer state changes.
+    pub fn ip_version(&self) -> Option<IpVersion> {
+        match self.prefix()? {
+            IpNet::V4(_) => Some(IpVersion::IPv4),
+            IpNet::V6(_) => Some(IpVersion::IPv6),
+        }
+    }
+
+    /// Whether the element has an IPv4 prefix. This is `false` for peer state changes.
+    pub fn is_ipv4(&self) -> bool {
+        self.ip_version() == Some(IpVersion::IPv4)
+    }
+
+    /// Whether the element has an IPv6 prefix. This is `false` for peer state changes.
+    pub fn is_ipv6(&self) -> bool {
+        self.ip_version() == Some(IpVersion::IPv6)
+    }
+
+    /// Get the update of a RIB entry or an announcement, and `None` otherwise.
+    pub fn as_update(&self) -> Option<&Update> {
+        match &self.e {
+            ElementType::RIB(u) | ElementType::Announcement(u) => Some(u),
+            ElementType::Withdrawal(_) | ElementType::PeerState { .. } => None,
+        }
+    }
+
+    /// Take the update of a RIB entry or an announcement, and `None` otherwise.
+    pub fn into_update(self) -> Option<Update> {
+        match self.e {
+            ElementType::RIB(u) | ElementType::Announcement(u) => Some(u),
+            ElementType::Withdrawal(_) | ElementType::PeerState { .. } => None,
+        }
+    }
+
+    /// Take the withdrawn prefix of a withdrawal, and `None` otherwise.
+    pub fn into_withdrawal(self) -> Option<IpNet> {
+        match self.e {
+            ElementType::Withdrawal(p) => Some(p),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -143,6 +228,157 @@ pub enum AsSegment {
     Set(Vec<u32>),
 }
 
+/// Whether `asn` is reserved for private use (RFC 6996): `64512..=65534` and
+/// `4200000000..=4294967294`.
+pub fn is_private_asn(asn: u32) -> bool {
+    matches!(asn, 64512..=65534 | 4_200_000_000..=4_294_967_294)
+}
+
+/// Whether `asn` is reserved and must not appear in the global routing table (private AS numbers
+/// are not included, see [`is_private_asn`]):
+///
+/// | AS numbers                | Reason                              |
+/// |---------------------------|-------------------------------------|
+/// | `0`                       | Reserved (RFC 7607)                 |
+/// | `23456`                   | `AS_TRANS` (RFC 6793)               |
+/// | `64496..=64511`           | Documentation (RFC 5398)            |
+/// | `65535`                   | Last 2-byte AS number (RFC 7300)    |
+/// | `65536..=65551`           | Documentation (RFC 5398)            |
+/// | `65552..=131071`          | Reserved by IANA                    |
+/// | `4294967295`              | Last 4-byte AS number (RFC 7300)    |
+pub fn is_reserved_asn(asn: u32) -> bool {
+    matches!(
+        asn,
+        0 | AS_TRANS | 64496..=64511 | 65535 | 65536..=65551 | 65552..=131071 | u32::MAX
+    )
+}
+
+/// Reserved IPv4 prefixes, see [`is_bogon_prefix`].
+const IPV4_BOGONS: &[(Ipv4Addr, u8)] = &[
+    (Ipv4Addr::new(0, 0, 0, 0), 8),
+    (Ipv4Addr::new(10, 0, 0, 0), 8),
+    (Ipv4Addr::new(100, 64, 0, 0), 10),
+    (Ipv4Addr::new(127, 0, 0, 0), 8),
+    (Ipv4Addr::new(169, 254, 0, 0), 16),
+    (Ipv4Addr::new(172, 16, 0, 0), 12),
+    (Ipv4Addr::new(192, 0, 0, 0), 24),
+    (Ipv4Addr::new(192, 0, 2, 0), 24),
+    (Ipv4Addr::new(192, 168, 0, 0), 16),
+    (Ipv4Addr::new(198, 18, 0, 0), 15),
+    (Ipv4Addr::new(198, 51, 100, 0), 24),
+    (Ipv4Addr::new(203, 0, 113, 0), 24),
+    (Ipv4Addr::new(224, 0, 0, 0), 4),
+    (Ipv4Addr::new(240, 0, 0, 0), 4),
+];
+
+/// Reserved IPv6 prefixes within `2000::/3`, see [`is_bogon_prefix`].
+const IPV6_BOGONS: &[(Ipv6Addr, u8)] = &[
+    (Ipv6Addr::new(0x2001, 0x2, 0, 0, 0, 0, 0, 0), 48),
+    (Ipv6Addr::new(0x2001, 0x10, 0, 0, 0, 0, 0, 0), 28),
+    (Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32),
+    (Ipv6Addr::new(0x2002, 0, 0, 0, 0, 0, 0, 0), 16),
+    (Ipv6Addr::new(0x3fff, 0, 0, 0, 0, 0, 0, 0), 20),
+];
+
+/// Whether `prefix` lies within reserved address space that must not appear in the global routing
+/// table. This is the case if the prefix is equal to or more specific than any of the following
+/// prefixes:
+///
+/// | IPv4 prefix        | Reason                                |
+/// |--------------------|---------------------------------------|
+/// | `0.0.0.0/8`        | "This network" (RFC 791)              |
+/// | `10.0.0.0/8`       | Private use (RFC 1918)                |
+/// | `100.64.0.0/10`    | Shared address space (RFC 6598)       |
+/// | `127.0.0.0/8`      | Loopback (RFC 1122)                   |
+/// | `169.254.0.0/16`   | Link local (RFC 3927)                 |
+/// | `172.16.0.0/12`    | Private use (RFC 1918)                |
+/// | `192.0.0.0/24`     | IETF protocol assignments (RFC 6890)  |
+/// | `192.0.2.0/24`     | Documentation (RFC 5737)              |
+/// | `192.168.0.0/16`   | Private use (RFC 1918)                |
+/// | `198.18.0.0/15`    | Benchmarking (RFC 2544)               |
+/// | `198.51.100.0/24`  | Documentation (RFC 5737)              |
+/// | `203.0.113.0/24`   | Documentation (RFC 5737)              |
+/// | `224.0.0.0/4`      | Multicast (RFC 5771)                  |
+/// | `240.0.0.0/4`      | Reserved (RFC 1112)                   |
+///
+/// | IPv6 prefix        | Reason                                |
+/// |--------------------|---------------------------------------|
+/// | outside `2000::/3` | Not global unicast (RFC 4291)         |
+/// | `2001:2::/48`      | Benchmarking (RFC 5180)               |
+/// | `2001:10::/28`     | ORCHID (RFC 4843)                     |
+/// | `2001:db8::/32`    | Documentation (RFC 3849)              |
+/// | `2002::/16`        | 6to4 (RFC 3056)                       |
+/// | `3fff::/20`        | Documentation (RFC 9637)              |
+///
+/// Everything outside of `2000::/3` includes unique local addresses (`fc00::/7`), link local
+/// addresses (`fe80::/10`), and multicast (`ff00::/8`). Less specific prefixes that cover
+/// reserved space (e.g., `0.0.0.0/0` or `::/0`) are not bogons. Note that
+/// this table contains only reserved space, not address space that is merely unallocated.
+pub fn is_bogon_prefix(prefix: &IpNet) -> bool {
+    match prefix {
+        IpNet::V4(p) => IPV4_BOGONS.iter().any(|(addr, len)| {
+            p.prefix_len() >= *len
+                && Ipv4Net::new(*addr, *len).is_ok_and(|bogon| bogon.contains(&p.network()))
+        }),
+        IpNet::V6(p) => {
+            let global_unicast = Ipv6Net::new(Ipv6Addr::new(0x2000, 0, 0, 0, 0, 0, 0, 0), 3)
+                .expect("valid prefix length");
+            (p.prefix_len() >= 3 && !global_unicast.contains(&p.network()))
+                || IPV6_BOGONS.iter().any(|(addr, len)| {
+                    p.prefix_len() >= *len
+                        && Ipv6Net::new(*addr, *len).is_ok_and(|bogon| bogon.contains(&p.network()))
+                })
+        }
+    }
+}
+
+impl AsSegment {
+    /// Whether the segment contains an AS number reserved for private use (see
+    /// [`is_private_asn`]). An AS set is private if any of its members is private.
+    pub fn is_private(&self) -> bool {
+        match self {
+            AsSegment::Num(asn) => is_private_asn(*asn),
+            AsSegment::Set(set) => set.iter().any(|asn| is_private_asn(*asn)),
+        }
+    }
+
+    /// Whether the segment contains a reserved AS number (see [`is_reserved_asn`]). An AS set is
+    /// reserved if any of its members is reserved.
+    pub fn is_reserved(&self) -> bool {
+        match self {
+            AsSegment::Num(asn) => is_reserved_asn(*asn),
+            AsSegment::Set(set) => set.iter().any(|asn| is_reserved_asn(*asn)),
+        }
+    }
+
+    /// Get the canonical form of the segment: the members of an AS set are sorted and
+    /// de-duplicated, and single AS numbers are left untouched. An AS set is unordered, and
+    /// contains each AS at most once (RFC 4271, Section 5.1.2), so the canonical form is
+    /// semantically equivalent. Compare or hash canonical segments to treat `[3, 2, 1, 1]` and
+    /// `[1, 2, 3]` as equal.
+    ///
+    /// An AS set with a single member remains an AS set, as it is not equivalent to a single AS
+    /// number (e.g., for the length of the AS path). Canonicalization is not applied when parsing,
+    /// so segments keep the order in which they were received.
+    ///
+    /// ```
+    /// # use routeviews::element::AsSegment;
+    /// let set = AsSegment::Set(vec![3, 2, 1, 1]);
+    /// assert_eq!(set.canonicalize(), AsSegment::Set(vec![1, 2, 3]));
+    /// ```
+    pub fn canonicalize(&self) -> AsSegment {
+        match self {
+            AsSegment::Num(asn) => AsSegment::Num(*asn),
+            AsSegment::Set(set) => {
+                let mut set = set.clone();
+                set.sort_unstable();
+                set.dedup();
+                AsSegment::Set(set)
+            }
+        }
+    }
+}
+
 impl Display for AsSegment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -152,6 +388,46 @@ impl Display for AsSegment {
     }
 }
 
+/// Parse an AS path from a string. AS numbers are separated by whitespace, and AS sets are enclosed
+/// in curly braces with their members separated by commas (e.g., `3356 174 {64512,64513}`). AS sets
+/// in square brackets (as written by the `Display` implementation of [`AsSegment`]) are accepted as
+/// well.
+pub fn parse_as_path(s: &str) -> Result<Vec<AsSegment>, ParseError> {
+    let parse_asn = |x: &str| {
+        x.parse::<u32>()
+            .map_err(|_| ParseError::new("AS number", x))
+    };
+
+    let mut path = Vec::new();
+    let mut rest = s.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c == '{' || c == '[' {
+            let close = if c == '{' { '}' } else { ']' };
+            let Some(end) = rest.find(close) else {
+                return Err(ParseError::new("AS path (unterminated AS set)", s));
+            };
+            let set = rest[1..end]
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|x| !x.is_empty())
+                .map(parse_asn)
+                .collect::<Result<Vec<u32>, _>>()?;
+            if set.is_empty() {
+                return Err(ParseError::new("AS path (empty AS set)", s));
+            }
+            path.push(AsSegment::Set(set));
+            rest = rest[end + 1..].trim_start();
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '{' || c == '[')
+                .unwrap_or(rest.len());
+            path.push(AsSegment::Num(parse_asn(&rest[..end])?));
+            rest = rest[end..].trim_start();
+        }
+    }
+
+    Ok(path)
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ElementType {
@@ -161,10 +437,28 @@ pub enum ElementType {
     PeerState { from: PeerState, to: PeerState },
 }
 
-#[derive(Debug, Clone)]
+/// The 2-byte placeholder `AS_TRANS` (RFC 6793), used by 2-byte speakers in place of any AS number
+/// that does not fit into 2 bytes.
+pub const AS_TRANS: u32 = 23456;
+
+/// A route announcement or a RIB entry.
+///
+/// # 4-byte AS numbers
+/// When a route traverses a 2-byte speaker, 4-byte AS numbers in `AS_PATH` are replaced with
+/// [`AS_TRANS`], and the true path is carried in the `AS4_PATH` attribute. libbgpstream only
+/// exposes a single AS path per element, which its MRT parser merges from `AS_PATH` and `AS4_PATH`
+/// (following RFC 6793, Section 4.2.3). This crate does not modify that path. Hence, `as_path`
+/// usually contains the true 4-byte AS numbers. However, `AS_TRANS` can still appear if the
+/// `AS4_PATH` attribute was missing or malformed (e.g., dropped by a misbehaving router). Since the
+/// original `AS4_PATH` is not available, such paths cannot be repaired. Use
+/// [`Update::contains_as_trans`] to detect them, or [`crate::stream::Query::skip_as_trans`] to drop
+/// them from the stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Update {
     pub prefix: IpNet,
+    /// The next hop of the route. For IPv6 link-local next hops, the scope is not known (see
+    /// [`Update::next_hop_in`]).
     pub next_hop: IpAddr,
     pub as_path: Vec<AsSegment>,
     pub communities: Vec<(u16, u16)>,
@@ -173,6 +467,396 @@ pub struct Update {
     pub local_pref: Option<u32>,
 }
 
+impl Update {
+    /// Start building an update for `prefix` via `next_hop`, e.g., to fabricate updates in tests.
+    /// All other fields start out empty (no AS path, no communities, and no optional attributes).
+    ///
+    /// ```
+    /// use routeviews::element::{parse_as_path, OriginType, Update};
+    ///
+    /// let prefix = "192.0.2.0/24".parse().unwrap();
+    /// let next_hop = "198.51.100.1".parse().unwrap();
+    /// let update = Update::builder(prefix, next_hop)
+    ///     .as_path(parse_as_path("3356 174 64512").unwrap())
+    ///     .community(3356, 2)
+    ///     .origin_type(OriginType::Igp)
+    ///     .med(10)
+    ///     .build();
+    /// assert_eq!(update.as_path_len(), 3);
+    /// assert_eq!(update.local_pref, None);
+    /// ```
+    pub fn builder(prefix: IpNet, next_hop: IpAddr) -> UpdateBuilder {
+        UpdateBuilder {
+            update: Update {
+                prefix,
+                next_hop,
+                as_path: Vec::new(),
+                communities: Vec::new(),
+                origin_type: None,
+                med: None,
+                local_pref: None,
+            },
+        }
+    }
+
+    /// Check whether the next hop lies within `net`, following the same rules as
+    /// [`Element::peer_in`].
+    ///
+    /// libbgpstream does not report the scope (zone) of IPv6 link-local next hops, so link-local
+    /// next hops learned over different interfaces compare equal. Only match link-local next hops
+    /// against `fe80::/10` in combination with the peer that announced them.
+    pub fn next_hop_in(&self, net: IpNet) -> bool {
+        net.contains(&self.next_hop)
+    }
+
+    /// The origin of the route, i.e., the last segment of the AS path. This is either a single AS
+    /// number, or an AS set (e.g., for aggregated routes).
+    pub fn origin(&self) -> Option<&AsSegment> {
+        self.as_path.last()
+    }
+
+    /// The neighbor of the route, i.e., the first AS number in the AS path. This is the AS
+    /// adjacent to the peer, usually the peer itself (compare with [`Element::peer_asn`]), and the
+    /// counterpart of [`Update::origin`].
+    ///
+    /// - Leading AS sets are skipped. libbgpstream reports the segments of a BGP confederation
+    ///   (`AS_CONFED_SEQUENCE` and `AS_CONFED_SET`) as [`AsSegment::Set`], so a path that starts
+    ///   with a set was received from within the peer's confederation (an aggregating AS prepends
+    ///   its own number before the `AS_SET`). The result is the first AS outside the
+    ///   confederation.
+    /// - If the path consists only of sets (or is empty), the neighbor is unknown and the result
+    ///   is `None`.
+    /// - Route servers usually do not prepend their own AS number, so for routes learned from a
+    ///   route server, the result is the route server's client rather than the route server.
+    pub fn neighbor_asn(&self) -> Option<u32> {
+        self.as_path.iter().find_map(|seg| match seg {
+            AsSegment::Num(asn) => Some(*asn),
+            AsSegment::Set(_) => None,
+        })
+    }
+
+    /// The length of the AS path, where each AS set counts as a single hop (as in the BGP decision
+    /// process, see RFC 4271, Section 9.1.2.2).
+    pub fn as_path_len(&self) -> usize {
+        self.as_path.len()
+    }
+
+    /// Whether the AS path contains [`AS_TRANS`], i.e., a 4-byte AS number that could not be
+    /// reconstructed. See the documentation of [`Update`].
+    pub fn contains_as_trans(&self) -> bool {
+        self.as_path.iter().any(|seg| match seg {
+            AsSegment::Num(asn) => *asn == AS_TRANS,
+            AsSegment::Set(set) => set.contains(&AS_TRANS),
+        })
+    }
+
+    /// Compute the edit distance (Levenshtein distance) between the AS paths of `self` and
+    /// `other`, i.e., the minimal number of AS numbers to insert, delete or substitute to turn one
+    /// path into the other. Prepending counts as one edit per prepended AS number.
+    ///
+    /// Each AS set is treated as a single token, which only matches an AS set with the same members
+    /// (irrespective of their order). Replacing an AS set by a single AS number (or by a different
+    /// set) counts as one substitution.
+    pub fn path_distance(&self, other: &Update) -> usize {
+        let same = |a: &AsSegment, b: &AsSegment| match (a, b) {
+            (AsSegment::Num(a), AsSegment::Num(b)) => a == b,
+            (AsSegment::Set(a), AsSegment::Set(b)) => {
+                a.len() == b.len() && a.iter().sorted().eq(b.iter().sorted())
+            }
+            _ => false,
+        };
+
+        let (a, b) = (&self.as_path, &other.as_path);
+        // distances between the prefix of `a` processed so far and all prefixes of `b`.
+        let mut row: Vec<usize> = (0..=b.len()).collect();
+        for (i, x) in a.iter().enumerate() {
+            let mut diag = row[0];
+            row[0] = i + 1;
+            for (j, y) in b.iter().enumerate() {
+                let substitute = diag + usize::from(!same(x, y));
+                diag = row[j + 1];
+                row[j + 1] = substitute.min(row[j] + 1).min(diag + 1);
+            }
+        }
+        row[b.len()]
+    }
+
+    /// Whether the AS path contains a loop, i.e., an AS number that appears again after a different
+    /// AS number. Prepending (the same AS number in consecutive positions) is not a loop. See
+    /// [`Update::loop_asns`] for the exact definition.
+    pub fn has_as_loop(&self) -> bool {
+        !self.loop_asns().is_empty()
+    }
+
+    /// All AS numbers that form a loop on the path, in the order in which the loops are detected.
+    /// Each segment of the path is one position, and an AS set is a single position containing
+    /// all of its members. An AS number is part of a loop if it appears in two positions that are
+    /// not adjacent, and in no position in between. Hence:
+    ///
+    /// - `1 2 2 2 3` has no loop (prepending).
+    /// - `1 2 3 2` has a loop with AS 2.
+    /// - `1 2 {2 3}` has no loop, as the AS set directly follows AS 2 (e.g., when AS 2 aggregated
+    ///   routes and included itself in the set).
+    /// - `2 1 {2 3}` has a loop with AS 2.
+    ///
+    /// Members of the same AS set never form a loop among themselves.
+    pub fn loop_asns(&self) -> Vec<u32> {
+        let mut last_pos: HashMap<u32, usize> = HashMap::new();
+        let mut loops = Vec::new();
+        for (pos, seg) in self.as_path.iter().enumerate() {
+            let asns = match seg {
+                AsSegment::Num(asn) => std::slice::from_ref(asn),
+                AsSegment::Set(set) => set.as_slice(),
+            };
+            for asn in asns {
+                if let Some(last) = last_pos.insert(*asn, pos) {
+                    if last + 1 < pos && !loops.contains(asn) {
+                        loops.push(*asn);
+                    }
+                }
+            }
+        }
+        loops
+    }
+
+    /// Whether any AS number on the path (including the origin) is reserved for private use. See
+    /// [`is_private_asn`] for the exact ranges.
+    pub fn has_private_asn(&self) -> bool {
+        self.as_path.iter().any(AsSegment::is_private)
+    }
+
+    /// Whether any AS number on the path (including the origin) is reserved. See
+    /// [`is_reserved_asn`] for the exact ranges.
+    pub fn has_reserved_asn(&self) -> bool {
+        self.as_path.iter().any(AsSegment::is_reserved)
+    }
+
+    /// Whether the prefix lies within reserved address space. See [`is_bogon_prefix`] for the
+    /// table of reserved prefixes.
+    pub fn prefix_is_bogon(&self) -> bool {
+        is_bogon_prefix(&self.prefix)
+    }
+
+    /// Whether the update carries the `ORIGIN` attribute.
+    pub fn has_origin(&self) -> bool {
+        self.origin_type.is_some()
+    }
+
+    /// Whether the update carries the `MULTI_EXIT_DISC` attribute.
+    pub fn has_med(&self) -> bool {
+        self.med.is_some()
+    }
+
+    /// Whether the update carries the `LOCAL_PREF` attribute.
+    pub fn has_local_pref(&self) -> bool {
+        self.local_pref.is_some()
+    }
+
+    /// Whether the update carries the `COMMUNITIES` attribute with at least one community.
+    pub fn has_communities(&self) -> bool {
+        !self.communities.is_empty()
+    }
+
+    /// Which of the path attributes kept by an owned update it carries. Use
+    /// [`crate::record::ElementRef::attribute_flags`] to also get the `ATOMIC_AGGREGATE` and
+    /// `AGGREGATOR` attributes, which an owned update does not keep.
+    pub fn attribute_flags(&self) -> UpdateAttributeFlags {
+        UpdateAttributeFlags {
+            origin: self.has_origin(),
+            med: self.has_med(),
+            local_pref: self.has_local_pref(),
+            communities: self.has_communities(),
+        }
+    }
+
+    /// Iterate over the communities as [`Community`] values (e.g., to format them as `asn:value`).
+    pub fn iter_communities(&self) -> impl Iterator<Item = Community> + '_ {
+        self.communities.iter().copied().map(Community::from)
+    }
+}
+
+/// Which path attributes an update carried, as reported by libbgpstream. Get them for each
+/// element of a record with [`crate::record::ElementRef::attribute_flags`].
+///
+/// An owned [`Update`] only keeps the `ORIGIN`, `MULTI_EXIT_DISC`, `LOCAL_PREF`, and
+/// `COMMUNITIES` attributes (see [`UpdateAttributeFlags`]), so the presence of `ATOMIC_AGGREGATE`
+/// and `AGGREGATOR` is only available from the record.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AttributeFlags {
+    /// The `ORIGIN` attribute.
+    pub origin: bool,
+    /// The `MULTI_EXIT_DISC` attribute.
+    pub med: bool,
+    /// The `LOCAL_PREF` attribute.
+    pub local_pref: bool,
+    /// The `COMMUNITIES` attribute, with at least one community.
+    pub communities: bool,
+    /// The `ATOMIC_AGGREGATE` attribute.
+    pub atomic_aggregate: bool,
+    /// The `AGGREGATOR` attribute.
+    pub aggregator: bool,
+}
+
+/// Which of the path attributes kept by an owned [`Update`] it carried, returned by
+/// [`Update::attribute_flags`]. This is the subset of [`AttributeFlags`] that survives parsing an
+/// element. Convert the flags of the record into it (using `From`) to compare them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UpdateAttributeFlags {
+    /// The `ORIGIN` attribute.
+    pub origin: bool,
+    /// The `MULTI_EXIT_DISC` attribute.
+    pub med: bool,
+    /// The `LOCAL_PREF` attribute.
+    pub local_pref: bool,
+    /// The `COMMUNITIES` attribute, with at least one community.
+    pub communities: bool,
+}
+
+impl From<AttributeFlags> for UpdateAttributeFlags {
+    fn from(flags: AttributeFlags) -> Self {
+        Self {
+            origin: flags.origin,
+            med: flags.med,
+            local_pref: flags.local_pref,
+            communities: flags.communities,
+        }
+    }
+}
+
+/// Builder for an [`Update`], created with [`Update::builder`].
+#[derive(Debug, Clone)]
+pub struct UpdateBuilder {
+    update: Update,
+}
+
+impl UpdateBuilder {
+    /// Set the prefix.
+    pub fn prefix(&mut self, prefix: IpNet) -> &mut Self {
+        self.update.prefix = prefix;
+        self
+    }
+
+    /// Set the next hop.
+    pub fn next_hop(&mut self, next_hop: IpAddr) -> &mut Self {
+        self.update.next_hop = next_hop;
+        self
+    }
+
+    /// Set the AS path, replacing any path set before.
+    pub fn as_path(&mut self, path: impl IntoIterator<Item = AsSegment>) -> &mut Self {
+        self.update.as_path = path.into_iter().collect();
+        self
+    }
+
+    /// Add the community `asn:value`.
+    pub fn community(&mut self, asn: u16, value: u16) -> &mut Self {
+        self.update.communities.push((asn, value));
+        self
+    }
+
+    /// Set the communities, replacing any communities added before.
+    pub fn communities(&mut self, communities: impl IntoIterator<Item = (u16, u16)>) -> &mut Self {
+        self.update.communities = communities.into_iter().collect();
+        self
+    }
+
+    /// Set the `ORIGIN` attribute.
+    pub fn origin_type(&mut self, origin_type: OriginType) -> &mut Self {
+        self.update.origin_type = Some(origin_type);
+        self
+    }
+
+    /// Set the `MULTI_EXIT_DISC` attribute.
+    pub fn med(&mut self, med: u32) -> &mut Self {
+        self.update.med = Some(med);
+        self
+    }
+
+    /// Set the `LOCAL_PREF` attribute.
+    pub fn local_pref(&mut self, local_pref: u32) -> &mut Self {
+        self.update.local_pref = Some(local_pref);
+        self
+    }
+
+    /// Build the update. The builder can be reused to create further updates.
+    pub fn build(&self) -> Update {
+        self.update.clone()
+    }
+}
+
+/// A standard BGP community (RFC 1997), consisting of an AS number and a value. Formatted and
+/// parsed as `asn:value` (e.g., `65000:100`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Community(pub u16, pub u16);
+
+impl From<(u16, u16)> for Community {
+    fn from((asn, value): (u16, u16)) -> Self {
+        Self(asn, value)
+    }
+}
+
+impl From<Community> for (u16, u16) {
+    fn from(c: Community) -> Self {
+        (c.0, c.1)
+    }
+}
+
+impl Display for Community {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.0, self.1)
+    }
+}
+
+impl FromStr for Community {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_colon_separated(s) {
+            Some([asn, value]) => Ok(Self(asn, value)),
+            _ => Err(ParseError::new("community", s)),
+        }
+    }
+}
+
+/// A large BGP community (RFC 8092), consisting of the global administrator (an AS number) and
+/// two local data parts. Formatted and parsed as `asn:data1:data2` (e.g., `65000:1:2`).
+///
+/// libbgpstream does not expose the `LARGE_COMMUNITY` attribute, so this type is only used for
+/// configuration and filtering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LargeCommunity(pub u32, pub u32, pub u32);
+
+impl Display for LargeCommunity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.0, self.1, self.2)
+    }
+}
+
+impl FromStr for LargeCommunity {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_colon_separated(s) {
+            Some([asn, data1, data2]) => Ok(Self(asn, data1, data2)),
+            _ => Err(ParseError::new("large community", s)),
+        }
+    }
+}
+
+/// Parse exactly `N` colon-separated numbers, or return `None`.
+fn parse_colon_separated<T: FromStr, const N: usize>(s: &str) -> Option<[T; N]> {
+    let parts = s
+        .trim()
+        .split(':')
+        .map(|x| x.parse().ok())
+        .collect::<Option<Vec<T>>>()?;
+    parts.try_into().ok()
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PeerState {
@@ -187,25 +871,6 @@ pub enum PeerState {
     Unknown,
 }
 
-impl TryFrom<u32> for PeerState {
-    type Error = BgpStreamError;
-
-    fn try_from(value: u32) -> Result<Self, Self::Error> {
-        match value {
-            ELEM_PEERSTATE_ACTIVE => Ok(Self::Active),
-            ELEM_PEERSTATE_CLEARING => Ok(Self::Clearing),
-            ELEM_PEERSTATE_CONNECT => Ok(Self::Connect),
-            ELEM_PEERSTATE_DELETED => Ok(Self::Deleted),
-            ELEM_PEERSTATE_ESTABLISHED => Ok(Self::Established),
-            ELEM_PEERSTATE_IDLE => Ok(Self::Idle),
-            ELEM_PEERSTATE_OPENCONFIRM => Ok(Self::OpenConfirm),
-            ELEM_PEERSTATE_OPENSENT => Ok(Self::OpenSent),
-            ELEM_PEERSTATE_UNKNOWN => Ok(Self::Unknown),
-            _ => Err(BgpStreamError::UnknownPeerState),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OriginType {
@@ -223,72 +888,3 @@ impl Display for OriginType {
         }
     }
 }
-
-impl TryFrom<u32> for OriginType {
-    type Error = BgpStreamError;
-
-    fn try_from(value: u32) -> Result<Self, Self::Error> {
-        match value {
-            ORIGIN_EGP => Ok(Self::Egp),
-            ORIGIN_IGP => Ok(Self::Igp),
-            ORIGIN_INCOMPLETE => Ok(Self::Incomplete),
-            _ => Err(BgpStreamError::UnknownOriginType),
-        }
-    }
-}
-
-unsafe fn extract_as_path(p_elem: *mut bgpstream_elem_t) -> Vec<AsSegment> {
-    let mut iter = bgpstream_as_path_iter { cur_offset: 0 };
-    let mut path: Vec<AsSegment> = Vec::new();
-    let elem = &*p_elem;
-    let iter = &mut iter as *mut bgpstream_as_path_iter;
-    // reset the iterator
-    bgpstream_as_path_iter_reset(iter);
-
-    loop {
-        let seg = bgpstream_as_path_get_next_seg(elem.as_path, iter);
-        if seg.is_null() {
-            break;
-        }
-        path.push(parse_as_path_seg(seg));
-    }
-
-    path
-}
-
-unsafe fn extract_communities(p_elem: *mut bgpstream_elem_t) -> Vec<(u16, u16)> {
-    // read the full as path length
-    let mut communities = Vec::new();
-    let elem = &*p_elem;
-
-    for i in 0.. {
-        let comm = bgpstream_community_set_get(elem.communities, i);
-        if comm.is_null() {
-            break;
-        }
-        let comm = &*comm;
-        let asn = comm.__bindgen_anon_1.__bindgen_anon_1.asn;
-        let value = comm.__bindgen_anon_1.__bindgen_anon_1.value;
-        communities.push((asn, value))
-    }
-
-    communities
-}
-
-unsafe fn parse_as_path_seg(seg: *mut bgpstream_as_path_seg_t) -> AsSegment {
-    let seg = &*seg;
-    if *seg.__bindgen_anon_1.type_.as_ref() == AS_PATH_SEG_ASN as u8 {
-        // single AS number
-        AsSegment::Num(seg.__bindgen_anon_1.asn.as_ref().asn)
-    } else {
-        // AS set
-        let set = seg.__bindgen_anon_1.set.as_ref();
-        let len = set.asn_cnt as isize;
-        let slice_base = addr_of!(set.asn) as *const u32;
-        let mut list = Vec::with_capacity(len as usize);
-        for i in 0..len {
-            list.push(std::ptr::read_unaligned(slice_base.offset(i)));
-        }
-        AsSegment::Set(list.to_vec())
-    }
-}
@@ -0,0 +1,372 @@
+//! Client-side filters. In contrast to the filters applied by libbgpstream, these filters are
+//! evaluated in the [`crate::stream::BgpStream`] iterator on each parsed element, i.e.,
+//! libbgpstream still fetches and parses all elements, and the iterator skips those that do not
+//! match. They are combined with the server-side filters (and with each other) using a logical AND.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    ops::RangeInclusive,
+};
+
+use ipnet::IpNet;
+use prefix_trie::joint::JointPrefixSet;
+
+use crate::{
+    element::{is_bogon_prefix, AsSegment, Element, ElementType, PeerState},
+    stream::{ElementTypeDescr, IpVersion, Query},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum ClientFilter {
+    /// Keep only elements whose peer AS number lies within the range.
+    PeerAsn(RangeInclusive<u32>),
+    /// Keep only RIB entries and announcements whose origin AS lies within the range.
+    OriginAsn(RangeInclusive<u32>),
+    /// Keep only RIB entries and announcements whose AS path length lies within the range.
+    AsPathLen(RangeInclusive<usize>),
+    /// Keep only elements with a prefix of the given family whose length lies within the range.
+    PrefixLen(IpVersion, RangeInclusive<u8>),
+    /// Skip elements whose prefix lies within reserved address space.
+    NoBogonPrefix,
+    /// Skip elements whose prefix is excluded.
+    ExcludePrefixes(PrefixExclusion),
+    /// Skip RIB entries and announcements whose AS path contains `AS_TRANS`.
+    NoAsTrans,
+    /// Keep only RIB entries and announcements whose communities match.
+    Communities(CommunityMatcher),
+    /// Keep only RIB entries and announcements with a community `asn:value`, where `None` matches
+    /// any value.
+    CommunityWildcard(Option<u16>, Option<u16>),
+    /// Keep only peer state changes into the given state.
+    PeerStateInto(PeerState),
+    /// Keep only peer state changes from the given state.
+    PeerStateFrom(PeerState),
+}
+
+impl ClientFilter {
+    /// Check whether `element` passes the filter.
+    pub(crate) fn matches(&self, element: &Element) -> bool {
+        match self {
+            ClientFilter::PeerAsn(range) => range.contains(&element.peer_asn),
+            ClientFilter::OriginAsn(range) => match element.as_update().and_then(|u| u.origin()) {
+                Some(AsSegment::Num(asn)) => range.contains(asn),
+                Some(AsSegment::Set(set)) => set.iter().any(|asn| range.contains(asn)),
+                None => false,
+            },
+            ClientFilter::AsPathLen(range) => element
+                .as_update()
+                .is_some_and(|u| range.contains(&u.as_path_len())),
+            ClientFilter::PrefixLen(family, range) => match (family, element.prefix()) {
+                (IpVersion::IPv4, Some(IpNet::V4(p))) => range.contains(&p.prefix_len()),
+                (IpVersion::IPv6, Some(IpNet::V6(p))) => range.contains(&p.prefix_len()),
+                _ => false,
+            },
+            ClientFilter::NoBogonPrefix => !element.prefix().is_some_and(|p| is_bogon_prefix(&p)),
+            ClientFilter::ExcludePrefixes(exclusion) => {
+                !element.prefix().is_some_and(|p| exclusion.excludes(&p))
+            }
+            ClientFilter::NoAsTrans => element.as_update().is_none_or(|u| !u.contains_as_trans()),
+            ClientFilter::Communities(matcher) => matcher.matches(element),
+            ClientFilter::CommunityWildcard(asn, value) => element.as_update().is_some_and(|u| {
+                u.communities.iter().any(|(a, v)| {
+                    asn.is_none_or(|asn| asn == *a) && value.is_none_or(|value| value == *v)
+                })
+            }),
+            ClientFilter::PeerStateInto(state) => {
+                matches!(&element.e, ElementType::PeerState { to, .. } if to == state)
+            }
+            ClientFilter::PeerStateFrom(state) => {
+                matches!(&element.e, ElementType::PeerState { from, .. } if from == state)
+            }
+        }
+    }
+}
+
+impl Display for ClientFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientFilter::PeerAsn(range) => write!(f, "peer-asn in {range:?}"),
+            ClientFilter::OriginAsn(range) => write!(f, "origin-asn in {range:?}"),
+            ClientFilter::AsPathLen(range) => write!(f, "as-path-len in {range:?}"),
+            ClientFilter::PrefixLen(family, range) => {
+                write!(f, "prefix-len (IPv{family}) in {range:?}")
+            }
+            ClientFilter::NoBogonPrefix => f.write_str("no bogon prefix"),
+            ClientFilter::ExcludePrefixes(exclusion) => {
+                let kind = if exclusion.exact { "exact" } else { "covered" };
+                write!(f, "no prefix ({kind}) in {:?}", exclusion.nets)
+            }
+            ClientFilter::NoAsTrans => f.write_str("no AS_TRANS in as-path"),
+            ClientFilter::Communities(matcher) => write!(f, "communities match {matcher:?}"),
+            ClientFilter::CommunityWildcard(asn, value) => {
+                let part = |x: &Option<u16>| x.map_or("*".to_string(), |x| x.to_string());
+                write!(f, "has community {}:{}", part(asn), part(value))
+            }
+            ClientFilter::PeerStateInto(state) => write!(f, "peer state changes into {state:?}"),
+            ClientFilter::PeerStateFrom(state) => write!(f, "peer state changes from {state:?}"),
+        }
+    }
+}
+
+/// A set of excluded prefixes, backed by a patricia trie. Prefixes are excluded if they are equal
+/// to an entry, or (unless `exact`) covered by one.
+#[derive(Debug, Clone)]
+pub(crate) struct PrefixExclusion {
+    // all entries, sorted and without duplicates, to compare and describe the filter.
+    nets: Vec<IpNet>,
+    exact: bool,
+    set: JointPrefixSet<IpNet>,
+}
+
+impl PrefixExclusion {
+    pub(crate) fn new(nets: impl IntoIterator<Item = IpNet>, exact: bool) -> Self {
+        let mut nets: Vec<IpNet> = nets.into_iter().map(|net| net.trunc()).collect();
+        nets.sort();
+        nets.dedup();
+        let mut set = JointPrefixSet::new();
+        for net in &nets {
+            set.insert(*net);
+        }
+        Self { nets, exact, set }
+    }
+
+    fn excludes(&self, prefix: &IpNet) -> bool {
+        if self.exact {
+            self.set.contains(&prefix.trunc())
+        } else {
+            self.set.get_spm(&prefix.trunc()).is_some()
+        }
+    }
+}
+
+impl PartialEq for PrefixExclusion {
+    fn eq(&self, other: &Self) -> bool {
+        (&self.nets, self.exact) == (&other.nets, other.exact)
+    }
+}
+
+impl Eq for PrefixExclusion {}
+
+impl Hash for PrefixExclusion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.nets.hash(state);
+        self.exact.hash(state);
+    }
+}
+
+impl Element {
+    /// Check whether the element passes all element-level filters of `query`, evaluated in pure
+    /// Rust without running the query. This allows refining a set of elements fetched with a broad
+    /// query using narrower ones. The following filters are supported:
+    ///
+    /// - Element type ([`Query::event_type`]).
+    /// - Peer AS ([`Query::peer_asn`] and [`Query::not_peer_asn`]).
+    /// - Origin AS ([`Query::origin_asn`]). For AS-set origins, any member matches.
+    /// - Prefix ([`Query::prefix`]), with all match types.
+    /// - Community ([`Query::community`]), given as `asn:value`, where either part may be `*`.
+    /// - IP version ([`Query::ip_version`]).
+    /// - All client-side filters (e.g., [`Query::peer_asn_range`] or
+    ///   [`Query::community_matcher`]).
+    ///
+    /// As in libbgpstream, filters of the same type are combined using a logical OR, and filters
+    /// of different types using a logical AND. Filters on the record (project, collector, and
+    /// record type), AS path expressions ([`Query::as_path`]), the interval, and the limit cannot
+    /// be evaluated on a single element and are ignored.
+    pub fn matches(&self, query: &Query) -> bool {
+        query.matches(self)
+    }
+}
+
+/// Evaluate the element-level libbgpstream filters on `element`, where each filter is given by its
+/// name (as in [`crate::stream::QueryPlan::filters`]) and its value. As in libbgpstream, filters
+/// of the same type are combined using a logical OR, and filters of different types using a
+/// logical AND. All prefix filters count as the same type, and `not-peer-asn` excludes every
+/// listed AS. Record-level filters (`project`, `collector`, and `record-type`) and AS path
+/// expressions (`aspath`) cannot be evaluated on an element and are ignored.
+pub(crate) fn matches_filters<'a>(
+    filters: impl IntoIterator<Item = (&'a str, &'a str)>,
+    element: &Element,
+) -> bool {
+    let mut groups: HashMap<&str, bool> = HashMap::new();
+    for (name, value) in filters {
+        let Some(m) = matches_filter(name, value, element) else {
+            continue;
+        };
+        if name == "not-peer-asn" {
+            *groups.entry(name).or_insert(true) &= m;
+        } else {
+            let group = if name.starts_with("prefix") {
+                "prefix"
+            } else {
+                name
+            };
+            *groups.entry(group).or_insert(false) |= m;
+        }
+    }
+    groups.values().all(|m| *m)
+}
+
+/// All element-level libbgpstream filters, given as `(name, value)`, that `element` satisfies.
+/// Filters that cannot be evaluated on an element are never included.
+pub(crate) fn satisfied_filters(
+    filters: &[(&'static str, String)],
+    element: &Element,
+) -> Vec<(&'static str, String)> {
+    filters
+        .iter()
+        .filter(|(name, value)| matches_filter(name, value, element) == Some(true))
+        .cloned()
+        .collect()
+}
+
+/// Evaluate a single libbgpstream filter, or return `None` if it does not apply to elements.
+/// Values that cannot be parsed never match.
+fn matches_filter(name: &str, value: &str, element: &Element) -> Option<bool> {
+    let value = value.trim();
+    let prefix = || value.parse::<IpNet>().ok().zip(element.prefix());
+    Some(match name {
+        "elem-type" => value.parse::<ElementTypeDescr>().is_ok_and(|t| {
+            matches!(
+                (t, &element.e),
+                (ElementTypeDescr::RIBs, ElementType::RIB(_))
+                    | (
+                        ElementTypeDescr::Announcements,
+                        ElementType::Announcement(_)
+                    )
+                    | (ElementTypeDescr::Withdrawals, ElementType::Withdrawal(_))
+                    | (ElementTypeDescr::PeerStates, ElementType::PeerState { .. })
+            )
+        }),
+        "peer-asn" => value.parse() == Ok(element.peer_asn),
+        "not-peer-asn" => value.parse() != Ok(element.peer_asn),
+        "origin-asn" => match (
+            value.parse::<u32>(),
+            element.as_update().and_then(|u| u.origin()),
+        ) {
+            (Ok(asn), Some(AsSegment::Num(origin))) => asn == *origin,
+            (Ok(asn), Some(AsSegment::Set(set))) => set.contains(&asn),
+            _ => false,
+        },
+        "prefix-exact" => prefix().is_some_and(|(f, p)| f == p),
+        "prefix-more" => prefix().is_some_and(|(f, p)| f.contains(&p)),
+        "prefix-less" => prefix().is_some_and(|(f, p)| p.contains(&f)),
+        "prefix-any" => prefix().is_some_and(|(f, p)| f.contains(&p) || p.contains(&f)),
+        "community" => {
+            let Some((asn, val)) = value.split_once(':') else {
+                return Some(false);
+            };
+            let part = |s: &str| match s {
+                "*" => Ok(None),
+                s => s.parse::<u16>().map(Some),
+            };
+            let (Ok(asn), Ok(val)) = (part(asn), part(val)) else {
+                return Some(false);
+            };
+            element.as_update().is_some_and(|u| {
+                u.communities.iter().any(|(a, v)| {
+                    asn.is_none_or(|asn| asn == *a) && val.is_none_or(|val| val == *v)
+                })
+            })
+        }
+        "ip-version" => matches!(
+            (value, element.prefix()),
+            ("4", Some(IpNet::V4(_))) | ("6", Some(IpNet::V6(_)))
+        ),
+        _ => return None,
+    })
+}
+
+/// A community matcher combines several conditions on the set of communities of an element using
+/// a logical AND. A community is given as a pair `(asn, value)`, e.g., `(65000, 100)` for
+/// `65000:100`. The matcher is built with the following conditions:
+///
+/// - [`CommunityMatcher::has`]: the community is present.
+/// - [`CommunityMatcher::not`]: the community is not present.
+/// - [`CommunityMatcher::any_of`]: at least one of the communities is present. An empty list never
+///   matches.
+/// - [`CommunityMatcher::all_of`]: all of the communities are present. An empty list always
+///   matches.
+///
+/// A matcher without any condition matches every RIB entry and announcement. Withdrawals and peer
+/// state changes carry no communities and never match. Use the matcher in a query with
+/// [`crate::stream::Query::community_matcher`].
+///
+/// ```
+/// use routeviews::filter::CommunityMatcher;
+///
+/// // has 65000:100 AND NOT 65000:666 AND (3356:2 OR 3356:3)
+/// let mut matcher = CommunityMatcher::new();
+/// matcher
+///     .has(65000, 100)
+///     .not(65000, 666)
+///     .any_of([(3356, 2), (3356, 3)]);
+///
+/// assert!(matcher.matches_communities(&[(65000, 100), (3356, 3)]));
+/// assert!(!matcher.matches_communities(&[(65000, 100), (65000, 666), (3356, 3)]));
+/// assert!(!matcher.matches_communities(&[(65000, 100)]));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CommunityMatcher {
+    conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Condition {
+    Has((u16, u16)),
+    Not((u16, u16)),
+    AnyOf(Vec<(u16, u16)>),
+    AllOf(Vec<(u16, u16)>),
+}
+
+impl CommunityMatcher {
+    /// Create a new matcher without any condition.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require that the community `asn:value` is present.
+    pub fn has(&mut self, asn: u16, value: u16) -> &mut Self {
+        self.conditions.push(Condition::Has((asn, value)));
+        self
+    }
+
+    /// Require that the community `asn:value` is not present.
+    pub fn not(&mut self, asn: u16, value: u16) -> &mut Self {
+        self.conditions.push(Condition::Not((asn, value)));
+        self
+    }
+
+    /// Require that at least one of the `communities` is present.
+    pub fn any_of(&mut self, communities: impl IntoIterator<Item = (u16, u16)>) -> &mut Self {
+        self.conditions
+            .push(Condition::AnyOf(communities.into_iter().collect()));
+        self
+    }
+
+    /// Require that all of the `communities` are present.
+    pub fn all_of(&mut self, communities: impl IntoIterator<Item = (u16, u16)>) -> &mut Self {
+        self.conditions
+            .push(Condition::AllOf(communities.into_iter().collect()));
+        self
+    }
+
+    /// Check whether the communities of `element` match. Withdrawals and peer state changes never
+    /// match.
+    pub fn matches(&self, element: &Element) -> bool {
+        element
+            .as_update()
+            .is_some_and(|u| self.matches_communities(&u.communities))
+    }
+
+    /// Check whether the set of `communities` matches all conditions.
+    pub fn matches_communities(&self, communities: &[(u16, u16)]) -> bool {
+        self.conditions.iter().all(|c| match c {
+            Condition::Has(x) => communities.contains(x),
+            Condition::Not(x) => !communities.contains(x),
+            Condition::AnyOf(xs) => xs.iter().any(|x| communities.contains(x)),
+            Condition::AllOf(xs) => xs.iter().all(|x| communities.contains(x)),
+        })
+    }
+}
@@ -0,0 +1,95 @@
+//! Conversion of single elements into JSON values, independent of the `serde` feature.
+
+use serde_json::{json, Value};
+use time::format_description::well_known::Rfc3339;
+
+use crate::element::{AsSegment, Element, ElementType, OriginType, PeerState, Update};
+
+impl Element {
+    /// Convert the element into a JSON object, e.g., to embed it in a larger document. Requires
+    /// the `json` feature (but not the `serde` feature). The representation is stable, and uses the
+    /// field names of [`Element`] and [`Update`]:
+    ///
+    /// | Field         | Type                          | Present for                             |
+    /// |---------------|-------------------------------|-----------------------------------------|
+    /// | `type`        | string (see below)            | all                                     |
+    /// | `time`        | RFC 3339 string               | all                                     |
+    /// | `peer_ip`     | string                        | all                                     |
+    /// | `peer_asn`    | number                        | all                                     |
+    /// | `prefix`      | string                        | RIB entries, announcements, withdrawals |
+    /// | `next_hop`    | string                        | RIB entries, announcements              |
+    /// | `as_path`     | array (see below)             | RIB entries, announcements              |
+    /// | `communities` | array of `[asn, value]` pairs | RIB entries, announcements              |
+    /// | `origin_type` | string (see below) or `null`  | RIB entries, announcements              |
+    /// | `med`         | number or `null`              | RIB entries, announcements              |
+    /// | `local_pref`  | number or `null`              | RIB entries, announcements              |
+    /// | `from`, `to`  | string (see below)            | peer state changes                      |
+    ///
+    /// The `type` is one of `"rib"`, `"announcement"`, `"withdrawal"`, and `"peer_state"`. The AS
+    /// path is an array of AS numbers, in which an AS set is a nested array. The origin type is one
+    /// of `"igp"`, `"egp"`, and `"incomplete"`, and peer states are written in snake case (e.g.,
+    /// `"open_sent"`).
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({
+            "time": self.time.format(&Rfc3339).ok(),
+            "peer_ip": self.peer_ip.to_string(),
+            "peer_asn": self.peer_asn,
+        });
+        let fields = match &self.e {
+            ElementType::RIB(u) => update_json("rib", u),
+            ElementType::Announcement(u) => update_json("announcement", u),
+            ElementType::Withdrawal(prefix) => json!({
+                "type": "withdrawal",
+                "prefix": prefix.to_string(),
+            }),
+            ElementType::PeerState { from, to } => json!({
+                "type": "peer_state",
+                "from": peer_state_name(*from),
+                "to": peer_state_name(*to),
+            }),
+        };
+        if let (Value::Object(value), Value::Object(fields)) = (&mut value, fields) {
+            value.extend(fields);
+        }
+        value
+    }
+}
+
+fn update_json(kind: &str, u: &Update) -> Value {
+    let as_path: Vec<Value> = u
+        .as_path
+        .iter()
+        .map(|seg| match seg {
+            AsSegment::Num(asn) => json!(asn),
+            AsSegment::Set(set) => json!(set),
+        })
+        .collect();
+    json!({
+        "type": kind,
+        "prefix": u.prefix.to_string(),
+        "next_hop": u.next_hop.to_string(),
+        "as_path": as_path,
+        "communities": u.communities,
+        "origin_type": u.origin_type.map(|o| match o {
+            OriginType::Igp => "igp",
+            OriginType::Egp => "egp",
+            OriginType::Incomplete => "incomplete",
+        }),
+        "med": u.med,
+        "local_pref": u.local_pref,
+    })
+}
+
+fn peer_state_name(state: PeerState) -> &'static str {
+    match state {
+        PeerState::Idle => "idle",
+        PeerState::Connect => "connect",
+        PeerState::Active => "active",
+        PeerState::OpenSent => "open_sent",
+        PeerState::OpenConfirm => "open_confirm",
+        PeerState::Established => "established",
+        PeerState::Clearing => "clearing",
+        PeerState::Deleted => "deleted",
+        PeerState::Unknown => "unknown",
+    }
+}
@@ -28,58 +28,193 @@
 //! # }
 //! ```
 
+/// Emit a warning through the `tracing` facade if the `tracing` feature is enabled, and through
+/// the `log` facade otherwise, such that each warning reaches exactly one of them.
+#[cfg(feature = "ffi")]
+macro_rules! log_warn {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::warn!($($arg)*);
+        #[cfg(not(feature = "tracing"))]
+        log::warn!($($arg)*);
+    }};
+}
+
+pub mod adapter;
+pub mod analysis;
+#[cfg(feature = "clap")]
+pub mod cli;
+#[cfg(feature = "compression")]
+mod compression;
 pub mod element;
+#[cfg(feature = "ffi")]
+pub mod filter;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "ffi")]
+pub mod merge;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "ffi")]
+mod mrt;
+#[cfg(feature = "ffi")]
+mod parse;
+#[cfg(feature = "ffi")]
 pub mod record;
+mod reorder;
+#[cfg(feature = "rislive")]
+pub mod rislive;
+pub mod rpki;
+#[cfg(feature = "ffi")]
 pub mod stream;
+#[cfg(feature = "tracing")]
+mod trace;
+pub mod watch;
 
+#[cfg(feature = "ffi")]
 pub use stream::Query;
 
+use std::ffi::NulError;
+#[cfg(feature = "ffi")]
 use std::{
-    ffi::{CString, IntoStringError, NulError},
-    net::IpAddr,
-    slice,
+    fs::{File, OpenOptions},
+    hash::{BuildHasher, RandomState},
+    io,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
-use ipnet::{IpNet, PrefixLenError};
-use libbgpstream_sys::{
-    bgpstream_addr_version_t_BGPSTREAM_ADDR_VERSION_IPV4 as ADDR_VERSION_IPV4,
-    bgpstream_addr_version_t_BGPSTREAM_ADDR_VERSION_IPV6 as ADDR_VERSION_IPV6,
-    union_bgpstream_ip_addr_t, union_bgpstream_pfx_t,
-};
+use ipnet::PrefixLenError;
 use thiserror::Error;
 use time::error::ComponentRange;
 
-fn array_to_string(array: &[i8]) -> Result<String, IntoStringError> {
-    let s: &[u8] = unsafe { slice::from_raw_parts(array.as_ptr() as *const u8, array.len()) };
+/// Create a new temporary file, readable and writable only by the current user, and return its
+/// path. The name has a random suffix, and the file is created exclusively (failing if the path
+/// already exists, even as a symlink), such that other users can neither predict nor hijack it.
+#[cfg(feature = "ffi")]
+fn create_temp_file() -> io::Result<(PathBuf, File)> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    loop {
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        // `RandomState` is seeded from the operating system's random number generator.
+        let suffix = RandomState::new().hash_one(id);
+        let name = format!("routeviews-{}-{suffix:016x}.mrt", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
 
-    let null_pos = s.iter().position(|x| *x == 0).unwrap_or(s.len());
-    let s = CString::new(&s[..null_pos]).expect("already checked");
-    s.into_string()
+#[cfg(feature = "ffi")]
+impl BgpStreamError {
+    /// Whether the error only affects a single element while parsing it, such that the following
+    /// elements of the same record can still be read.
+    pub(crate) fn is_element_error(&self) -> bool {
+        matches!(
+            self,
+            BgpStreamError::UnknownElementType(_)
+                | BgpStreamError::InvalidIpAddress
+                | BgpStreamError::UnknownPeerState(_)
+                | BgpStreamError::UnknownOriginType(_)
+                | BgpStreamError::InvalidMaskLen(_)
+                | BgpStreamError::Timestamp(_)
+        )
+    }
+
+    /// Whether [`Query::retry_start`] retries the error for a query that reads from the broker.
+    /// This is the case for a failure to start the stream, and for a failure to fetch its first
+    /// record (e.g., because the broker or a dump file is briefly unavailable).
+    pub fn is_retryable_at_start(&self) -> bool {
+        matches!(
+            self,
+            BgpStreamError::Start
+                | BgpStreamError::GetNextRecord
+                | BgpStreamError::RecordSourceEmptyOrCorrupted
+        )
+    }
 }
 
-unsafe fn parse_bgpstream_ip(ip: union_bgpstream_ip_addr_t) -> Result<IpAddr, BgpStreamError> {
-    let version = ip.__bindgen_anon_1.__bindgen_anon_1.version;
-    match version {
-        ADDR_VERSION_IPV4 => Ok(IpAddr::V4(ip.bs_ipv4.addr.s_addr.into())),
-        ADDR_VERSION_IPV6 => Ok(IpAddr::V6(ip.bs_ipv6.addr.__in6_u.__u6_addr8.into())),
-        _ => Err(BgpStreamError::InvalidIpAddress),
+#[cfg(any(feature = "metrics", feature = "tracing"))]
+impl BgpStreamError {
+    /// The snake-case name of the variant, used to label metrics and tracing events.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            BgpStreamError::Create => "create",
+            BgpStreamError::LibraryUnusable(_) => "library_unusable",
+            BgpStreamError::Start => "start",
+            BgpStreamError::AddFilter => "add_filter",
+            BgpStreamError::AddRecentInterval => "add_recent_interval",
+            BgpStreamError::AddInterval => "add_interval",
+            BgpStreamError::AddRibPeriod => "add_rib_period",
+            BgpStreamError::InvalidRibPeriod(_) => "invalid_rib_period",
+            BgpStreamError::IntervalShorterThanDumps { .. } => "interval_shorter_than_dumps",
+            BgpStreamError::CollectorProjectMismatch { .. } => "collector_project_mismatch",
+            BgpStreamError::RecordTypeExcludesElementTypes { .. } => {
+                "record_type_excludes_element_types"
+            }
+            BgpStreamError::EmptyRib => "empty_rib",
+            BgpStreamError::GetNextRecord => "get_next_record",
+            BgpStreamError::GetNextRecordNull => "get_next_record_null",
+            BgpStreamError::RecordCorrupted => "record_corrupted",
+            BgpStreamError::RecordUnsupported => "record_unsupported",
+            BgpStreamError::RecordSourceEmptyOrCorrupted => "record_source_empty_or_corrupted",
+            BgpStreamError::RecordSourceFiltered => "record_source_filtered",
+            BgpStreamError::TruncatedFile { .. } => "truncated_file",
+            BgpStreamError::UnknownRecordStatus(_) => "unknown_record_status",
+            BgpStreamError::GetNextElement => "get_next_element",
+            BgpStreamError::GetNextElementNull => "get_next_element_null",
+            BgpStreamError::UnknownElementType(_) => "unknown_element_type",
+            BgpStreamError::InvalidIpAddress => "invalid_ip_address",
+            BgpStreamError::UnknownPeerState(_) => "unknown_peer_state",
+            BgpStreamError::UnknownOriginType(_) => "unknown_origin_type",
+            BgpStreamError::InterfaceNotFound(_) => "interface_not_found",
+            BgpStreamError::InterfaceOptionNotFound(_) => "interface_option_not_found",
+            BgpStreamError::SetInterfaceOption => "set_interface_option",
+            BgpStreamError::InvalidMaskLen(_) => "invalid_mask_len",
+            BgpStreamError::StringContainsNull(_) => "string_contains_null",
+            BgpStreamError::Timestamp(_) => "timestamp",
+            BgpStreamError::Io(_) => "io",
+            BgpStreamError::Parse(_) => "parse",
+            #[cfg(feature = "rislive")]
+            BgpStreamError::WebSocket(_) => "web_socket",
+            #[cfg(feature = "rislive")]
+            BgpStreamError::InvalidRisLiveMessage(_) => "invalid_ris_live_message",
+            #[cfg(feature = "rislive")]
+            BgpStreamError::RisLive(_) => "ris_live",
+        }
     }
 }
 
-unsafe fn parse_bgpstream_prefix(prefix: union_bgpstream_pfx_t) -> Result<IpNet, BgpStreamError> {
-    let prefix_len = prefix
-        .__bindgen_anon_1
-        .__bindgen_anon_1
-        .__bindgen_anon_1
-        .mask_len;
-    let ip = parse_bgpstream_ip(prefix.__bindgen_anon_1.address)?;
-    Ok(IpNet::new(ip, prefix_len)?)
+/// Error when parsing a value from a string.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Invalid {expected}: {input:?}")]
+pub struct ParseError {
+    expected: &'static str,
+    input: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(expected: &'static str, input: impl Into<String>) -> Self {
+        Self {
+            expected,
+            input: input.into(),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum BgpStreamError {
     #[error("Cannot create the BGP stream object")]
     Create,
+    #[error("libbgpstream is not usable: {0}")]
+    LibraryUnusable(String),
     #[error("Cannot start the BGP stream")]
     Start,
     #[error("Error adding a filter")]
@@ -90,6 +225,33 @@ pub enum BgpStreamError {
     AddInterval,
     #[error("Error adding the RIB period")]
     AddRibPeriod,
+    #[error("The RIB period must be a positive number of whole seconds: {0}")]
+    InvalidRibPeriod(time::Duration),
+    #[error("The interval ({window}) is shorter than the dump period ({cadence})")]
+    IntervalShorterThanDumps {
+        window: time::Duration,
+        cadence: time::Duration,
+    },
+    #[cfg(feature = "ffi")]
+    #[error(
+        "None of the collectors {collectors:?} is part of the projects {projects:?}, so the \
+         stream is empty"
+    )]
+    CollectorProjectMismatch {
+        collectors: Vec<String>,
+        projects: Vec<stream::Project>,
+    },
+    #[cfg(feature = "ffi")]
+    #[error(
+        "Records of type {record_type} contain none of the element types {element_types:?}, so \
+         the stream is empty"
+    )]
+    RecordTypeExcludesElementTypes {
+        record_type: stream::RecordType,
+        element_types: Vec<stream::ElementTypeDescr>,
+    },
+    #[error("The RIB dump selected by the query contains no entries")]
+    EmptyRib,
     #[error("Error getting the next record")]
     GetNextRecord,
     #[error("The next record computed is a NULL pointer")]
@@ -100,22 +262,24 @@ pub enum BgpStreamError {
     RecordUnsupported,
     #[error("The record source is empty, corrupted, or contains no valid record")]
     RecordSourceEmptyOrCorrupted,
-    #[error("Received a record with an unknown status")]
-    UnknownRecordStatus,
+    #[error("The record source was excluded by the filters")]
+    RecordSourceFiltered,
+    #[error("The file {file} ends with a truncated record at byte {offset}")]
+    TruncatedFile { file: String, offset: u64 },
+    #[error("Received a record with an unknown status: {0}")]
+    UnknownRecordStatus(u32),
     #[error("Error getting the next element of a record")]
     GetNextElement,
     #[error("The next element computed is a NULL pointer")]
     GetNextElementNull,
-    #[error("Unknown element type")]
-    UnknownElementType,
+    #[error("Unknown element type: {0}")]
+    UnknownElementType(u32),
     #[error("Invalid IP address")]
     InvalidIpAddress,
-    #[error("Element was detached without fetching the requested data.")]
-    ElementIsDetached,
-    #[error("Unknown peer state recieved")]
-    UnknownPeerState,
-    #[error("Unknown origin type received")]
-    UnknownOriginType,
+    #[error("Unknown peer state recieved: {0}")]
+    UnknownPeerState(u32),
+    #[error("Unknown origin type received: {0}")]
+    UnknownOriginType(u32),
     #[error("Interface with name {0} does not exist")]
     InterfaceNotFound(String),
     #[error("Interface option with name {0} does not exist")]
@@ -128,4 +292,17 @@ pub enum BgpStreamError {
     StringContainsNull(#[from] NulError),
     #[error("Error converting from a timestamp into date and time: {0}")]
     Timestamp(#[from] ComponentRange),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[cfg(feature = "rislive")]
+    #[error("RIS Live websocket error: {0}")]
+    WebSocket(Box<tungstenite::Error>),
+    #[cfg(feature = "rislive")]
+    #[error("Cannot parse the RIS Live message: {0}")]
+    InvalidRisLiveMessage(#[from] serde_json::Error),
+    #[cfg(feature = "rislive")]
+    #[error("RIS Live error: {0}")]
+    RisLive(String),
 }
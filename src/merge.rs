@@ -0,0 +1,150 @@
+//! Merge the elements of multiple streams (e.g., one per collector) into a single timeline, see
+//! [`merge_streams`].
+
+use std::{cmp::Reverse, collections::BinaryHeap, fmt::Display, sync::Arc};
+
+use time::OffsetDateTime;
+
+use crate::{
+    element::Element,
+    stream::{BgpStream, RecordMetaStream},
+    BgpStreamError,
+};
+
+/// The name of the collector of an element yielded by [`merge_streams`]. Tags are cheap to clone,
+/// as all elements of a collector share the same name.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CollectorTag(Arc<str>);
+
+impl CollectorTag {
+    /// The name of the collector as reported by libbgpstream (e.g., `route-views.amsix`).
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for CollectorTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Merge the elements of multiple streams into a single stream ordered by the element time (a
+/// k-way merge), and tag each element with the name of its collector. Elements with the same time
+/// are ordered by the collector name, and then by the position of their stream in `streams`.
+/// Elements of the same stream keep their relative order.
+///
+/// The merge only holds one element per stream in memory, so it relies on each stream being
+/// ordered by time. libbgpstream yields records in time order, but the elements of a record (and
+/// of a RIB dump in particular) may be slightly out of order. Use [`crate::Query::reorder_window`]
+/// on each query for a strictly ordered result.
+///
+/// Errors are yielded as soon as they occur, and the erroneous stream is polled again on the next
+/// call. The merged stream ends once all streams have ended.
+///
+/// ```no_run
+/// use routeviews::{merge::merge_streams, stream::*};
+///
+/// # fn main() -> Result<(), routeviews::BgpStreamError> {
+/// let streams = [RouteView::Amsix, RouteView::Linx]
+///     .into_iter()
+///     .map(|rv| {
+///         Query::new()
+///             .collector(Collector::RouteView(rv))
+///             .record_type(RecordType::Updates)
+///             .run()
+///     })
+///     .collect::<Result<Vec<_>, _>>()?;
+///
+/// for item in merge_streams(streams) {
+///     let (collector, element) = item?;
+///     println!("{} {collector}: {:?}", element.time, element.prefix());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn merge_streams(streams: Vec<BgpStream>) -> MergedStream {
+    let sources: Vec<Source> = streams
+        .into_iter()
+        .map(|stream| Source {
+            stream: stream.with_record_meta(),
+            tag: None,
+            head: None,
+        })
+        .collect();
+    MergedStream {
+        to_fill: (0..sources.len()).rev().collect(),
+        sources,
+        heap: BinaryHeap::new(),
+    }
+}
+
+/// Iterator over the merged elements of multiple streams, returned by [`merge_streams`].
+pub struct MergedStream {
+    sources: Vec<Source>,
+    // index of all sources whose next element must be fetched before yielding the next element.
+    to_fill: Vec<usize>,
+    // time, collector, and index of the source of each buffered element.
+    heap: BinaryHeap<Reverse<(OffsetDateTime, CollectorTag, usize)>>,
+}
+
+struct Source {
+    stream: RecordMetaStream,
+    // tag of the last element, reused as long as the collector stays the same.
+    tag: Option<CollectorTag>,
+    // next element of the stream, if it was already fetched.
+    head: Option<(CollectorTag, Element)>,
+}
+
+impl Source {
+    fn fetch(&mut self) -> Option<Result<(), BgpStreamError>> {
+        let (meta, element) = match self.stream.next()? {
+            Ok(x) => x,
+            Err(e) => return Some(Err(e)),
+        };
+        let tag = match &self.tag {
+            Some(tag) if tag.as_str() == meta.collector_name => tag.clone(),
+            _ => {
+                let tag = CollectorTag(meta.collector_name.as_str().into());
+                self.tag = Some(tag.clone());
+                tag
+            }
+        };
+        self.head = Some((tag, element));
+        Some(Ok(()))
+    }
+}
+
+impl MergedStream {
+    /// Get back the underlying streams, in the order in which they were passed to
+    /// [`merge_streams`]. Elements that were already fetched but not yet yielded are lost.
+    pub fn into_inner(self) -> Vec<BgpStream> {
+        self.sources
+            .into_iter()
+            .map(|source| source.stream.into_inner())
+            .collect()
+    }
+}
+
+impl Iterator for MergedStream {
+    type Item = Result<(CollectorTag, Element), BgpStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(i) = self.to_fill.pop() {
+            match self.sources[i].fetch() {
+                Some(Ok(())) => {
+                    let (tag, element) = self.sources[i].head.as_ref().expect("just fetched");
+                    self.heap.push(Reverse((element.time, tag.clone(), i)));
+                }
+                Some(Err(e)) => {
+                    self.to_fill.push(i);
+                    return Some(Err(e));
+                }
+                None => {}
+            }
+        }
+        let Reverse((_, _, i)) = self.heap.pop()?;
+        self.to_fill.push(i);
+        self.sources[i].head.take().map(Ok)
+    }
+}
@@ -0,0 +1,33 @@
+//! Operational metrics of a running [`crate::stream::BgpStream`], exported through the
+//! [`metrics`] facade. Enable them with [`crate::stream::BgpStream::with_metrics`].
+
+use metrics::{counter, gauge};
+use time::OffsetDateTime;
+
+use crate::{
+    element::{Element, ElementType},
+    BgpStreamError,
+};
+
+/// Called whenever the stream fetches a new record.
+pub(crate) fn record_fetched(prefix: &str, time: OffsetDateTime) {
+    counter!(format!("{prefix}_records_total")).increment(1);
+    let lag = OffsetDateTime::now_utc() - time;
+    gauge!(format!("{prefix}_lag_seconds")).set(lag.as_seconds_f64());
+}
+
+/// Called whenever the stream yields an element.
+pub(crate) fn element_yielded(prefix: &str, element: &Element) {
+    let kind = match element.e {
+        ElementType::RIB(_) => "rib",
+        ElementType::Announcement(_) => "announcement",
+        ElementType::Withdrawal(_) => "withdrawal",
+        ElementType::PeerState { .. } => "peerstate",
+    };
+    counter!(format!("{prefix}_elements_total"), "type" => kind).increment(1);
+}
+
+/// Called whenever the stream yields an error.
+pub(crate) fn error_yielded(prefix: &str, error: &BgpStreamError) {
+    counter!(format!("{prefix}_errors_total"), "error" => error.name()).increment(1);
+}
@@ -0,0 +1,60 @@
+//! Integrity check of local MRT files (RFC 6396).
+//!
+//! Depending on its version, libbgpstream reports a file whose last record is cut off as a regular
+//! end of the file. Therefore, the stream walks the MRT headers of each local file once it reached
+//! the end, and reports a file that does not end exactly after a complete record.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// Length of the MRT common header: timestamp (4 bytes), type (2), subtype (2), and length (4).
+const HEADER_LEN: u64 = 12;
+
+/// Return the offset of the first record in the file at `path` that extends past the end of the
+/// file, or `None` if the file ends exactly after a complete record. Compressed files (gzip or
+/// bzip2) are not checked, as they are read by libbgpstream directly.
+pub(crate) fn truncated_at(path: &Path) -> io::Result<Option<u64>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut magic = Vec::with_capacity(10);
+    (&mut file).take(10).read_to_end(&mut magic)?;
+    if is_compressed(&magic) {
+        return Ok(None);
+    }
+    file.seek(SeekFrom::Start(0))?;
+
+    let mut file = BufReader::new(file);
+    let mut header = [0; HEADER_LEN as usize];
+    let mut offset = 0;
+    while offset < len {
+        if len - offset < HEADER_LEN {
+            return Ok(Some(offset));
+        }
+        file.read_exact(&mut header)?;
+        let body = u32::from_be_bytes([header[8], header[9], header[10], header[11]]) as u64;
+        if len - offset - HEADER_LEN < body {
+            return Ok(Some(offset));
+        }
+        file.seek_relative(body as i64)?;
+        offset += HEADER_LEN + body;
+    }
+    Ok(None)
+}
+
+/// Check the magic bytes of gzip (including the compression method) and bzip2 (including the
+/// block size and the magic of the first block or the end of the stream). MRT files do not start
+/// with these bytes in practice, as they would encode a timestamp in 1986 or 2005, respectively
+/// (the latter followed by an unassigned MRT type).
+fn is_compressed(magic: &[u8]) -> bool {
+    let bzip2_block = |m: &[u8]| {
+        m == [0x31, 0x41, 0x59, 0x26, 0x53, 0x59] || m == [0x17, 0x72, 0x45, 0x38, 0x50, 0x90]
+    };
+    match magic {
+        [0x1f, 0x8b, 0x08, ..] => true,
+        [b'B', b'Z', b'h', b'1'..=b'9', block @ ..] => bzip2_block(block),
+        _ => false,
+    }
+}
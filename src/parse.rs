@@ -0,0 +1,307 @@
+//! Parsing of the libbgpstream data structures into the types of [`crate::element`].
+
+use std::{
+    ffi::{CString, IntoStringError},
+    net::IpAddr,
+    ptr::{addr_of, null_mut},
+    slice,
+};
+
+use ipnet::IpNet;
+use libbgpstream_sys::{
+    bgpstream_addr_version_t_BGPSTREAM_ADDR_VERSION_IPV4 as ADDR_VERSION_IPV4,
+    bgpstream_addr_version_t_BGPSTREAM_ADDR_VERSION_IPV6 as ADDR_VERSION_IPV6,
+    bgpstream_as_path_get_next_seg, bgpstream_as_path_iter, bgpstream_as_path_iter_reset,
+    bgpstream_as_path_seg_t,
+    bgpstream_as_path_seg_type_t_BGPSTREAM_AS_PATH_SEG_ASN as AS_PATH_SEG_ASN,
+    bgpstream_community_set_get, bgpstream_community_set_size,
+    bgpstream_elem_origin_type_t_BGPSTREAM_ELEM_BGP_UPDATE_ORIGIN_EGP as ORIGIN_EGP,
+    bgpstream_elem_origin_type_t_BGPSTREAM_ELEM_BGP_UPDATE_ORIGIN_IGP as ORIGIN_IGP,
+    bgpstream_elem_origin_type_t_BGPSTREAM_ELEM_BGP_UPDATE_ORIGIN_INCOMPLETE as ORIGIN_INCOMPLETE,
+    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_ACTIVE as ELEM_PEERSTATE_ACTIVE,
+    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_CLEARING as ELEM_PEERSTATE_CLEARING,
+    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_CONNECT as ELEM_PEERSTATE_CONNECT,
+    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_DELETED as ELEM_PEERSTATE_DELETED,
+    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_ESTABLISHED as ELEM_PEERSTATE_ESTABLISHED,
+    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_IDLE as ELEM_PEERSTATE_IDLE,
+    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_OPENCONFIRM as ELEM_PEERSTATE_OPENCONFIRM,
+    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_OPENSENT as ELEM_PEERSTATE_OPENSENT,
+    bgpstream_elem_peerstate_t_BGPSTREAM_ELEM_PEERSTATE_UNKNOWN as ELEM_PEERSTATE_UNKNOWN,
+    bgpstream_elem_t,
+    bgpstream_elem_type_t_BGPSTREAM_ELEM_TYPE_ANNOUNCEMENT as ELEM_TYPE_ANNOUNCEMENT,
+    bgpstream_elem_type_t_BGPSTREAM_ELEM_TYPE_PEERSTATE as ELEM_TYPE_PEERSTATE,
+    bgpstream_elem_type_t_BGPSTREAM_ELEM_TYPE_RIB as ELEM_TYPE_RIB,
+    bgpstream_elem_type_t_BGPSTREAM_ELEM_TYPE_WITHDRAWAL as ELEM_TYPE_WITHDRAWAL,
+    bgpstream_record_get_next_elem, union_bgpstream_ip_addr_t, union_bgpstream_pfx_t,
+};
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    element::{AsSegment, Element, ElementType, OriginType, ParseOptions, PeerState, Update},
+    record::Record,
+    BgpStreamError,
+};
+
+/// Convert a fixed-size C string buffer (see [`array_to_bytes`]) into a `String`. If the bytes are
+/// not valid UTF-8, the error contains the raw bytes (see [`IntoStringError::into_cstring`]) and
+/// the position of the invalid sequence (see [`IntoStringError::utf8_error`]).
+pub(crate) fn array_to_string(array: &[i8]) -> Result<String, IntoStringError> {
+    // cannot fail, as `array_to_bytes` stops at the first null byte.
+    let s = CString::new(array_to_bytes(array)).expect("no interior null byte");
+    s.into_string()
+}
+
+/// Like [`array_to_string`], but replaces invalid UTF-8 sequences with `U+FFFD`.
+pub(crate) fn array_to_string_lossy(array: &[i8]) -> String {
+    String::from_utf8_lossy(array_to_bytes(array)).into_owned()
+}
+
+/// The bytes of a fixed-size C string buffer, up to the first null byte. libbgpstream always
+/// terminates its strings, but if the buffer contains no null byte (e.g., a name that fills the
+/// buffer exactly), all of its bytes are returned instead of reading past its end.
+pub(crate) fn array_to_bytes(array: &[i8]) -> &[u8] {
+    let s: &[u8] = unsafe { slice::from_raw_parts(array.as_ptr() as *const u8, array.len()) };
+    let null_pos = s.iter().position(|x| *x == 0).unwrap_or(s.len());
+    &s[..null_pos]
+}
+
+/// Convert an address of libbgpstream. libbgpstream only stores the address bytes, and no scope ID
+/// for IPv6 link-local addresses, so the result never carries a scope.
+pub(crate) unsafe fn parse_bgpstream_ip(
+    ip: union_bgpstream_ip_addr_t,
+) -> Result<IpAddr, BgpStreamError> {
+    let version = ip.__bindgen_anon_1.__bindgen_anon_1.version;
+    match version {
+        ADDR_VERSION_IPV4 => Ok(IpAddr::V4(ip.bs_ipv4.addr.s_addr.into())),
+        ADDR_VERSION_IPV6 => Ok(IpAddr::V6(ip.bs_ipv6.addr.__in6_u.__u6_addr8.into())),
+        _ => Err(BgpStreamError::InvalidIpAddress),
+    }
+}
+
+pub(crate) unsafe fn parse_bgpstream_prefix(
+    prefix: union_bgpstream_pfx_t,
+) -> Result<IpNet, BgpStreamError> {
+    let prefix_len = prefix
+        .__bindgen_anon_1
+        .__bindgen_anon_1
+        .__bindgen_anon_1
+        .mask_len;
+    let ip = parse_bgpstream_ip(prefix.__bindgen_anon_1.address)?;
+    Ok(IpNet::new(ip, prefix_len)?)
+}
+
+impl Element {
+    pub(crate) fn new(record: &mut Record<'_>) -> Result<Option<Element>, BgpStreamError> {
+        unsafe {
+            let Some(p_elem) = next_elem(record)? else {
+                return Ok(None);
+            };
+            parse_elem(p_elem, record.time, record.parse_options).map(Some)
+        }
+    }
+}
+
+/// Fetch the next element of the record, or `None` if the record is exhausted.
+pub(crate) unsafe fn next_elem(
+    record: &mut Record<'_>,
+) -> Result<Option<*mut bgpstream_elem_t>, BgpStreamError> {
+    let mut p_elem = null_mut::<bgpstream_elem_t>();
+    let p_p_elem: *mut *mut bgpstream_elem_t = &mut p_elem;
+    let res = bgpstream_record_get_next_elem(record.p_record, p_p_elem);
+
+    match res {
+        1 => {}
+        0 => return Ok(None),
+        _ => return Err(BgpStreamError::GetNextElement),
+    }
+
+    // check that p_record is non-null
+    if p_elem.is_null() {
+        return Err(BgpStreamError::GetNextElementNull);
+    };
+
+    Ok(Some(p_elem))
+}
+
+/// Get the time of an element, falling back to the time of its record.
+pub(crate) unsafe fn elem_time(
+    elem: &bgpstream_elem_t,
+    record_time: OffsetDateTime,
+) -> Result<OffsetDateTime, BgpStreamError> {
+    if elem.orig_time_sec == 0 {
+        Ok(record_time)
+    } else {
+        let secs = elem.orig_time_sec;
+        let micros = elem.orig_time_usec;
+        // a corrupted record may carry a timestamp at the very end of the supported range.
+        OffsetDateTime::from_unix_timestamp(secs as i64)?
+            .checked_add(Duration::microseconds(micros as i64))
+            .ok_or(BgpStreamError::RecordCorrupted)
+    }
+}
+
+/// Parse an element into an owned [`Element`].
+pub(crate) unsafe fn parse_elem(
+    p_elem: *mut bgpstream_elem_t,
+    record_time: OffsetDateTime,
+    options: ParseOptions,
+) -> Result<Element, BgpStreamError> {
+    let elem = &*p_elem;
+
+    let time = elem_time(elem, record_time)?;
+    let peer_ip = parse_bgpstream_ip(elem.peer_ip)?;
+    let peer_asn = elem.peer_asn;
+
+    let e = match elem.type_ {
+        ELEM_TYPE_ANNOUNCEMENT | ELEM_TYPE_RIB => {
+            let update = Update {
+                prefix: parse_bgpstream_prefix(elem.prefix)?,
+                next_hop: parse_bgpstream_ip(elem.nexthop)?,
+                as_path: if options.as_path {
+                    extract_as_path(p_elem)?
+                } else {
+                    Vec::new()
+                },
+                communities: if options.communities {
+                    extract_communities(p_elem)
+                } else {
+                    Vec::new()
+                },
+                origin_type: if elem.has_origin != 0 {
+                    Some(elem.origin.try_into()?)
+                } else {
+                    None
+                },
+                med: if elem.has_med != 0 {
+                    Some(elem.med)
+                } else {
+                    None
+                },
+                local_pref: if elem.has_local_pref != 0 {
+                    Some(elem.local_pref)
+                } else {
+                    None
+                },
+            };
+
+            if elem.type_ == ELEM_TYPE_ANNOUNCEMENT {
+                ElementType::Announcement(update)
+            } else {
+                ElementType::RIB(update)
+            }
+        }
+        ELEM_TYPE_PEERSTATE => ElementType::PeerState {
+            from: elem.old_state.try_into()?,
+            to: elem.new_state.try_into()?,
+        },
+        ELEM_TYPE_WITHDRAWAL => ElementType::Withdrawal(parse_bgpstream_prefix(elem.prefix)?),
+        t => return Err(BgpStreamError::UnknownElementType(t)),
+    };
+
+    Ok(Element {
+        time,
+        peer_ip,
+        peer_asn,
+        e,
+    })
+}
+
+impl TryFrom<u32> for PeerState {
+    type Error = BgpStreamError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            ELEM_PEERSTATE_ACTIVE => Ok(Self::Active),
+            ELEM_PEERSTATE_CLEARING => Ok(Self::Clearing),
+            ELEM_PEERSTATE_CONNECT => Ok(Self::Connect),
+            ELEM_PEERSTATE_DELETED => Ok(Self::Deleted),
+            ELEM_PEERSTATE_ESTABLISHED => Ok(Self::Established),
+            ELEM_PEERSTATE_IDLE => Ok(Self::Idle),
+            ELEM_PEERSTATE_OPENCONFIRM => Ok(Self::OpenConfirm),
+            ELEM_PEERSTATE_OPENSENT => Ok(Self::OpenSent),
+            ELEM_PEERSTATE_UNKNOWN => Ok(Self::Unknown),
+            _ => Err(BgpStreamError::UnknownPeerState(value)),
+        }
+    }
+}
+
+impl TryFrom<u32> for OriginType {
+    type Error = BgpStreamError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            ORIGIN_EGP => Ok(Self::Egp),
+            ORIGIN_IGP => Ok(Self::Igp),
+            ORIGIN_INCOMPLETE => Ok(Self::Incomplete),
+            _ => Err(BgpStreamError::UnknownOriginType(value)),
+        }
+    }
+}
+
+unsafe fn extract_as_path(p_elem: *mut bgpstream_elem_t) -> Result<Vec<AsSegment>, BgpStreamError> {
+    let mut iter = bgpstream_as_path_iter { cur_offset: 0 };
+    let mut path: Vec<AsSegment> = Vec::new();
+    let elem = &*p_elem;
+    let iter = &mut iter as *mut bgpstream_as_path_iter;
+    // reset the iterator
+    bgpstream_as_path_iter_reset(iter);
+
+    loop {
+        let seg = bgpstream_as_path_get_next_seg(elem.as_path, iter);
+        if seg.is_null() {
+            break;
+        }
+        path.push(parse_as_path_seg(seg)?);
+    }
+
+    Ok(path)
+}
+
+unsafe fn extract_communities(p_elem: *mut bgpstream_elem_t) -> Vec<(u16, u16)> {
+    let mut communities = Vec::new();
+    let elem = &*p_elem;
+    if elem.communities.is_null() {
+        return communities;
+    }
+
+    // never read past the end of the set, even if an entry is missing.
+    for i in 0..bgpstream_community_set_size(elem.communities) {
+        let comm = bgpstream_community_set_get(elem.communities, i);
+        if comm.is_null() {
+            break;
+        }
+        let comm = &*comm;
+        let asn = comm.__bindgen_anon_1.__bindgen_anon_1.asn;
+        let value = comm.__bindgen_anon_1.__bindgen_anon_1.value;
+        communities.push((asn, value))
+    }
+
+    communities
+}
+
+/// Upper bound on the number of ASNs in a single AS set. AS sets created by aggregation
+/// realistically hold a handful of ASNs; a larger count indicates a corrupted record.
+const MAX_AS_SET_LEN: usize = 128;
+
+pub(crate) unsafe fn parse_as_path_seg(
+    seg: *mut bgpstream_as_path_seg_t,
+) -> Result<AsSegment, BgpStreamError> {
+    let seg = &*seg;
+    if *seg.__bindgen_anon_1.type_.as_ref() == AS_PATH_SEG_ASN as u8 {
+        // single AS number
+        Ok(AsSegment::Num(seg.__bindgen_anon_1.asn.as_ref().asn))
+    } else {
+        // AS set or confederation segment. The count is taken from the record, so don't trust it
+        // for reading memory.
+        let set = seg.__bindgen_anon_1.set.as_ref();
+        let len = set.asn_cnt as usize;
+        if len > MAX_AS_SET_LEN {
+            return Err(BgpStreamError::RecordCorrupted);
+        }
+        let slice_base = addr_of!(set.asn) as *const u32;
+        let list = (0..len)
+            .map(|i| std::ptr::read_unaligned(slice_base.add(i)))
+            .collect();
+        Ok(AsSegment::Set(list))
+    }
+}
@@ -1,7 +1,20 @@
-use std::{ffi::IntoStringError, marker::PhantomData, net::IpAddr, ptr::null_mut, time::Duration};
+use std::{
+    collections::HashMap, ffi::IntoStringError, marker::PhantomData, net::IpAddr, ptr::null_mut,
+    time::Duration,
+};
+
+use ipnet::IpNet;
 
 use libbgpstream_sys::{
-    bgpstream_get_next_record,
+    bgpstream_as_path_get_next_seg, bgpstream_as_path_iter, bgpstream_as_path_iter_reset,
+    bgpstream_as_path_t, bgpstream_community_set_get, bgpstream_community_set_size,
+    bgpstream_dump_position_t_BGPSTREAM_DUMP_END as DUMP_END,
+    bgpstream_dump_position_t_BGPSTREAM_DUMP_START as DUMP_START, bgpstream_elem_t,
+    bgpstream_elem_type_t_BGPSTREAM_ELEM_TYPE_ANNOUNCEMENT as ELEM_TYPE_ANNOUNCEMENT,
+    bgpstream_elem_type_t_BGPSTREAM_ELEM_TYPE_PEERSTATE as ELEM_TYPE_PEERSTATE,
+    bgpstream_elem_type_t_BGPSTREAM_ELEM_TYPE_RIB as ELEM_TYPE_RIB,
+    bgpstream_elem_type_t_BGPSTREAM_ELEM_TYPE_WITHDRAWAL as ELEM_TYPE_WITHDRAWAL,
+    bgpstream_get_next_record, bgpstream_record_get_next_elem,
     bgpstream_record_status_t_BGPSTREAM_RECORD_STATUS_CORRUPTED_RECORD as RECORD_STATUS_CORRUPTED_RECORD,
     bgpstream_record_status_t_BGPSTREAM_RECORD_STATUS_CORRUPTED_SOURCE as RECORD_STATUS_CORRUPTED_SOURCE,
     bgpstream_record_status_t_BGPSTREAM_RECORD_STATUS_EMPTY_SOURCE as RECORD_STATUS_EMPTY_SOURCE,
@@ -15,78 +28,477 @@ use libbgpstream_sys::{
 use time::OffsetDateTime;
 
 use crate::{
-    array_to_string, element::Element, parse_bgpstream_ip, stream::BgpStream, BgpStreamError,
+    element::{AsSegment, AttributeFlags, Element, OriginType, ParseOptions, PeerState},
+    parse::{
+        array_to_string, array_to_string_lossy, elem_time, next_elem, parse_as_path_seg,
+        parse_bgpstream_ip, parse_bgpstream_prefix, parse_elem,
+    },
+    stream::{BgpStream, ElementTypeDescr, RecordType},
+    BgpStreamError,
 };
 
+/// A record fetched from a [`BgpStream`]. The record mutably borrows the stream, as libbgpstream
+/// reuses the same memory for each record. Hence, there can only ever exist a single record per
+/// stream, and the record must be dropped before fetching the next one.
+///
+/// Records are single-pass: libbgpstream decodes the elements of a record one by one and cannot
+/// rewind, so each element can be read only once (using [`Record::next_element`] or the
+/// `Iterator` implementation). To process the elements multiple times, buffer them with
+/// [`Record::collect_elements`].
+///
+/// # Peer index table
+///
+/// `TABLE_DUMP_V2` RIB dumps start with a `PEER_INDEX_TABLE` that lists all peers of the dump.
+/// libbgpstream consumes this table internally while decoding the RIB entries, and does not expose
+/// it through its public API. Hence, there is no way to get the peers of a RIB dump before
+/// iterating over its entries. As a workaround, read the dump in two passes: first, collect the
+/// peers with [`crate::stream::BgpStream::track_peers`] (using [`ParseOptions`] that skip the AS
+/// path and communities to make the pass cheap), and then run the query again to build the
+/// per-peer structures.
 pub struct Record<'a> {
     pub(crate) p_record: *mut bgpstream_record_t,
     pub(crate) time: OffsetDateTime,
-    _phantom: PhantomData<&'a ()>,
+    pub(crate) record_type: RecordType,
+    pub(crate) parse_options: ParseOptions,
+    pub(crate) tolerate_element_errors: bool,
+    _phantom: PhantomData<&'a mut BgpStream>,
 }
 
-impl<'a> Record<'a> {
-    pub(crate) fn new(record: &'a mut BgpStream) -> Result<Option<Record<'a>>, BgpStreamError> {
-        unsafe {
-            let mut p_record = null_mut::<bgpstream_record_t>();
-            let p_p_record: *mut *mut bgpstream_record_t = &mut p_record;
-            let res = bgpstream_get_next_record(record.bs.as_ptr(), p_p_record);
-            if res == 0 {
-                return Ok(None);
-            } else if res.is_negative() {
-                return Err(BgpStreamError::GetNextRecord);
-            }
+/// A borrowed view of an element of a [`Record`], obtained with [`Record::next_element_ref`]. In
+/// contrast to [`Element`], the fields are parsed from libbgpstream only when they are accessed,
+/// and the AS path and communities are iterated without allocating. The view is only valid until
+/// the next element is fetched from the record.
+pub struct ElementRef<'a> {
+    p_elem: *mut bgpstream_elem_t,
+    record_time: OffsetDateTime,
+    _phantom: PhantomData<&'a bgpstream_elem_t>,
+}
 
-            // check that p_record is non-null
-            if p_record.is_null() {
-                return Err(BgpStreamError::GetNextRecordNull);
-            };
-            // get a reference to the record
-            let record = &*p_record;
-
-            // check the record
-            match record.status {
-                RECORD_STATUS_VALID_RECORD => {}
-                RECORD_STATUS_FILTERED_SOURCE
-                | RECORD_STATUS_EMPTY_SOURCE
-                | RECORD_STATUS_CORRUPTED_SOURCE => {
-                    return Err(BgpStreamError::RecordSourceEmptyOrCorrupted)
-                }
-                RECORD_STATUS_OUTSIDE_TIME_INTERVAL => return Ok(None),
-                RECORD_STATUS_CORRUPTED_RECORD => return Err(BgpStreamError::RecordCorrupted),
-                RECORD_STATUS_UNSUPPORTED_RECORD => return Err(BgpStreamError::RecordUnsupported),
-                _ => return Err(BgpStreamError::UnknownRecordStatus),
+impl ElementRef<'_> {
+    fn elem(&self) -> &bgpstream_elem_t {
+        unsafe { &*self.p_elem }
+    }
+
+    /// Whether the element is a RIB entry or an announcement.
+    fn is_update(&self) -> bool {
+        matches!(self.elem().type_, ELEM_TYPE_RIB | ELEM_TYPE_ANNOUNCEMENT)
+    }
+
+    /// The type of the element.
+    pub fn element_type(&self) -> Result<ElementTypeDescr, BgpStreamError> {
+        match self.elem().type_ {
+            ELEM_TYPE_RIB => Ok(ElementTypeDescr::RIBs),
+            ELEM_TYPE_ANNOUNCEMENT => Ok(ElementTypeDescr::Announcements),
+            ELEM_TYPE_WITHDRAWAL => Ok(ElementTypeDescr::Withdrawals),
+            ELEM_TYPE_PEERSTATE => Ok(ElementTypeDescr::PeerStates),
+            t => Err(BgpStreamError::UnknownElementType(t)),
+        }
+    }
+
+    /// The time of the element (see [`Element::time`]).
+    pub fn time(&self) -> Result<OffsetDateTime, BgpStreamError> {
+        unsafe { elem_time(self.elem(), self.record_time) }
+    }
+
+    pub fn peer_ip(&self) -> Result<IpAddr, BgpStreamError> {
+        unsafe { parse_bgpstream_ip(self.elem().peer_ip) }
+    }
+
+    pub fn peer_asn(&self) -> u32 {
+        self.elem().peer_asn
+    }
+
+    /// The prefix of a RIB entry, announcement or withdrawal, and `None` for peer state changes.
+    pub fn prefix(&self) -> Result<Option<IpNet>, BgpStreamError> {
+        if self.elem().type_ == ELEM_TYPE_PEERSTATE {
+            return Ok(None);
+        }
+        unsafe { parse_bgpstream_prefix(self.elem().prefix).map(Some) }
+    }
+
+    /// The next hop of a RIB entry or an announcement, and `None` otherwise.
+    pub fn next_hop(&self) -> Result<Option<IpAddr>, BgpStreamError> {
+        if !self.is_update() {
+            return Ok(None);
+        }
+        unsafe { parse_bgpstream_ip(self.elem().nexthop).map(Some) }
+    }
+
+    /// Iterate over the segments of the AS path. The iterator is empty for withdrawals and peer
+    /// state changes. Only AS sets allocate. An AS set with an implausibly large number of ASNs
+    /// yields [`BgpStreamError::RecordCorrupted`] and ends the iteration.
+    pub fn as_path(&self) -> AsPathIter<'_> {
+        let mut iter = bgpstream_as_path_iter { cur_offset: 0 };
+        unsafe { bgpstream_as_path_iter_reset(&mut iter) };
+        AsPathIter {
+            path: if self.is_update() {
+                self.elem().as_path
+            } else {
+                null_mut()
+            },
+            iter,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Iterate over the communities as `(asn, value)` pairs. The iterator is empty for withdrawals
+    /// and peer state changes.
+    pub fn communities(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        let set = self.elem().communities;
+        let n = if self.is_update() && !set.is_null() {
+            unsafe { bgpstream_community_set_size(set) }
+        } else {
+            0
+        };
+        (0..n).map_while(move |i| unsafe {
+            let comm = bgpstream_community_set_get(set, i);
+            if comm.is_null() {
+                return None;
             }
+            let comm = &(*comm).__bindgen_anon_1.__bindgen_anon_1;
+            Some((comm.asn, comm.value))
+        })
+    }
+
+    /// The origin type of a RIB entry or an announcement, if present.
+    pub fn origin_type(&self) -> Result<Option<OriginType>, BgpStreamError> {
+        if !self.is_update() || self.elem().has_origin == 0 {
+            return Ok(None);
+        }
+        self.elem().origin.try_into().map(Some)
+    }
 
-            // compute the time
-            let secs = record.time_sec;
-            let micros = record.time_usec;
+    /// The multi-exit discriminator of a RIB entry or an announcement, if present.
+    pub fn med(&self) -> Option<u32> {
+        (self.is_update() && self.elem().has_med != 0).then_some(self.elem().med)
+    }
 
-            let time = OffsetDateTime::from_unix_timestamp(secs as i64)?
-                + Duration::from_micros(micros as u64);
+    /// The local preference of a RIB entry or an announcement, if present.
+    pub fn local_pref(&self) -> Option<u32> {
+        (self.is_update() && self.elem().has_local_pref != 0).then_some(self.elem().local_pref)
+    }
 
-            Ok(Some(Record {
-                p_record,
-                _phantom: PhantomData,
-                time,
-            }))
+    /// Whether the route was aggregated, i.e., whether it carries the `ATOMIC_AGGREGATE`
+    /// attribute. Always `false` for withdrawals and peer state changes.
+    pub fn atomic_aggregate(&self) -> bool {
+        self.is_update() && self.elem().atomic_aggregate != 0
+    }
+
+    /// The AS number and IP address of the router that aggregated the route (the `AGGREGATOR`
+    /// attribute), if present.
+    pub fn aggregator(&self) -> Result<Option<(u32, IpAddr)>, BgpStreamError> {
+        if !self.is_update() || self.elem().has_aggregator == 0 {
+            return Ok(None);
+        }
+        let aggregator = &self.elem().aggregator;
+        let addr = unsafe { parse_bgpstream_ip(aggregator.aggregator_addr) }?;
+        Ok(Some((aggregator.aggregator_asn, addr)))
+    }
+
+    /// Which path attributes the element carries. All flags are `false` for withdrawals and peer
+    /// state changes.
+    pub fn attribute_flags(&self) -> AttributeFlags {
+        if !self.is_update() {
+            return AttributeFlags::default();
+        }
+        let elem = self.elem();
+        AttributeFlags {
+            origin: elem.has_origin != 0,
+            med: elem.has_med != 0,
+            local_pref: elem.has_local_pref != 0,
+            communities: self.communities().next().is_some(),
+            atomic_aggregate: elem.atomic_aggregate != 0,
+            aggregator: elem.has_aggregator != 0,
         }
     }
 
-    pub fn is_update(&self) -> Result<bool, BgpStreamError> {
+    /// The old and the new state of a peer state change, and `None` otherwise.
+    pub fn peer_states(&self) -> Result<Option<(PeerState, PeerState)>, BgpStreamError> {
+        if self.elem().type_ != ELEM_TYPE_PEERSTATE {
+            return Ok(None);
+        }
+        let elem = self.elem();
+        Ok(Some((
+            elem.old_state.try_into()?,
+            elem.new_state.try_into()?,
+        )))
+    }
+
+    /// Parse the element into an owned [`Element`], including all fields.
+    pub fn to_element(&self) -> Result<Element, BgpStreamError> {
+        self.to_element_with(ParseOptions::default())
+    }
+
+    /// Parse the element into an owned [`Element`], extracting only the fields selected by
+    /// `options`.
+    pub(crate) fn to_element_with(&self, options: ParseOptions) -> Result<Element, BgpStreamError> {
+        unsafe { parse_elem(self.p_elem, self.record_time, options) }
+    }
+}
+
+/// Iterator over the segments of an AS path, returned by [`ElementRef::as_path`].
+pub struct AsPathIter<'a> {
+    path: *mut bgpstream_as_path_t,
+    iter: bgpstream_as_path_iter,
+    _phantom: PhantomData<&'a bgpstream_elem_t>,
+}
+
+impl Iterator for AsPathIter<'_> {
+    type Item = Result<AsSegment, BgpStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.path.is_null() {
+            return None;
+        }
         unsafe {
-            let record = &*self.p_record;
-            match record.type_ {
-                BGPSTREAM_UPDATE => Ok(true),
-                BGPSTREAM_RIB => Ok(false),
-                _ => Err(BgpStreamError::RecordCorrupted),
+            let seg = bgpstream_as_path_get_next_seg(self.path, &mut self.iter);
+            if seg.is_null() {
+                return None;
+            }
+            let result = parse_as_path_seg(seg);
+            if result.is_err() {
+                // the remaining segments cannot be trusted either.
+                self.path = null_mut();
             }
+            Some(result)
         }
     }
+}
+
+/// A record together with all of its elements, which does not borrow the stream it was fetched
+/// from. Obtain it with [`Record::into_owned`], or iterate over owned records with
+/// [`BgpStream::into_records`].
+#[derive(Debug, Clone)]
+pub struct OwnedRecord {
+    pub time: OffsetDateTime,
+    pub record_type: RecordType,
+    pub project_name: String,
+    pub collector_name: String,
+    pub router_name: String,
+    /// The IP address of the router, if the record contains one.
+    pub router_ip: Option<IpAddr>,
+    pub elements: Vec<Element>,
+}
+
+/// The metadata of a record, without its elements. It is computed once per record and shared
+/// among all of its elements by [`BgpStream::with_record_meta`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordMeta {
+    pub time: OffsetDateTime,
+    pub record_type: RecordType,
+    pub project_name: String,
+    pub collector_name: String,
+    pub router_name: String,
+    /// The IP address of the router, if the record contains one.
+    pub router_ip: Option<IpAddr>,
+}
+
+/// A record that does not borrow its stream. The `BgpStream` uses this to remember the current
+/// record while iterating over elements.
+#[derive(Clone, Copy)]
+pub(crate) struct RawRecord {
+    pub(crate) p_record: *mut bgpstream_record_t,
+    pub(crate) time: OffsetDateTime,
+    pub(crate) record_type: RecordType,
+    pub(crate) parse_options: ParseOptions,
+    pub(crate) tolerate_element_errors: bool,
+}
+
+/// The position of a record within its source (i.e., its dump file), returned by
+/// [`Record::dump_position`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DumpPosition {
+    /// The first record of the dump.
+    Start,
+    /// Any record between the first and the last one.
+    Middle,
+    /// The last record of the dump.
+    End,
+}
+
+/// Why libbgpstream skipped a record, reported by [`BgpStream::next_record_detailed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordStatus {
+    /// The source (i.e., a dump file) was excluded by the filters.
+    FilteredSource,
+    /// The source contains no data.
+    EmptySource,
+    /// The source cannot be read (e.g., a truncated dump file).
+    CorruptedSource,
+    /// The record lies outside of the time interval of the query.
+    OutsideTimeInterval,
+    /// The record is corrupted.
+    CorruptedRecord,
+    /// The record uses a format that libbgpstream does not support.
+    UnsupportedRecord,
+}
+
+/// The result of fetching a single record with [`BgpStream::next_record_detailed`].
+pub enum RecordOutcome<'a> {
+    /// A valid record.
+    Record(Record<'a>),
+    /// libbgpstream skipped a record or source. The stream continues with the next record.
+    Skipped(RecordStatus),
+    /// The stream has ended (or the limit was reached).
+    End,
+}
+
+/// Like [`RecordOutcome`], but without borrowing the stream.
+enum RawOutcome {
+    Record(RawRecord),
+    Skipped(RecordStatus),
+    End,
+}
+
+/// Fetch the next record from libbgpstream, without interpreting its status.
+fn fetch_raw(stream: &mut BgpStream) -> Result<RawOutcome, BgpStreamError> {
+    unsafe {
+        let mut p_record = null_mut::<bgpstream_record_t>();
+        let p_p_record: *mut *mut bgpstream_record_t = &mut p_record;
+        let res = bgpstream_get_next_record(stream.bs.as_ptr(), p_p_record);
+        if res == 0 {
+            stream.check_local_files()?;
+            return Ok(RawOutcome::End);
+        } else if res.is_negative() {
+            return Err(BgpStreamError::GetNextRecord);
+        }
+
+        // check that p_record is non-null
+        if p_record.is_null() {
+            return Err(BgpStreamError::GetNextRecordNull);
+        };
+
+        // check the record
+        let status = match (*p_record).status {
+            RECORD_STATUS_VALID_RECORD => None,
+            RECORD_STATUS_FILTERED_SOURCE => Some(RecordStatus::FilteredSource),
+            RECORD_STATUS_EMPTY_SOURCE => Some(RecordStatus::EmptySource),
+            RECORD_STATUS_CORRUPTED_SOURCE => Some(RecordStatus::CorruptedSource),
+            RECORD_STATUS_OUTSIDE_TIME_INTERVAL => Some(RecordStatus::OutsideTimeInterval),
+            RECORD_STATUS_CORRUPTED_RECORD => Some(RecordStatus::CorruptedRecord),
+            RECORD_STATUS_UNSUPPORTED_RECORD => Some(RecordStatus::UnsupportedRecord),
+            status => return Err(BgpStreamError::UnknownRecordStatus(status)),
+        };
+        if let Some(status) = status {
+            return Ok(RawOutcome::Skipped(status));
+        }
+        let record = &*p_record;
+
+        // check the record type
+        let record_type = match record.type_ {
+            BGPSTREAM_UPDATE => RecordType::Updates,
+            BGPSTREAM_RIB => RecordType::RIBs,
+            _ => return Err(BgpStreamError::RecordCorrupted),
+        };
+
+        // compute the time
+        let secs = record.time_sec;
+        let micros = record.time_usec;
+
+        let time = OffsetDateTime::from_unix_timestamp(secs as i64)?
+            + Duration::from_micros(micros as u64);
+        stream.last_record_time = Some(time);
+        stream.health.records_fetched += 1;
+
+        Ok(RawOutcome::Record(RawRecord {
+            p_record,
+            time,
+            record_type,
+            parse_options: stream.parse_options,
+            tolerate_element_errors: stream.tolerate_element_errors,
+        }))
+    }
+}
+
+impl<'a> Record<'a> {
+    pub(crate) fn new(stream: &'a mut BgpStream) -> Result<Option<Record<'a>>, BgpStreamError> {
+        let result = Self::fetch(stream);
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(Some(record)) => crate::trace::record_fetched(record),
+            Ok(None) => {}
+            Err(e) => crate::trace::error(e),
+        }
+        result
+    }
+
+    /// Fetch the next valid record from libbgpstream.
+    fn fetch(stream: &'a mut BgpStream) -> Result<Option<Record<'a>>, BgpStreamError> {
+        let raw = loop {
+            match fetch_raw(stream)? {
+                RawOutcome::Record(raw) => break raw,
+                RawOutcome::End => return Ok(None),
+                // the source was excluded by the filters, which is not an error.
+                RawOutcome::Skipped(RecordStatus::FilteredSource)
+                    if !stream.report_filtered_sources => {}
+                RawOutcome::Skipped(RecordStatus::FilteredSource) => {
+                    return Err(BgpStreamError::RecordSourceFiltered)
+                }
+                RawOutcome::Skipped(RecordStatus::EmptySource | RecordStatus::CorruptedSource) => {
+                    return Err(BgpStreamError::RecordSourceEmptyOrCorrupted)
+                }
+                // libbgpstream stops reading at the end of the interval, so check the local files
+                // as when reaching the end of the stream.
+                RawOutcome::Skipped(RecordStatus::OutsideTimeInterval) => {
+                    stream.check_local_files()?;
+                    return Ok(None);
+                }
+                RawOutcome::Skipped(RecordStatus::CorruptedRecord) => {
+                    return Err(BgpStreamError::RecordCorrupted)
+                }
+                RawOutcome::Skipped(RecordStatus::UnsupportedRecord) => {
+                    return Err(BgpStreamError::RecordUnsupported)
+                }
+            }
+        };
+        // safety: `raw` was just fetched from `stream`.
+        Ok(Some(unsafe { Self::from_raw(raw, stream) }))
+    }
+
+    /// Fetch the next record from libbgpstream, reporting invalid records with their status.
+    pub(crate) fn new_detailed(
+        stream: &'a mut BgpStream,
+    ) -> Result<RecordOutcome<'a>, BgpStreamError> {
+        let result = fetch_raw(stream).map(|outcome| match outcome {
+            // safety: `raw` was just fetched from `stream`.
+            RawOutcome::Record(raw) => {
+                RecordOutcome::Record(unsafe { Self::from_raw(raw, stream) })
+            }
+            RawOutcome::Skipped(status) => RecordOutcome::Skipped(status),
+            RawOutcome::End => RecordOutcome::End,
+        });
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(RecordOutcome::Record(record)) => crate::trace::record_fetched(record),
+            Ok(_) => {}
+            Err(e) => crate::trace::error(e),
+        }
+        result
+    }
+
+    /// Get the type of the record. The type is validated when fetching the record; records of any
+    /// other type are rejected with [`BgpStreamError::RecordCorrupted`].
+    pub fn record_type(&self) -> RecordType {
+        self.record_type
+    }
+
+    /// Whether the record contains updates.
+    pub fn is_update(&self) -> bool {
+        self.record_type == RecordType::Updates
+    }
+
+    /// Whether the record is part of a RIB dump.
+    pub fn is_rib(&self) -> bool {
+        self.record_type == RecordType::RIBs
+    }
 
     pub fn time(&self) -> OffsetDateTime {
         self.time
     }
 
+    /// The name of the project. Fails if the name is not valid UTF-8, in which case the error
+    /// contains the raw bytes (see [`IntoStringError::into_cstring`]). Use
+    /// [`Record::project_name_lossy`] to get the name regardless.
     pub fn project_name(&self) -> Result<String, IntoStringError> {
         unsafe {
             let record = &*self.p_record;
@@ -95,6 +507,15 @@ impl<'a> Record<'a> {
         }
     }
 
+    /// Like [`Record::project_name`], but replaces invalid UTF-8 sequences with `U+FFFD` instead of
+    /// failing.
+    pub fn project_name_lossy(&self) -> String {
+        unsafe { array_to_string_lossy(&(*self.p_record).project_name) }
+    }
+
+    /// The name of the collector. Fails if the name is not valid UTF-8, in which case the error
+    /// contains the raw bytes (see [`IntoStringError::into_cstring`]). Use
+    /// [`Record::collector_name_lossy`] to get the name regardless.
     pub fn collector_name(&self) -> Result<String, IntoStringError> {
         unsafe {
             let record = &*self.p_record;
@@ -103,6 +524,15 @@ impl<'a> Record<'a> {
         }
     }
 
+    /// Like [`Record::collector_name`], but replaces invalid UTF-8 sequences with `U+FFFD` instead
+    /// of failing.
+    pub fn collector_name_lossy(&self) -> String {
+        unsafe { array_to_string_lossy(&(*self.p_record).collector_name) }
+    }
+
+    /// The name of the router. Fails if the name is not valid UTF-8, in which case the error
+    /// contains the raw bytes (see [`IntoStringError::into_cstring`]). Use
+    /// [`Record::router_name_lossy`] to get the name regardless.
     pub fn router_name(&self) -> Result<String, IntoStringError> {
         unsafe {
             let record = &*self.p_record;
@@ -111,6 +541,12 @@ impl<'a> Record<'a> {
         }
     }
 
+    /// Like [`Record::router_name`], but replaces invalid UTF-8 sequences with `U+FFFD` instead of
+    /// failing.
+    pub fn router_name_lossy(&self) -> String {
+        unsafe { array_to_string_lossy(&(*self.p_record).router_name) }
+    }
+
     pub fn router_ip(&self) -> Result<IpAddr, BgpStreamError> {
         unsafe {
             let record = &*self.p_record;
@@ -118,18 +554,156 @@ impl<'a> Record<'a> {
         }
     }
 
+    /// The position of the record within its dump file. Use this to detect where one dump ends
+    /// and the next one begins, e.g., to group the records of a RIB dump.
+    ///
+    /// libbgpstream keeps the source of a record (the URL or path of the dump file, and the
+    /// offset within it) in its private state, and does not expose it through its public API, for
+    /// any data interface. Hence, the position is the only information about the source that is
+    /// available. For a query with a single local file (see [`crate::Query::local_file`]), all
+    /// records come from that file.
+    pub fn dump_position(&self) -> DumpPosition {
+        match unsafe { (*self.p_record).dump_pos } {
+            DUMP_START => DumpPosition::Start,
+            DUMP_END => DumpPosition::End,
+            _ => DumpPosition::Middle,
+        }
+    }
+
     /// Get the next element and return it.
+    ///
+    /// If the stream was created with [`crate::Query::tolerate_element_errors`], elements that
+    /// cannot be parsed are logged and skipped.
     pub fn next_element(&mut self) -> Result<Option<Element>, BgpStreamError> {
-        Element::new(self)
+        loop {
+            match Element::new(self) {
+                Err(e) if self.tolerate_element_errors && e.is_element_error() => {
+                    log_warn!("Skipping an element that cannot be parsed: {e}");
+                }
+                res => {
+                    #[cfg(feature = "tracing")]
+                    if let Err(e) = &res {
+                        crate::trace::error(e);
+                    }
+                    return res;
+                }
+            }
+        }
+    }
+
+    /// Get a borrowed view of the next element, without parsing it into an owned [`Element`].
+    /// Fields are only parsed when accessed, and the AS path and communities are iterated in place
+    /// without allocating. The view borrows the record, and must be dropped before fetching the
+    /// next element. Elements that cannot be parsed are not skipped, even if the stream was
+    /// created with [`crate::Query::tolerate_element_errors`].
+    pub fn next_element_ref(&mut self) -> Result<Option<ElementRef<'_>>, BgpStreamError> {
+        unsafe {
+            Ok(next_elem(self)?.map(|p_elem| ElementRef {
+                p_elem,
+                record_time: self.time,
+                _phantom: PhantomData,
+            }))
+        }
+    }
+
+    /// Parse all remaining elements of the record and return them, such that they can be processed
+    /// multiple times. Afterwards, [`Record::next_element`] will always return `Ok(None)`.
+    pub fn collect_elements(&mut self) -> Result<Vec<Element>, BgpStreamError> {
+        self.collect()
     }
 
-    /// Detach `self` to get a static Record.
+    /// Parse all remaining elements of the record and group them by prefix, e.g., to see all
+    /// announcements and withdrawals of the same prefix from different peers side by side. Within
+    /// each group, elements keep the order of the record. Peer state changes have no prefix and
+    /// are dropped.
     ///
-    /// **Safety**: Ensure that there only ever exists a single record for any `BgpStream`.
-    pub(crate) unsafe fn detach(self) -> Record<'static> {
-        Record {
+    /// Like [`Record::collect_elements`], this exhausts the record: afterwards,
+    /// [`Record::next_element`] will always return `Ok(None)`. Stops at the first error.
+    pub fn elements_by_prefix(&mut self) -> Result<HashMap<IpNet, Vec<Element>>, BgpStreamError> {
+        let mut groups: HashMap<IpNet, Vec<Element>> = HashMap::new();
+        while let Some(element) = self.next_element()? {
+            if let Some(prefix) = element.prefix() {
+                groups.entry(prefix).or_default().push(element);
+            }
+        }
+        Ok(groups)
+    }
+
+    /// Count the remaining elements of the record.
+    ///
+    /// **Warning**: This method is destructive! libbgpstream cannot report the number of elements
+    /// in a record without iterating over them, so counting exhausts the record. Afterwards,
+    /// [`Record::next_element`] will always return `Ok(None)`. The elements are skipped without
+    /// being parsed, which makes this cheaper than `record.count()`.
+    pub fn count_elements(&mut self) -> Result<usize, BgpStreamError> {
+        let mut count = 0;
+        loop {
+            unsafe {
+                let mut p_elem = null_mut::<bgpstream_elem_t>();
+                let p_p_elem: *mut *mut bgpstream_elem_t = &mut p_elem;
+                match bgpstream_record_get_next_elem(self.p_record, p_p_elem) {
+                    1 => count += 1,
+                    0 => return Ok(count),
+                    _ => return Err(BgpStreamError::GetNextElement),
+                }
+            }
+        }
+    }
+
+    /// Parse all remaining elements of the record, and return them together with the metadata of
+    /// the record. The result does not borrow the stream. Names that are not valid UTF-8 are
+    /// converted lossily. If any element cannot be parsed, the error is returned and the rest of
+    /// the record is lost.
+    pub fn into_owned(mut self) -> Result<OwnedRecord, BgpStreamError> {
+        let name = |name: Result<String, IntoStringError>| {
+            name.unwrap_or_else(|e| e.into_cstring().to_string_lossy().into_owned())
+        };
+        Ok(OwnedRecord {
+            time: self.time,
+            record_type: self.record_type,
+            project_name: name(self.project_name()),
+            collector_name: name(self.collector_name()),
+            router_name: name(self.router_name()),
+            router_ip: self.router_ip().ok(),
+            elements: self.collect_elements()?,
+        })
+    }
+
+    /// The metadata of the record, with names converted lossily (see
+    /// [`Record::collector_name_lossy`]).
+    pub fn meta(&self) -> RecordMeta {
+        RecordMeta {
+            time: self.time,
+            record_type: self.record_type,
+            project_name: self.project_name_lossy(),
+            collector_name: self.collector_name_lossy(),
+            router_name: self.router_name_lossy(),
+            router_ip: self.router_ip().ok(),
+        }
+    }
+
+    /// Release the borrow of the stream.
+    pub(crate) fn into_raw(self) -> RawRecord {
+        RawRecord {
             p_record: self.p_record,
             time: self.time,
+            record_type: self.record_type,
+            parse_options: self.parse_options,
+            tolerate_element_errors: self.tolerate_element_errors,
+        }
+    }
+
+    /// Re-attach a raw record to the stream it was fetched from.
+    ///
+    /// **Safety**: `raw` must be the last record fetched from `stream`. A raw record is invalidated
+    /// as soon as the stream fetches the next one.
+    pub(crate) unsafe fn from_raw(raw: RawRecord, _stream: &'a mut BgpStream) -> Record<'a> {
+        Record {
+            p_record: raw.p_record,
+            time: raw.time,
+            record_type: raw.record_type,
+            parse_options: raw.parse_options,
+            tolerate_element_errors: raw.tolerate_element_errors,
             _phantom: PhantomData,
         }
     }
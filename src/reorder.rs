@@ -0,0 +1,87 @@
+//! A bounded buffer that restores the time order of slightly out-of-order items (see
+//! [`crate::stream::Query::reorder_window`] and [`crate::adapter::ElementIteratorExt::reorder`]).
+
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use time::{Duration, OffsetDateTime};
+
+/// Buffer that holds items until they are older than `window` behind the newest item seen, and
+/// releases them in time order. Items with the same time are released in insertion order.
+pub(crate) struct ReorderBuffer<T> {
+    window: Duration,
+    newest: Option<OffsetDateTime>,
+    // number of items pushed so far, used to break ties between items with the same time.
+    seq: u64,
+    heap: BinaryHeap<Reverse<Entry<T>>>,
+}
+
+struct Entry<T> {
+    time: OffsetDateTime,
+    seq: u64,
+    item: T,
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.time, self.seq) == (other.time, other.seq)
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.time, self.seq).cmp(&(other.time, other.seq))
+    }
+}
+
+impl<T> ReorderBuffer<T> {
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            newest: None,
+            seq: 0,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    /// Add an item with the given time.
+    pub(crate) fn push(&mut self, time: OffsetDateTime, item: T) {
+        self.newest = Some(self.newest.map_or(time, |newest| newest.max(time)));
+        self.heap.push(Reverse(Entry {
+            time,
+            seq: self.seq,
+            item,
+        }));
+        self.seq += 1;
+    }
+
+    /// Take the oldest item, if it lies at least `window` behind the newest item seen.
+    pub(crate) fn pop_ready(&mut self) -> Option<T> {
+        let newest = self.newest?;
+        let Reverse(oldest) = self.heap.peek()?;
+        if newest - oldest.time >= self.window {
+            self.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Keep only the items for which `f` returns `true`.
+    #[cfg(feature = "ffi")]
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        self.heap.retain(|Reverse(entry)| f(&entry.item));
+    }
+
+    /// Take the oldest item, regardless of the window. Used to drain the buffer once the source is
+    /// exhausted.
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        self.heap.pop().map(|Reverse(entry)| entry.item)
+    }
+}
@@ -0,0 +1,291 @@
+//! Stream elements from [RIS Live](https://ris-live.ripe.net), the live websocket feed of RIPE RIS,
+//! instead of going through libbgpstream. Start a stream with [`crate::stream::Query::ris_live`].
+//! Requires the `rislive` feature.
+//!
+//! RIS Live messages are mapped onto the same [`Element`] types as the libbgpstream stream:
+//!
+//! - Each prefix announced in an `UPDATE` message becomes an [`ElementType::Announcement`], and
+//!   each withdrawn prefix an [`ElementType::Withdrawal`].
+//! - `RIS_PEER_STATE` messages become an [`ElementType::PeerState`]. RIS Live only reports the new
+//!   state of the session (`connected` or `down`), which is mapped to [`PeerState::Established`]
+//!   and [`PeerState::Idle`], respectively. The previous state is always [`PeerState::Unknown`].
+//! - All other messages (e.g., `OPEN`, `KEEPALIVE`, or `NOTIFICATION`) are ignored.
+
+use std::{
+    collections::VecDeque,
+    net::{IpAddr, TcpStream},
+};
+
+use ipnet::IpNet;
+use serde::Deserialize;
+use serde_json::json;
+use time::OffsetDateTime;
+use tungstenite::{stream::MaybeTlsStream, Message, WebSocket};
+
+use crate::{
+    element::{AsSegment, Element, ElementType, OriginType, PeerState, Update},
+    filter::{matches_filters, ClientFilter},
+    BgpStreamError,
+};
+
+/// The websocket endpoint of RIS Live.
+pub const RIS_LIVE_URL: &str = "wss://ris-live.ripe.net/v1/ws/?client=rust-routeviews";
+
+/// Iterator over the elements received from RIS Live, returned by
+/// [`crate::stream::Query::ris_live`]. This is an infinite iterator: it blocks until the next
+/// message arrives, and only ends once the server closes the connection (or the limit is
+/// reached).
+pub struct RisLiveStream {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+    // elements parsed from the last message, not yet yielded.
+    buffer: VecDeque<Element>,
+    // libbgpstream filters of the query, as `(name, value)`, evaluated on each element.
+    filters: Vec<(&'static str, String)>,
+    client_filters: Vec<ClientFilter>,
+    remaining: Option<usize>,
+}
+
+impl RisLiveStream {
+    /// Connect to RIS Live and subscribe to the messages selected by `filters`.
+    pub(crate) fn connect(
+        filters: Vec<(&'static str, String)>,
+        client_filters: Vec<ClientFilter>,
+        limit: Option<usize>,
+    ) -> Result<Self, BgpStreamError> {
+        let (mut socket, _) = tungstenite::connect(RIS_LIVE_URL).map_err(websocket_error)?;
+        for subscription in subscriptions(&filters) {
+            socket
+                .send(Message::text(subscription.to_string()))
+                .map_err(websocket_error)?;
+        }
+        Ok(Self {
+            socket,
+            buffer: VecDeque::new(),
+            filters,
+            client_filters,
+            remaining: limit,
+        })
+    }
+
+    fn matches(&self, element: &Element) -> bool {
+        let filters = self.filters.iter().map(|(n, v)| (*n, v.as_str()));
+        matches_filters(filters, element) && self.client_filters.iter().all(|f| f.matches(element))
+    }
+}
+
+impl Iterator for RisLiveStream {
+    type Item = Result<Element, BgpStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.remaining == Some(0) {
+                return None;
+            }
+            if let Some(element) = self.buffer.pop_front() {
+                if self.matches(&element) {
+                    if let Some(remaining) = self.remaining.as_mut() {
+                        *remaining -= 1;
+                    }
+                    return Some(Ok(element));
+                }
+                continue;
+            }
+            match self.socket.read() {
+                Ok(Message::Text(text)) => match parse_message(text.as_str()) {
+                    Ok(elements) => self.buffer.extend(elements),
+                    Err(e) => return Some(Err(e)),
+                },
+                Ok(Message::Close(_))
+                | Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                    return None
+                }
+                Ok(_) => {}
+                Err(e) => return Some(Err(websocket_error(e))),
+            }
+        }
+    }
+}
+
+fn websocket_error(e: tungstenite::Error) -> BgpStreamError {
+    BgpStreamError::WebSocket(Box::new(e))
+}
+
+/// Build the subscription messages sent by [`crate::stream::Query::ris_live`] for the libbgpstream
+/// filters of a query (as in [`crate::stream::QueryPlan::filters`]). RIS Live combines multiple
+/// subscriptions with a logical OR, so there is one subscription for each collector filter (or a
+/// single one without a collector). If the query has exactly one prefix filter, it is part of each
+/// subscription. All other filters are only evaluated client-side.
+pub fn subscriptions(filters: &[(&'static str, String)]) -> Vec<serde_json::Value> {
+    let mut data = json!({});
+    let prefixes: Vec<_> = filters
+        .iter()
+        .filter(|(name, _)| name.starts_with("prefix"))
+        .collect();
+    if let [(name, prefix)] = prefixes.as_slice() {
+        let (more, less) = match *name {
+            "prefix-exact" => (false, false),
+            "prefix-more" => (true, false),
+            "prefix-less" => (false, true),
+            _ => (true, true),
+        };
+        data["prefix"] = json!(prefix);
+        data["moreSpecific"] = json!(more);
+        data["lessSpecific"] = json!(less);
+    }
+
+    let hosts: Vec<_> = filters
+        .iter()
+        .filter(|(name, _)| *name == "collector")
+        .map(|(_, host)| host)
+        .collect();
+    let subscription = |data| json!({ "type": "ris_subscribe", "data": data });
+    if hosts.is_empty() {
+        return vec![subscription(data)];
+    }
+    hosts
+        .into_iter()
+        .map(|host| {
+            let mut data = data.clone();
+            data["host"] = json!(host);
+            subscription(data)
+        })
+        .collect()
+}
+
+/// The envelope of each RIS Live message. The `data` is only deserialized once the type is known,
+/// as the server sends other message types (e.g., `ris_subscribe_ok` or `pong`) with arbitrary
+/// data.
+#[derive(Deserialize)]
+struct RisLiveMessage {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct RisError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct RisMessage {
+    timestamp: f64,
+    peer: IpAddr,
+    peer_asn: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    path: Vec<RisPathSegment>,
+    #[serde(default)]
+    community: Vec<(u32, u32)>,
+    origin: Option<String>,
+    med: Option<u32>,
+    local_pref: Option<u32>,
+    #[serde(default)]
+    announcements: Vec<RisAnnouncement>,
+    #[serde(default)]
+    withdrawals: Vec<IpNet>,
+    state: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RisPathSegment {
+    Num(u32),
+    Set(Vec<u32>),
+}
+
+#[derive(Deserialize)]
+struct RisAnnouncement {
+    // may contain a second, link-local address, separated by a comma.
+    next_hop: String,
+    prefixes: Vec<IpNet>,
+}
+
+/// Parse a single RIS Live message (the JSON text of a websocket message) into the elements it
+/// contains, as done by [`RisLiveStream`] for each message it receives. This is useful to replay
+/// recorded messages. Messages of type `ris_error` are returned as [`BgpStreamError::RisLive`],
+/// and all other messages without elements (e.g., `ris_subscribe_ok`) yield no elements.
+pub fn parse_message(text: &str) -> Result<Vec<Element>, BgpStreamError> {
+    let envelope: RisLiveMessage = serde_json::from_str(text)?;
+    let msg: RisMessage = match envelope.kind.as_str() {
+        "ris_message" => serde_json::from_value(envelope.data)?,
+        "ris_error" => {
+            let error: RisError = serde_json::from_value(envelope.data)?;
+            return Err(BgpStreamError::RisLive(error.message));
+        }
+        _ => return Ok(Vec::new()),
+    };
+
+    let invalid = |what: &str| BgpStreamError::RisLive(format!("invalid {what} in {text}"));
+    let time = OffsetDateTime::from_unix_timestamp_nanos((msg.timestamp * 1e9) as i128)?;
+    let peer_asn = msg.peer_asn.parse().map_err(|_| invalid("peer_asn"))?;
+    let element = |e| Element {
+        time,
+        peer_ip: msg.peer,
+        peer_asn,
+        e,
+    };
+
+    match msg.kind.as_str() {
+        "UPDATE" => {
+            let as_path: Vec<AsSegment> = msg
+                .path
+                .iter()
+                .map(|seg| match seg {
+                    RisPathSegment::Num(asn) => AsSegment::Num(*asn),
+                    RisPathSegment::Set(set) => AsSegment::Set(set.clone()),
+                })
+                .collect();
+            let communities = msg
+                .community
+                .iter()
+                .map(|(asn, value)| Some(((*asn).try_into().ok()?, (*value).try_into().ok()?)))
+                .collect::<Option<Vec<(u16, u16)>>>()
+                .ok_or_else(|| invalid("community"))?;
+            let origin_type = match msg.origin.as_deref().map(str::to_ascii_lowercase) {
+                None => None,
+                Some(o) if o == "igp" => Some(OriginType::Igp),
+                Some(o) if o == "egp" => Some(OriginType::Egp),
+                Some(o) if o == "incomplete" => Some(OriginType::Incomplete),
+                Some(_) => return Err(invalid("origin")),
+            };
+
+            let mut elements = Vec::new();
+            for announcement in &msg.announcements {
+                let next_hop = announcement.next_hop.split(',').next().unwrap_or_default();
+                let next_hop: IpAddr = next_hop.trim().parse().map_err(|_| invalid("next_hop"))?;
+                elements.extend(announcement.prefixes.iter().map(|prefix| {
+                    element(ElementType::Announcement(Update {
+                        prefix: *prefix,
+                        next_hop,
+                        as_path: as_path.clone(),
+                        communities: communities.clone(),
+                        origin_type,
+                        med: msg.med,
+                        local_pref: msg.local_pref,
+                    }))
+                }));
+            }
+            elements.extend(
+                msg.withdrawals
+                    .iter()
+                    .map(|prefix| element(ElementType::Withdrawal(*prefix))),
+            );
+            Ok(elements)
+        }
+        "RIS_PEER_STATE" => {
+            let to = match msg.state.as_deref() {
+                Some("connected") => PeerState::Established,
+                Some("down") => PeerState::Idle,
+                _ => PeerState::Unknown,
+            };
+            Ok(vec![element(ElementType::PeerState {
+                from: PeerState::Unknown,
+                to,
+            })])
+        }
+        _ => Ok(Vec::new()),
+    }
+}
@@ -0,0 +1,183 @@
+//! Route origin validation (RFC 6811) against a set of ROAs supplied by the user, e.g., exported
+//! from a relying party. This does not fetch or verify any RPKI data itself.
+
+use ipnet::IpNet;
+use prefix_trie::joint::JointPrefixMap;
+
+use crate::element::{AsSegment, Update};
+
+/// A Route Origin Authorization: `asn` may originate `prefix` and any more specific prefix up to a
+/// length of `max_length`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Roa {
+    pub prefix: IpNet,
+    pub max_length: u8,
+    pub asn: u32,
+}
+
+impl From<(IpNet, u8, u32)> for Roa {
+    fn from((prefix, max_length, asn): (IpNet, u8, u32)) -> Self {
+        Self {
+            prefix,
+            max_length,
+            asn,
+        }
+    }
+}
+
+/// The validation state of a route (RFC 6811, Section 2), where invalid routes are split by the
+/// reason why no ROA matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteValidity {
+    /// A ROA covers the prefix, authorizes the origin AS, and allows the prefix length.
+    Valid,
+    /// At least one ROA covers the prefix, but none of them authorizes the origin AS.
+    InvalidOrigin,
+    /// A ROA covering the prefix authorizes the origin AS, but the prefix is longer than its
+    /// maximum length.
+    InvalidLength,
+    /// No ROA covers the prefix.
+    NotFound,
+}
+
+impl RouteValidity {
+    /// Whether the route is invalid (for any reason).
+    pub fn is_invalid(&self) -> bool {
+        matches!(
+            self,
+            RouteValidity::InvalidOrigin | RouteValidity::InvalidLength
+        )
+    }
+}
+
+/// A set of ROAs, backed by a patricia trie, to validate routes following RFC 6811.
+///
+/// A ROA covers a route if its prefix is equal to or less specific than the route's prefix. The
+/// route is [`RouteValidity::Valid`] if any covering ROA matches both its origin AS and its
+/// prefix length, [`RouteValidity::NotFound`] if no ROA covers it, and invalid otherwise. The
+/// origin AS is the last segment of the AS path. If that segment is an AS set, or the AS path is
+/// empty, the origin is unknown and no ROA matches. ROAs for AS 0 (RFC 6483) never match.
+///
+/// ```
+/// use routeviews::{
+///     element::{parse_as_path, Update},
+///     rpki::{RoaSet, RouteValidity},
+/// };
+///
+/// let roas: RoaSet = [("192.0.2.0/23".parse().unwrap(), 24, 64512)].into_iter().collect();
+/// let route = |prefix: &str, path: &str| {
+///     Update::builder(prefix.parse().unwrap(), "198.51.100.1".parse().unwrap())
+///         .as_path(parse_as_path(path).unwrap())
+///         .build()
+/// };
+///
+/// assert_eq!(roas.validate(&route("192.0.2.0/24", "3356 64512")), RouteValidity::Valid);
+/// assert_eq!(roas.validate(&route("192.0.2.0/24", "3356 64513")), RouteValidity::InvalidOrigin);
+/// assert_eq!(roas.validate(&route("192.0.2.0/25", "3356 64512")), RouteValidity::InvalidLength);
+/// assert_eq!(roas.validate(&route("198.51.100.0/24", "3356")), RouteValidity::NotFound);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RoaSet {
+    // ROAs by prefix, each given as `(max_length, asn)`.
+    map: JointPrefixMap<IpNet, Vec<(u8, u32)>>,
+    len: usize,
+}
+
+impl Default for RoaSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RoaSet {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self {
+            map: JointPrefixMap::new(),
+            len: 0,
+        }
+    }
+
+    /// Add a ROA to the set. Its prefix is normalized (i.e., host bits are cleared), and a maximum
+    /// length shorter than the prefix length is treated as the prefix length. Returns `false` if
+    /// the ROA was already present.
+    pub fn insert(&mut self, roa: impl Into<Roa>) -> bool {
+        let roa = roa.into();
+        let prefix = roa.prefix.trunc();
+        let entry = (roa.max_length.max(prefix.prefix_len()), roa.asn);
+        let roas = self.map.entry(prefix).or_default();
+        if roas.contains(&entry) {
+            return false;
+        }
+        roas.push(entry);
+        self.len += 1;
+        true
+    }
+
+    /// The number of ROAs in the set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the set contains no ROA.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// All ROAs that cover `prefix`, ordered from less to more specific.
+    pub fn covering(&self, prefix: &IpNet) -> Vec<Roa> {
+        let prefix = prefix.trunc();
+        self.map
+            .cover(&prefix)
+            .flat_map(|(p, roas)| {
+                roas.iter()
+                    .map(move |(max_length, asn)| (p, *max_length, *asn))
+            })
+            .map(Roa::from)
+            .collect()
+    }
+
+    /// Validate the prefix and origin AS of `update`.
+    pub fn validate(&self, update: &Update) -> RouteValidity {
+        let origin = match update.origin() {
+            Some(AsSegment::Num(asn)) => Some(*asn),
+            Some(AsSegment::Set(_)) | None => None,
+        };
+        self.validate_origin(&update.prefix, origin)
+    }
+
+    /// Validate a route for `prefix` originated by `origin`, where `None` is an unknown origin that
+    /// matches no ROA.
+    pub fn validate_origin(&self, prefix: &IpNet, origin: Option<u32>) -> RouteValidity {
+        let prefix = prefix.trunc();
+        let mut result = RouteValidity::NotFound;
+        for (max_length, asn) in self.map.cover_values(&prefix).flatten() {
+            if *asn == 0 || Some(*asn) != origin {
+                if result == RouteValidity::NotFound {
+                    result = RouteValidity::InvalidOrigin;
+                }
+            } else if prefix.prefix_len() <= *max_length {
+                return RouteValidity::Valid;
+            } else {
+                result = RouteValidity::InvalidLength;
+            }
+        }
+        result
+    }
+}
+
+impl<R: Into<Roa>> FromIterator<R> for RoaSet {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<R: Into<Roa>> Extend<R> for RoaSet {
+    fn extend<I: IntoIterator<Item = R>>(&mut self, iter: I) {
+        for roa in iter {
+            self.insert(roa);
+        }
+    }
+}
@@ -1,12 +1,27 @@
 use std::{
-    ffi::{c_char, CString},
+    collections::HashSet,
+    ffi::{c_char, CStr, CString},
     fmt::Display,
-    ptr::NonNull,
+    fs,
+    io::{self, Read},
+    ops::{Range, RangeFrom, RangeInclusive},
+    path::{Path, PathBuf},
+    ptr::{null_mut, NonNull},
+    slice,
+    str::FromStr,
+    sync::{
+        mpsc::{sync_channel, Receiver},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
+use ipnet::IpNet;
 use libbgpstream_sys::{
     bgpstream_add_filter, bgpstream_add_interval_filter, bgpstream_add_recent_interval_filter,
-    bgpstream_add_rib_period_filter, bgpstream_create, bgpstream_destroy, bgpstream_filter_type_t,
+    bgpstream_add_rib_period_filter, bgpstream_create, bgpstream_data_interface_id_t,
+    bgpstream_data_interface_option_t, bgpstream_destroy, bgpstream_filter_type_t,
     bgpstream_filter_type_t_BGPSTREAM_FILTER_TYPE_COLLECTOR as FILTER_TYPE_COLLECTOR,
     bgpstream_filter_type_t_BGPSTREAM_FILTER_TYPE_ELEM_ASPATH as FILTER_TYPE_ELEM_ASPATH,
     bgpstream_filter_type_t_BGPSTREAM_FILTER_TYPE_ELEM_COMMUNITY as FILTER_TYPE_ELEM_COMMUNITY,
@@ -21,12 +36,27 @@ use libbgpstream_sys::{
     bgpstream_filter_type_t_BGPSTREAM_FILTER_TYPE_ELEM_TYPE as FILTER_TYPE_ELEM_TYPE,
     bgpstream_filter_type_t_BGPSTREAM_FILTER_TYPE_PROJECT as FILTER_TYPE_PROJECT,
     bgpstream_filter_type_t_BGPSTREAM_FILTER_TYPE_RECORD_TYPE as FILTER_TYPE_RECORD_TYPE,
-    bgpstream_get_data_interface_id_by_name, bgpstream_get_data_interface_option_by_name,
-    bgpstream_set_data_interface_option, bgpstream_start, bgpstream_t,
+    bgpstream_get_data_interface_id, bgpstream_get_data_interface_id_by_name,
+    bgpstream_get_data_interface_info, bgpstream_get_data_interface_option_by_name,
+    bgpstream_get_data_interface_options, bgpstream_get_data_interfaces,
+    bgpstream_set_data_interface, bgpstream_set_data_interface_option, bgpstream_start,
+    bgpstream_t,
 };
+#[cfg(feature = "rand")]
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use time::OffsetDateTime;
 
-use crate::{element::Element, record::Record, BgpStreamError};
+pub use crate::element::IpVersion;
+#[cfg(feature = "rislive")]
+use crate::rislive::RisLiveStream;
+use crate::{
+    element::{Element, ElementType, ParseOptions, Peer, PeerState},
+    filter::{matches_filters, satisfied_filters, ClientFilter, CommunityMatcher, PrefixExclusion},
+    parse::array_to_bytes,
+    record::{ElementRef, OwnedRecord, RawRecord, Record, RecordMeta, RecordOutcome},
+    reorder::ReorderBuffer,
+    BgpStreamError, ParseError,
+};
 
 #[derive(Default, Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum FilterInterval {
@@ -49,6 +79,76 @@ pub enum FilterInterval {
     },
 }
 
+/// The bounded interval `start..stop`.
+impl From<Range<OffsetDateTime>> for FilterInterval {
+    fn from(range: Range<OffsetDateTime>) -> Self {
+        FilterInterval::Interval {
+            start: range.start,
+            stop: Some(range.end),
+        }
+    }
+}
+
+/// The open interval `start..`, which enables live mode.
+impl From<RangeFrom<OffsetDateTime>> for FilterInterval {
+    fn from(range: RangeFrom<OffsetDateTime>) -> Self {
+        FilterInterval::Interval {
+            start: range.start,
+            stop: None,
+        }
+    }
+}
+
+/// Parse a point in time from a string, trying the following formats in order:
+///
+/// - RFC 3339, e.g., `2023-11-08T10:00:00Z` or `2023-11-08T12:00:00+02:00`.
+/// - Date and time without a time zone, e.g., `2023-11-08 10:00:00`, `2023-11-08 10:00`, or the
+///   same with a `T` separating date and time. UTC is assumed.
+/// - Date only, e.g., `2023-11-08`, meaning midnight UTC.
+/// - Unix timestamp in seconds, e.g., `1699437600`.
+///
+/// ```
+/// # use routeviews::stream::parse_time;
+/// use time::macros::datetime;
+///
+/// let t = datetime!(2023-11-08 10:00 UTC);
+/// assert_eq!(parse_time("2023-11-08T10:00:00Z").unwrap(), t);
+/// assert_eq!(parse_time("2023-11-08T12:00:00+02:00").unwrap(), t);
+/// assert_eq!(parse_time("2023-11-08 10:00").unwrap(), t);
+/// assert_eq!(parse_time("1699437600").unwrap(), t);
+/// assert!(parse_time("yesterday").is_err());
+/// ```
+pub fn parse_time(s: &str) -> Result<OffsetDateTime, ParseError> {
+    use time::{
+        format_description::well_known::Rfc3339, macros::format_description, Date,
+        PrimitiveDateTime,
+    };
+
+    let trimmed = s.trim();
+    if let Ok(t) = OffsetDateTime::parse(trimmed, &Rfc3339) {
+        return Ok(t);
+    }
+    let without_zone = [
+        format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"),
+        format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]"),
+        format_description!("[year]-[month]-[day] [hour]:[minute]"),
+        format_description!("[year]-[month]-[day]T[hour]:[minute]"),
+    ];
+    for format in without_zone {
+        if let Ok(t) = PrimitiveDateTime::parse(trimmed, format) {
+            return Ok(t.assume_utc());
+        }
+    }
+    if let Ok(date) = Date::parse(trimmed, format_description!("[year]-[month]-[day]")) {
+        return Ok(date.midnight().assume_utc());
+    }
+    trimmed
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| OffsetDateTime::from_unix_timestamp(secs).ok())
+        .ok_or_else(|| ParseError::new("time", s))
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum TimeUnit {
     Seconds,
@@ -57,6 +157,18 @@ pub enum TimeUnit {
     Days,
 }
 
+impl TimeUnit {
+    /// The duration of a single unit.
+    pub fn duration(&self) -> time::Duration {
+        match self {
+            TimeUnit::Seconds => time::Duration::SECOND,
+            TimeUnit::Minutes => time::Duration::MINUTE,
+            TimeUnit::Hours => time::Duration::HOUR,
+            TimeUnit::Days => time::Duration::DAY,
+        }
+    }
+}
+
 impl Display for TimeUnit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -68,12 +180,6 @@ impl Display for TimeUnit {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum IpVersion {
-    IPv4,
-    IPv6,
-}
-
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum RecordType {
     /// Individual and more frequent (incremental) updates.
@@ -82,6 +188,35 @@ pub enum RecordType {
     RIBs,
 }
 
+impl RecordType {
+    /// The canonical name as used by libbgpstream (`updates` or `ribs`). Parsing is
+    /// case-insensitive and also accepts the singular form.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordType::Updates => "updates",
+            RecordType::RIBs => "ribs",
+        }
+    }
+}
+
+impl Display for RecordType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RecordType {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "updates" | "update" => Ok(RecordType::Updates),
+            "ribs" | "rib" => Ok(RecordType::RIBs),
+            _ => Err(ParseError::new("record type", s)),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum PrefixMatchType {
     /// Match either the exact prefix, a less specific or a more specific prefix.
@@ -94,6 +229,38 @@ pub enum PrefixMatchType {
     More,
 }
 
+impl PrefixMatchType {
+    /// The canonical name (`any`, `exact`, `less`, or `more`). Parsing is case-insensitive.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrefixMatchType::Any => "any",
+            PrefixMatchType::Exact => "exact",
+            PrefixMatchType::Less => "less",
+            PrefixMatchType::More => "more",
+        }
+    }
+}
+
+impl Display for PrefixMatchType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PrefixMatchType {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "any" => Ok(PrefixMatchType::Any),
+            "exact" => Ok(PrefixMatchType::Exact),
+            "less" => Ok(PrefixMatchType::Less),
+            "more" => Ok(PrefixMatchType::More),
+            _ => Err(ParseError::new("prefix match type", s)),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Project {
     RouteViews,
@@ -109,13 +276,176 @@ pub enum Collector {
     RipeNcc(RipeNcc),
 }
 
+impl Display for Project {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Project {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "routeviews" => Ok(Project::RouteViews),
+            "ris" => Ok(Project::RIS),
+            _ => Err(ParseError::new("project", s)),
+        }
+    }
+}
+
+impl Project {
+    /// The canonical name as used by libbgpstream (`routeviews` or `ris`). Parsing is
+    /// case-insensitive.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Project::RouteViews => "routeviews",
+            Project::RIS => "ris",
+        }
+    }
+
+    fn cstring(&self) -> CString {
+        CString::new(self.as_str()).unwrap()
+    }
+
+    /// The project of the collector with the given name. RIS collectors are named `rrc*`, all
+    /// others belong to RouteViews.
+    pub(crate) fn of_collector_name(name: &str) -> Project {
+        if name.to_ascii_lowercase().starts_with("rrc") {
+            Project::RIS
+        } else {
+            Project::RouteViews
+        }
+    }
+
+    /// The time between two RIB dumps of all collectors of the project. The cadence is fixed per
+    /// project:
+    ///
+    /// | Project    | RIB dumps     | Updates          |
+    /// |------------|---------------|------------------|
+    /// | RouteViews | every 2 hours | every 15 minutes |
+    /// | RIS        | every 8 hours | every 5 minutes  |
+    ///
+    /// RIB dumps are aligned to midnight UTC, i.e., RouteViews dumps at 00:00, 02:00, 04:00, etc.,
+    /// and RIS dumps at 00:00, 08:00 and 16:00.
+    pub fn rib_period(&self) -> time::Duration {
+        match self {
+            Project::RouteViews => time::Duration::hours(2),
+            Project::RIS => time::Duration::hours(8),
+        }
+    }
+
+    /// The time between two update dumps of all collectors of the project. See
+    /// [`Project::rib_period`] for the cadence of each project.
+    pub fn update_period(&self) -> time::Duration {
+        match self {
+            Project::RouteViews => time::Duration::minutes(15),
+            Project::RIS => time::Duration::minutes(5),
+        }
+    }
+
+    /// The time between two dumps of the given record type.
+    pub fn dump_period(&self, record_type: RecordType) -> time::Duration {
+        match record_type {
+            RecordType::RIBs => self.rib_period(),
+            RecordType::Updates => self.update_period(),
+        }
+    }
+}
+
+impl Display for Collector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Collector {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Collector::all()
+            .find(|c| c.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ParseError::new("collector", s))
+    }
+}
+
 impl Collector {
+    /// All known collectors, first those of RouteViews, then those of RIS.
+    pub fn all() -> impl Iterator<Item = Collector> {
+        RouteView::ALL
+            .into_iter()
+            .map(Collector::RouteView)
+            .chain(RipeNcc::ALL.into_iter().map(Collector::RipeNcc))
+    }
+
+    /// The name of the collector as used by libbgpstream (e.g., `route-views.amsix` or `rrc00`).
+    /// Parsing is case-insensitive.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Collector::RouteView(rv) => rv.as_str(),
+            Collector::RipeNcc(rrc) => rrc.as_str(),
+        }
+    }
+
     fn cstring(&self) -> CString {
         match self {
             Collector::RouteView(rv) => rv.cstring(),
             Collector::RipeNcc(rv) => rv.cstring(),
         }
     }
+
+    /// Check whether the name of the collector matches the glob `pattern` (see
+    /// [`Query::collector_pattern`]).
+    pub fn matches_pattern(&self, pattern: &str) -> bool {
+        glob_match(pattern.as_bytes(), self.as_str().as_bytes())
+    }
+
+    /// The project that operates the collector.
+    pub fn project(&self) -> Project {
+        match self {
+            Collector::RouteView(_) => Project::RouteViews,
+            Collector::RipeNcc(_) => Project::RIS,
+        }
+    }
+
+    /// The time between two RIB dumps of the collector (see [`Project::rib_period`] for the
+    /// cadence of each project).
+    pub fn rib_period(&self) -> time::Duration {
+        self.project().rib_period()
+    }
+
+    /// The time between two update dumps of the collector (see [`Project::rib_period`] for the
+    /// cadence of each project).
+    pub fn update_period(&self) -> time::Duration {
+        self.project().update_period()
+    }
+
+    /// Get the interval `(start, stop)` that covers the single RIB dump nearest to `around`.
+    /// `start` is the scheduled time of that dump, and `stop` is one update period (see
+    /// [`Collector::update_period`]) later, which is much shorter than the time to the next dump.
+    /// The computation relies on the fixed cadence documented in [`Project::rib_period`], and does
+    /// not check whether the dump actually exists.
+    ///
+    /// ```
+    /// use routeviews::stream::*;
+    /// use time::macros::datetime;
+    ///
+    /// let collector = Collector::RouteView(RouteView::Amsix);
+    /// let (start, stop) = collector.snap_interval(datetime!(2023-11-08 09:10 UTC));
+    /// assert_eq!(start, datetime!(2023-11-08 10:00 UTC));
+    /// assert_eq!(stop, datetime!(2023-11-08 10:15 UTC));
+    /// ```
+    pub fn snap_interval(&self, around: OffsetDateTime) -> (OffsetDateTime, OffsetDateTime) {
+        let period = self.rib_period().whole_seconds();
+        let ts = around.unix_timestamp();
+        let mut dump = ts.div_euclid(period);
+        if ts.rem_euclid(period) * 2 >= period {
+            dump += 1;
+        }
+        let start = OffsetDateTime::from_unix_timestamp(dump * period)
+            .expect("the RIB dump is close to a valid timestamp");
+        (start, start + self.update_period())
+    }
 }
 
 /// Route view with RIBs every 2 hours and updates every 15 minutes. You can find the current state
@@ -240,44 +570,87 @@ pub enum RouteView {
 }
 
 impl RouteView {
-    fn cstring(&self) -> CString {
+    /// All collectors, in the order in which they are declared.
+    pub const ALL: [RouteView; 34] = [
+        RouteView::Amsix,
+        RouteView::Bdix,
+        RouteView::Bknix,
+        RouteView::Chicago,
+        RouteView::Chile,
+        RouteView::Eqix,
+        RouteView::Flix,
+        RouteView::Fortaleza,
+        RouteView::Gixa,
+        RouteView::Gorex,
+        RouteView::Isc,
+        RouteView::Kixp,
+        RouteView::Linx,
+        RouteView::Mwix,
+        RouteView::Napafrica,
+        RouteView::Nwax,
+        RouteView::Ny,
+        RouteView::Perth,
+        RouteView::Peru,
+        RouteView::Phoix,
+        RouteView::Rio,
+        RouteView::Sfmix,
+        RouteView::Sg,
+        RouteView::Soxrs,
+        RouteView::Sydney,
+        RouteView::Telxatl,
+        RouteView::Uaeix,
+        RouteView::Wide,
+        RouteView::View2,
+        RouteView::View2SaoPaulo,
+        RouteView::View3,
+        RouteView::View4,
+        RouteView::View5,
+        RouteView::View6,
+    ];
+
+    /// The name of the collector as used by libbgpstream (e.g., `route-views.amsix`).
+    pub fn as_str(&self) -> &'static str {
         match self {
-            RouteView::Amsix => CString::new("route-views.amsix").unwrap(),
-            RouteView::Bdix => CString::new("route-views.bdix").unwrap(),
-            RouteView::Bknix => CString::new("route-views.bknix").unwrap(),
-            RouteView::Chicago => CString::new("route-views.chicago").unwrap(),
-            RouteView::Chile => CString::new("route-views.chile").unwrap(),
-            RouteView::Eqix => CString::new("route-views.eqix").unwrap(),
-            RouteView::Flix => CString::new("route-views.flix").unwrap(),
-            RouteView::Fortaleza => CString::new("route-views.fortaleza").unwrap(),
-            RouteView::Gixa => CString::new("route-views.gixa").unwrap(),
-            RouteView::Gorex => CString::new("route-views.gorex").unwrap(),
-            RouteView::Isc => CString::new("route-views.isc").unwrap(),
-            RouteView::Kixp => CString::new("route-views.kixp").unwrap(),
-            RouteView::Linx => CString::new("route-views.linx").unwrap(),
-            RouteView::Mwix => CString::new("route-views.mwix").unwrap(),
-            RouteView::Napafrica => CString::new("route-views.napafrica").unwrap(),
-            RouteView::Nwax => CString::new("route-views.nwax").unwrap(),
-            RouteView::Ny => CString::new("route-views.ny").unwrap(),
-            RouteView::Perth => CString::new("route-views.perth").unwrap(),
-            RouteView::Peru => CString::new("route-views.peru").unwrap(),
-            RouteView::Phoix => CString::new("route-views.phoix").unwrap(),
-            RouteView::Rio => CString::new("route-views.rio").unwrap(),
-            RouteView::Sfmix => CString::new("route-views.sfmix").unwrap(),
-            RouteView::Sg => CString::new("route-views.sg").unwrap(),
-            RouteView::Soxrs => CString::new("route-views.soxrs").unwrap(),
-            RouteView::Sydney => CString::new("route-views.sydney").unwrap(),
-            RouteView::Telxatl => CString::new("route-views.telxatl").unwrap(),
-            RouteView::Uaeix => CString::new("route-views.uaeix").unwrap(),
-            RouteView::Wide => CString::new("route-views.wide").unwrap(),
-            RouteView::View2 => CString::new("route-views2").unwrap(),
-            RouteView::View2SaoPaulo => CString::new("route-views2saopaulo").unwrap(),
-            RouteView::View3 => CString::new("route-views3").unwrap(),
-            RouteView::View4 => CString::new("route-views4").unwrap(),
-            RouteView::View5 => CString::new("route-views5").unwrap(),
-            RouteView::View6 => CString::new("route-views6").unwrap(),
+            RouteView::Amsix => "route-views.amsix",
+            RouteView::Bdix => "route-views.bdix",
+            RouteView::Bknix => "route-views.bknix",
+            RouteView::Chicago => "route-views.chicago",
+            RouteView::Chile => "route-views.chile",
+            RouteView::Eqix => "route-views.eqix",
+            RouteView::Flix => "route-views.flix",
+            RouteView::Fortaleza => "route-views.fortaleza",
+            RouteView::Gixa => "route-views.gixa",
+            RouteView::Gorex => "route-views.gorex",
+            RouteView::Isc => "route-views.isc",
+            RouteView::Kixp => "route-views.kixp",
+            RouteView::Linx => "route-views.linx",
+            RouteView::Mwix => "route-views.mwix",
+            RouteView::Napafrica => "route-views.napafrica",
+            RouteView::Nwax => "route-views.nwax",
+            RouteView::Ny => "route-views.ny",
+            RouteView::Perth => "route-views.perth",
+            RouteView::Peru => "route-views.peru",
+            RouteView::Phoix => "route-views.phoix",
+            RouteView::Rio => "route-views.rio",
+            RouteView::Sfmix => "route-views.sfmix",
+            RouteView::Sg => "route-views.sg",
+            RouteView::Soxrs => "route-views.soxrs",
+            RouteView::Sydney => "route-views.sydney",
+            RouteView::Telxatl => "route-views.telxatl",
+            RouteView::Uaeix => "route-views.uaeix",
+            RouteView::Wide => "route-views.wide",
+            RouteView::View2 => "route-views2",
+            RouteView::View2SaoPaulo => "route-views2saopaulo",
+            RouteView::View3 => "route-views3",
+            RouteView::View4 => "route-views4",
+            RouteView::View5 => "route-views5",
+            RouteView::View6 => "route-views6",
         }
     }
+
+    fn cstring(&self) -> CString {
+        CString::new(self.as_str()).unwrap()
+    }
 }
 
 /// RIPE NCC Routing Information Service with RIBs every 8 hours and updates every 5 minutes. You
@@ -356,33 +729,65 @@ pub enum RipeNcc {
 }
 
 impl RipeNcc {
-    fn cstring(&self) -> CString {
+    /// All collectors, in the order in which they are declared.
+    pub const ALL: [RipeNcc; 23] = [
+        RipeNcc::Amsterdam,
+        RipeNcc::London,
+        RipeNcc::AmsterdamIx,
+        RipeNcc::Geneva,
+        RipeNcc::Vienna,
+        RipeNcc::Otemachi,
+        RipeNcc::Stockholm,
+        RipeNcc::Milan,
+        RipeNcc::NewYork,
+        RipeNcc::Frankfurt,
+        RipeNcc::Moscow,
+        RipeNcc::PaloAlto,
+        RipeNcc::SaoPaolo,
+        RipeNcc::Miami,
+        RipeNcc::Barcelona,
+        RipeNcc::Johannesburg,
+        RipeNcc::Zurich,
+        RipeNcc::Paris,
+        RipeNcc::Bucharest,
+        RipeNcc::Singapore,
+        RipeNcc::Montevideo,
+        RipeNcc::Amsterdam2,
+        RipeNcc::Dubai,
+    ];
+
+    /// The name of the collector as used by libbgpstream (e.g., `rrc00`).
+    pub fn as_str(&self) -> &'static str {
         match self {
-            RipeNcc::Amsterdam => CString::new("rrc00").unwrap(),
-            RipeNcc::London => CString::new("rrc01").unwrap(),
-            RipeNcc::AmsterdamIx => CString::new("rrc03").unwrap(),
-            RipeNcc::Geneva => CString::new("rrc04").unwrap(),
-            RipeNcc::Vienna => CString::new("rrc05").unwrap(),
-            RipeNcc::Otemachi => CString::new("rrc06").unwrap(),
-            RipeNcc::Stockholm => CString::new("rrc07").unwrap(),
-            RipeNcc::Milan => CString::new("rrc10").unwrap(),
-            RipeNcc::NewYork => CString::new("rrc11").unwrap(),
-            RipeNcc::Frankfurt => CString::new("rrc12").unwrap(),
-            RipeNcc::Moscow => CString::new("rrc13").unwrap(),
-            RipeNcc::PaloAlto => CString::new("rrc14").unwrap(),
-            RipeNcc::SaoPaolo => CString::new("rrc15").unwrap(),
-            RipeNcc::Miami => CString::new("rrc16").unwrap(),
-            RipeNcc::Barcelona => CString::new("rrc18").unwrap(),
-            RipeNcc::Johannesburg => CString::new("rrc19").unwrap(),
-            RipeNcc::Zurich => CString::new("rrc20").unwrap(),
-            RipeNcc::Paris => CString::new("rrc21").unwrap(),
-            RipeNcc::Bucharest => CString::new("rrc22").unwrap(),
-            RipeNcc::Singapore => CString::new("rrc23").unwrap(),
-            RipeNcc::Montevideo => CString::new("rrc24").unwrap(),
-            RipeNcc::Amsterdam2 => CString::new("rrc25").unwrap(),
-            RipeNcc::Dubai => CString::new("rrc26").unwrap(),
+            RipeNcc::Amsterdam => "rrc00",
+            RipeNcc::London => "rrc01",
+            RipeNcc::AmsterdamIx => "rrc03",
+            RipeNcc::Geneva => "rrc04",
+            RipeNcc::Vienna => "rrc05",
+            RipeNcc::Otemachi => "rrc06",
+            RipeNcc::Stockholm => "rrc07",
+            RipeNcc::Milan => "rrc10",
+            RipeNcc::NewYork => "rrc11",
+            RipeNcc::Frankfurt => "rrc12",
+            RipeNcc::Moscow => "rrc13",
+            RipeNcc::PaloAlto => "rrc14",
+            RipeNcc::SaoPaolo => "rrc15",
+            RipeNcc::Miami => "rrc16",
+            RipeNcc::Barcelona => "rrc18",
+            RipeNcc::Johannesburg => "rrc19",
+            RipeNcc::Zurich => "rrc20",
+            RipeNcc::Paris => "rrc21",
+            RipeNcc::Bucharest => "rrc22",
+            RipeNcc::Singapore => "rrc23",
+            RipeNcc::Montevideo => "rrc24",
+            RipeNcc::Amsterdam2 => "rrc25",
+            RipeNcc::Dubai => "rrc26",
         }
     }
+
+    fn cstring(&self) -> CString {
+        CString::new(self.as_str()).unwrap()
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
@@ -393,68 +798,395 @@ pub enum ElementTypeDescr {
     PeerStates,
 }
 
+impl ElementTypeDescr {
+    /// The canonical name as used by libbgpstream (`ribs`, `withdrawals`, `announcements`, or
+    /// `peerstates`). Parsing is case-insensitive and also accepts the singular form.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ElementTypeDescr::RIBs => "ribs",
+            ElementTypeDescr::Withdrawals => "withdrawals",
+            ElementTypeDescr::Announcements => "announcements",
+            ElementTypeDescr::PeerStates => "peerstates",
+        }
+    }
+}
+
+impl Display for ElementTypeDescr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ElementTypeDescr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ribs" | "rib" => Ok(ElementTypeDescr::RIBs),
+            "withdrawals" | "withdrawal" => Ok(ElementTypeDescr::Withdrawals),
+            "announcements" | "announcement" => Ok(ElementTypeDescr::Announcements),
+            "peerstates" | "peerstate" => Ok(ElementTypeDescr::PeerStates),
+            _ => Err(ParseError::new("element type", s)),
+        }
+    }
+}
+
 /// Query the BGPStream server for an object.
 /// Add a filter to an unstarted BGP Stream instance. Only those records/elems that match the
 /// filter(s) will be included in the stream.
 ///
 /// If multiple filters of the same type are added, a record/elem is considered a match if it
-/// matches any of the filters. E.g. if `self.project("routeviews")` and `project("ris")` are used,
-/// then records that are from either the Route Views, or the RIS project will be included.
+/// matches any of the filters. E.g. if `add_project(Project::RouteViews)` and
+/// `add_project(Project::RIS)` are used, then records that are from either the Route Views, or the
+/// RIS project will be included.
+///
+/// The project, collector, and record type filters are an exception: [`Query::project`],
+/// [`Query::collector`], [`Query::collector_name`], and [`Query::record_type`] replace any
+/// previous filter of the same type, such that the last call wins. Use [`Query::add_project`] and
+/// [`Query::add_collector`] to combine multiple projects or collectors.
 ///
 /// If multiple filters of different types are added, a record/elem is considered a match if it
-/// matches all of the filters. If `project("routeviews")` and `record_type(RecordType::Updates)`
-/// are used, then records that are both from the Route Views project, and are updates will be
-/// included.
+/// matches all of the filters. If `project(Project::RouteViews)` and
+/// `record_type(RecordType::Updates)` are used, then records that are both from the Route Views
+/// project, and are updates will be included.
 #[derive(Default, Clone)]
 pub struct Query {
     filters: Vec<(bgpstream_filter_type_t, CString)>,
     interval: FilterInterval,
     rib_period: Option<u32>,
     data_interface_options: Vec<(CString, CString, CString)>,
+    data_interface: Option<CString>,
+    local_files: Vec<(RecordType, PathBuf)>,
+    readers: Vec<(RecordType, SharedReader)>,
+    limit: Option<usize>,
+    client_filters: Vec<ClientFilter>,
+    parse_options: ParseOptions,
+    tolerate_element_errors: bool,
+    report_filtered_sources: bool,
+    reorder_window: Option<time::Duration>,
+    start_retry: Option<(u32, Duration)>,
+    #[cfg(feature = "rand")]
+    sample_rate: Option<f64>,
+    #[cfg(feature = "rand")]
+    sample_seed: Option<u64>,
+}
+
+/// A summary of everything a [`Query`] configures, returned by [`Query::describe`]. Use its
+/// `Display` implementation to log the query in a human-readable form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryPlan {
+    /// All libbgpstream filters as `(filter type, value)`, in the order in which they are added.
+    pub filters: Vec<(&'static str, String)>,
+    pub interval: FilterInterval,
+    pub rib_period: Option<u32>,
+    /// The selected data interface, or `None` for libbgpstream's default (`broker`).
+    pub data_interface: Option<String>,
+    /// All data interface options as `(interface, option, value)`.
+    pub data_interface_options: Vec<(String, String, String)>,
+    /// All local files (see [`Query::local_file`]).
+    pub local_files: Vec<(RecordType, PathBuf)>,
+    /// The record types of all readers (see [`Query::from_reader`]).
+    pub readers: Vec<RecordType>,
+    /// A description of each client-side filter, applied in the iterator.
+    pub client_filters: Vec<String>,
+    pub limit: Option<usize>,
+    /// The window of the reordering buffer (see [`Query::reorder_window`]).
+    pub reorder_window: Option<time::Duration>,
+    /// The number of retries and the initial backoff when starting the stream (see
+    /// [`Query::retry_start`]). This is `None` if starting the stream is not retried for this
+    /// query, e.g., because it reads local files or readers.
+    pub start_retry: Option<(u32, Duration)>,
+}
+
+impl Display for QueryPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.data_interface.as_deref() {
+            Some(interface) => writeln!(f, "data interface: {interface}")?,
+            None => writeln!(f, "data interface: (default)")?,
+        }
+        for (interface, option, value) in &self.data_interface_options {
+            writeln!(f, "option: {interface}.{option} = {value}")?;
+        }
+        for (record_type, path) in &self.local_files {
+            writeln!(f, "local file ({record_type}): {}", path.display())?;
+        }
+        for record_type in &self.readers {
+            writeln!(f, "reader ({record_type})")?;
+        }
+        match self.interval {
+            FilterInterval::Open => writeln!(f, "interval: open")?,
+            FilterInterval::Interval { start, stop: None } => {
+                writeln!(f, "interval: {start} - (live)")?
+            }
+            FilterInterval::Interval {
+                start,
+                stop: Some(stop),
+            } => writeln!(f, "interval: {start} - {stop}")?,
+            FilterInterval::Since { amount, unit, live } => writeln!(
+                f,
+                "interval: last {amount}{unit}{}",
+                if live { " (live)" } else { "" }
+            )?,
+        }
+        if let Some(period) = self.rib_period {
+            writeln!(f, "rib period: {period}s")?;
+        }
+        for (kind, value) in &self.filters {
+            writeln!(f, "filter: {kind} {value}")?;
+        }
+        for filter in &self.client_filters {
+            writeln!(f, "client filter: {filter}")?;
+        }
+        if let Some(limit) = self.limit {
+            writeln!(f, "limit: {limit} elements")?;
+        }
+        if let Some(window) = self.reorder_window {
+            writeln!(f, "reorder window: {window}")?;
+        }
+        if let Some((retries, backoff)) = self.start_retry {
+            writeln!(f, "start retries: {retries} (backoff {backoff:?})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Match `name` against a glob `pattern` in which `*` matches any sequence of bytes, ignoring
+/// ASCII case.
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|i| glob_match(rest, &name[i..])),
+        Some((c, rest)) => name
+            .split_first()
+            .is_some_and(|(n, name)| c.eq_ignore_ascii_case(n) && glob_match(rest, name)),
+    }
+}
+
+/// The name of a libbgpstream filter type, as shown in a [`QueryPlan`].
+fn filter_type_name(filter_type: bgpstream_filter_type_t) -> &'static str {
+    match filter_type {
+        FILTER_TYPE_PROJECT => "project",
+        FILTER_TYPE_COLLECTOR => "collector",
+        FILTER_TYPE_RECORD_TYPE => "record-type",
+        FILTER_TYPE_ELEM_TYPE => "elem-type",
+        FILTER_TYPE_ELEM_PEER_ASN => "peer-asn",
+        FILTER_TYPE_ELEM_NOT_PEER_ASN => "not-peer-asn",
+        FILTER_TYPE_ELEM_ORIGIN_ASN => "origin-asn",
+        FILTER_TYPE_ELEM_PREFIX_ANY => "prefix-any",
+        FILTER_TYPE_ELEM_PREFIX_EXACT => "prefix-exact",
+        FILTER_TYPE_ELEM_PREFIX_LESS => "prefix-less",
+        FILTER_TYPE_ELEM_PREFIX_MORE => "prefix-more",
+        FILTER_TYPE_ELEM_COMMUNITY => "community",
+        FILTER_TYPE_ELEM_ASPATH => "aspath",
+        FILTER_TYPE_ELEM_IP_VERSION => "ip-version",
+        _ => "unknown",
+    }
 }
 
+/// A reader that is shared among all clones of a [`Query`], and consumed by the first stream
+/// started from any of them.
+type SharedReader = Arc<Mutex<Option<Box<dyn Read + Send>>>>;
+
 impl Query {
     /// Create a new, empty query
     pub fn new() -> Self {
         Default::default()
     }
 
-    /// Filter by the record type (either `RecordType::Updates` or `RecordType::RIBs`).
-    pub fn record_type(&mut self, t: RecordType) -> &mut Self {
-        self.filters.push((
-            FILTER_TYPE_RECORD_TYPE,
-            match t {
-                RecordType::Updates => CString::new("updates").unwrap(),
-                RecordType::RIBs => CString::new("ribs").unwrap(),
-            },
-        ));
+    /// Apply a query fragment, i.e., a function that configures part of the query. This allows
+    /// defining reusable fragments once, and combining them in the builder chain. Fragments are
+    /// applied in order, so they follow the same replace or add semantics as the methods they call.
+    ///
+    /// ```
+    /// use routeviews::stream::*;
+    ///
+    /// /// Only the peers we trust.
+    /// fn production_peers(query: &mut Query) {
+    ///     query.peer_asn("3356").peer_asn("174");
+    /// }
+    ///
+    /// /// Only IPv6 prefixes.
+    /// fn v6_only(query: &mut Query) {
+    ///     query.ip_version(IpVersion::IPv6);
+    /// }
+    ///
+    /// let plan = Query::new()
+    ///     .collector(Collector::RouteView(RouteView::Amsix))
+    ///     .apply(production_peers)
+    ///     .apply(v6_only)
+    ///     .describe();
+    /// assert_eq!(
+    ///     plan.filters,
+    ///     vec![
+    ///         ("collector", "route-views.amsix".to_string()),
+    ///         ("peer-asn", "3356".to_string()),
+    ///         ("peer-asn", "174".to_string()),
+    ///         ("ip-version", "6".to_string()),
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// Fragments that need parameters can be returned from a function as a closure, e.g.,
+    /// `fn since(start: OffsetDateTime) -> impl FnOnce(&mut Query)`.
+    pub fn apply(&mut self, f: impl FnOnce(&mut Query)) -> &mut Self {
+        f(self);
         self
     }
 
-    /// Filter by the collector
+    /// Describe everything this query configures (filters, interval, data interface and its
+    /// options, and client-side filters) without starting a stream. This is useful to debug
+    /// queries that yield unexpected results, or to log what a job actually runs.
+    pub fn describe(&self) -> QueryPlan {
+        let string = |s: &CString| s.to_string_lossy().into_owned();
+        QueryPlan {
+            filters: self.named_filters(),
+            interval: self.interval,
+            rib_period: self.rib_period,
+            data_interface: self.data_interface.as_ref().map(string),
+            data_interface_options: self
+                .data_interface_options
+                .iter()
+                .map(|(i, o, v)| (string(i), string(o), string(v)))
+                .collect(),
+            local_files: self.local_files.clone(),
+            readers: self.readers.iter().map(|(t, _)| *t).collect(),
+            client_filters: self.client_filters.iter().map(|f| f.to_string()).collect(),
+            limit: self.limit,
+            reorder_window: self.reorder_window,
+            start_retry: self.start_retry.filter(|_| self.start_is_retryable()),
+        }
+    }
+
+    /// All libbgpstream filters as `(filter type, value)`.
+    fn named_filters(&self) -> Vec<(&'static str, String)> {
+        self.filters
+            .iter()
+            .map(|(t, value)| (filter_type_name(*t), value.to_string_lossy().into_owned()))
+            .collect()
+    }
+
+    /// All libbgpstream filters of the query that `element` satisfies, as `(filter type, value)`
+    /// (see [`QueryPlan::filters`]). This is a best-effort attribution evaluated client-side, as
+    /// libbgpstream does not report which filter matched. It helps to understand which of several
+    /// filters of the same type (combined using a logical OR) let an element through.
+    ///
+    /// Only element-level filters are evaluated, with the same semantics as in
+    /// [`Element::matches`]. Record-level filters (project, collector, and record type) and AS path
+    /// expressions cannot be attributed to an element and are never included. Use
+    /// [`crate::adapter::ElementIteratorExt::with_matched_filters`] to annotate a stream.
+    pub fn matched_filters(&self, element: &Element) -> Vec<(&'static str, String)> {
+        satisfied_filters(&self.named_filters(), element)
+    }
+
+    /// Check whether `element` passes all filters that can be evaluated on it (see
+    /// [`Element::matches`]).
+    pub(crate) fn matches(&self, element: &Element) -> bool {
+        let filters = self.named_filters();
+        let filters = filters.iter().map(|(n, v)| (*n, v.as_str()));
+        matches_filters(filters, element) && self.client_filters.iter().all(|f| f.matches(element))
+    }
+
+    /// Filter by the record type (either `RecordType::Updates` or `RecordType::RIBs`). This
+    /// replaces any record type filter added before.
+    pub fn record_type(&mut self, t: RecordType) -> &mut Self {
+        self.replace_filter(FILTER_TYPE_RECORD_TYPE, CString::new(t.as_str()).unwrap())
+    }
+
+    /// Filter by the collector. This replaces any collector filter added before (including those
+    /// added with [`Query::collector_name`]). Use [`Query::add_collector`] to stream from multiple
+    /// collectors.
     pub fn collector(&mut self, collector: Collector) -> &mut Self {
+        self.replace_filter(FILTER_TYPE_COLLECTOR, collector.cstring())
+    }
+
+    /// Add a collector to the query, in addition to all collectors added before. Records from any
+    /// of the added collectors are included in the stream.
+    pub fn add_collector(&mut self, collector: Collector) -> &mut Self {
         self.filters
             .push((FILTER_TYPE_COLLECTOR, collector.cstring()));
         self
     }
 
+    /// Add multiple collectors to the query (see [`Query::add_collector`]).
+    pub fn add_collectors(&mut self, collectors: impl IntoIterator<Item = Collector>) -> &mut Self {
+        for collector in collectors {
+            self.add_collector(collector);
+        }
+        self
+    }
+
+    /// Add all known collectors whose name matches the glob `pattern`, in addition to all
+    /// collectors added before (see [`Query::add_collector`]). The pattern is matched against the
+    /// names of [`Collector::all`] (as shown by its `Display` implementation), ignoring case. A
+    /// `*` matches any sequence of characters (including none), and all other characters match
+    /// themselves. For instance, `rrc*` selects all RIS collectors, and `route-views*` all
+    /// RouteViews collectors.
+    ///
+    /// The pattern is expanded once, when building the query, into one collector filter per
+    /// match. Collectors that are not known to this crate are never matched; use
+    /// [`Query::collector_name`] for them. Returns an error if no collector matches, as the query
+    /// would otherwise include all collectors.
+    ///
+    /// ```
+    /// use routeviews::stream::*;
+    ///
+    /// let mut query = Query::new();
+    /// query.collector_pattern("route-views*saopaulo").unwrap();
+    /// assert_eq!(
+    ///     query.describe().filters,
+    ///     vec![("collector", "route-views2saopaulo".to_string())]
+    /// );
+    /// assert!(Query::new().collector_pattern("nonexistent*").is_err());
+    /// ```
+    pub fn collector_pattern(&mut self, pattern: &str) -> Result<&mut Self, ParseError> {
+        let matches: Vec<Collector> = Collector::all()
+            .filter(|c| c.matches_pattern(pattern))
+            .collect();
+        if matches.is_empty() {
+            return Err(ParseError::new("collector pattern", pattern));
+        }
+        Ok(self.add_collectors(matches))
+    }
+
     /// Filter by the collector, using the raw name. A list of available collectors can be found
-    /// [here](https://bgpstream.caida.org/data#!routeviews).
+    /// [here](https://bgpstream.caida.org/data#!routeviews). This replaces any collector filter
+    /// added before (including those added with [`Query::collector`]).
     pub fn collector_name(&mut self, s: impl Into<Vec<u8>>) -> &mut Self {
-        self.filters
-            .push((FILTER_TYPE_COLLECTOR, CString::new(s).unwrap()));
-        self
+        self.replace_filter(FILTER_TYPE_COLLECTOR, CString::new(s).unwrap())
     }
 
-    /// Use RouteViews or RIS.
+    /// Use RouteViews or RIS. This replaces any project filter added before. Use
+    /// [`Query::add_project`] to stream from multiple projects.
     pub fn project(&mut self, project: Project) -> &mut Self {
-        self.filters.push((
-            FILTER_TYPE_PROJECT,
-            match project {
-                Project::RouteViews => CString::new("routeviews").unwrap(),
-                Project::RIS => CString::new("ris").unwrap(),
-            },
-        ));
+        self.replace_filter(FILTER_TYPE_PROJECT, project.cstring())
+    }
+
+    /// Add a project to the query, in addition to all projects added before. Records from any of
+    /// the added projects are included in the stream.
+    pub fn add_project(&mut self, project: Project) -> &mut Self {
+        self.filters.push((FILTER_TYPE_PROJECT, project.cstring()));
+        self
+    }
+
+    /// Use only the RIPE NCC Routing Information Service. Shortcut for
+    /// `self.project(Project::RIS)`.
+    pub fn only_ris(&mut self) -> &mut Self {
+        self.project(Project::RIS)
+    }
+
+    /// Use only RouteViews. Shortcut for `self.project(Project::RouteViews)`.
+    pub fn only_routeviews(&mut self) -> &mut Self {
+        self.project(Project::RouteViews)
+    }
+
+    /// Remove all filters of type `filter_type`, and add a new filter with `value`.
+    fn replace_filter(
+        &mut self,
+        filter_type: bgpstream_filter_type_t,
+        value: CString,
+    ) -> &mut Self {
+        self.filters.retain(|(t, _)| *t != filter_type);
+        self.filters.push((filter_type, value));
         self
     }
 
@@ -471,6 +1203,35 @@ impl Query {
         self
     }
 
+    /// Keep only elements whose AS path contains `asn` anywhere (including as the peer or the
+    /// origin). This adds the AS path filter `_<asn>_` (see [`Query::as_path`]), where `_` matches
+    /// the start or the end of the path, or the separator between two AS numbers, such that
+    /// `AS3356` does not match `AS13356`.
+    ///
+    /// Like all AS path filters, multiple calls are combined with a logical OR, i.e., the path
+    /// must contain any of the AS numbers.
+    ///
+    /// ```
+    /// use routeviews::stream::*;
+    ///
+    /// let plan = Query::new().path_contains(3356).path_origin(64512).describe();
+    /// assert_eq!(
+    ///     plan.filters,
+    ///     vec![("aspath", "_3356_".to_string()), ("aspath", "_64512$".to_string())]
+    /// );
+    /// ```
+    pub fn path_contains(&mut self, asn: u32) -> &mut Self {
+        self.as_path(format!("_{asn}_"))
+    }
+
+    /// Keep only elements whose AS path ends with `asn`, i.e., that are originated by `asn`. This
+    /// adds the AS path filter `_<asn>$` (see [`Query::path_contains`]). In contrast to
+    /// [`Query::origin_asn`], this uses the AS path filter, so it is combined with a logical OR
+    /// with [`Query::path_contains`] and other AS path filters.
+    pub fn path_origin(&mut self, asn: u32) -> &mut Self {
+        self.as_path(format!("_{asn}$"))
+    }
+
     /// Filter the community value. The value is a `ASN:COMM` string pair. You can provide an
     /// asterics (e.g., `*:COMM`) to match on all AS numbers (or all community values).
     pub fn community(&mut self, s: impl Into<Vec<u8>>) -> &mut Self {
@@ -483,10 +1244,7 @@ impl Query {
     pub fn ip_version(&mut self, version: IpVersion) -> &mut Self {
         self.filters.push((
             FILTER_TYPE_ELEM_IP_VERSION,
-            match version {
-                IpVersion::IPv4 => CString::new("4").unwrap(),
-                IpVersion::IPv6 => CString::new("6").unwrap(),
-            },
+            CString::new(version.as_str()).unwrap(),
         ));
         self
     }
@@ -512,50 +1270,351 @@ impl Query {
         self
     }
 
-    /// Match a given prefix. The kind pf prefix match is given by `kind`. The prefix `s` must be
-    /// a string (either IPv4 or IPv6).
-    pub fn prefix(&mut self, kind: PrefixMatchType, s: impl Into<Vec<u8>>) -> &mut Self {
-        self.filters.push((
-            match kind {
-                PrefixMatchType::Any => FILTER_TYPE_ELEM_PREFIX_ANY,
-                PrefixMatchType::Exact => FILTER_TYPE_ELEM_PREFIX_EXACT,
-                PrefixMatchType::Less => FILTER_TYPE_ELEM_PREFIX_LESS,
-                PrefixMatchType::More => FILTER_TYPE_ELEM_PREFIX_MORE,
-            },
-            CString::new(s).unwrap(),
-        ));
+    /// Exclude all elements whose peer AS number is any of `asns`. This adds one negative peer AS
+    /// filter (see [`Query::not_peer_asn`]) per AS number.
+    ///
+    /// Negative filters are combined differently than positive ones: An element is excluded if its
+    /// peer AS number matches *any* of the excluded AS numbers. Put differently, an element is
+    /// only included if its peer AS number differs from *all* excluded AS numbers. Combined with
+    /// positive peer AS filters (see [`Query::peer_asn`]), an element must match one of the
+    /// positive filters and none of the negative ones.
+    pub fn exclude_peer_asns(&mut self, asns: impl IntoIterator<Item = u32>) -> &mut Self {
+        for asn in asns {
+            self.not_peer_asn(asn.to_string());
+        }
         self
     }
 
-    /// The element type filter can be used to limit the stream to only certain element types. See
-    /// [`crate::element::ElementType`] for options.
-    pub fn event_type(&mut self, t: ElementTypeDescr) -> &mut Self {
-        self.filters.push((
-            FILTER_TYPE_ELEM_TYPE,
-            match t {
-                ElementTypeDescr::RIBs => CString::new("ribs").unwrap(),
-                ElementTypeDescr::Withdrawals => CString::new("withdrawals").unwrap(),
-                ElementTypeDescr::Announcements => CString::new("announcements").unwrap(),
-                ElementTypeDescr::PeerStates => CString::new("peerstates").unwrap(),
-            },
-        ));
+    /// Keep only elements whose peer AS number lies within `range`.
+    ///
+    /// This filter is applied client-side, see [`crate::filter`].
+    pub fn peer_asn_range(&mut self, range: RangeInclusive<u32>) -> &mut Self {
+        self.client_filters.push(ClientFilter::PeerAsn(range));
         self
     }
 
-    /// Set the time interval for the stream.
-    pub fn interval(&mut self, interval: FilterInterval) -> &mut Self {
-        self.interval = interval;
+    /// Keep only RIB entries and announcements whose origin AS number lies within `range`. If the
+    /// origin is an AS set, the element is kept if any member of the set lies within `range`.
+    /// Withdrawals and peer state changes are skipped.
+    ///
+    /// This filter is applied client-side, see [`crate::filter`].
+    pub fn origin_asn_range(&mut self, range: RangeInclusive<u32>) -> &mut Self {
+        self.client_filters.push(ClientFilter::OriginAsn(range));
         self
     }
 
-    /// Set the RIB period filter for the current stream. Configure the minimum BGP time interval
-    /// between two consecutive RIB files that belong to the same collector. This information can
-    /// be modified once the stream has started.
-    pub fn rib_period(&mut self, secs: u32) -> &mut Self {
-        self.rib_period = Some(secs);
+    /// Keep only RIB entries and announcements whose AS path length lies within `range`. The
+    /// length is computed by [`crate::element::Update::as_path_len`], i.e., each AS set counts as
+    /// a single hop, and prepended AS numbers are counted every time they appear. Withdrawals and
+    /// peer state changes are skipped.
+    ///
+    /// This filter is applied client-side, see [`crate::filter`].
+    pub fn as_path_len_range(&mut self, range: RangeInclusive<usize>) -> &mut Self {
+        self.client_filters.push(ClientFilter::AsPathLen(range));
+        self
+    }
+
+    /// Keep only RIB entries, announcements and withdrawals whose prefix belongs to `family` and
+    /// has a prefix length within `range` (e.g., `/24` to `/32` for IPv4). Elements of the other
+    /// family and peer state changes are skipped. Hence, adding this filter once for each family
+    /// yields no elements at all.
+    ///
+    /// This filter is applied client-side, see [`crate::filter`].
+    ///
+    /// # Panics
+    /// Panics if `range` is empty, or if it exceeds the maximum prefix length of `family` (32 for
+    /// IPv4, and 128 for IPv6).
+    pub fn prefix_len_range(&mut self, family: IpVersion, range: RangeInclusive<u8>) -> &mut Self {
+        let max_len = match family {
+            IpVersion::IPv4 => 32,
+            IpVersion::IPv6 => 128,
+        };
+        assert!(!range.is_empty(), "The prefix length range is empty");
+        assert!(
+            *range.end() <= max_len,
+            "The prefix length of IPv{family} is at most {max_len}"
+        );
+        self.client_filters
+            .push(ClientFilter::PrefixLen(family, range));
+        self
+    }
+
+    /// Skip RIB entries, announcements, and withdrawals whose prefix lies within reserved address
+    /// space (e.g., RFC 1918 private space, documentation prefixes, or IPv6 unique local
+    /// addresses). See [`crate::element::is_bogon_prefix`] for the table of reserved prefixes.
+    /// Peer state changes are kept.
+    ///
+    /// This filter is applied client-side, see [`crate::filter`].
+    pub fn drop_bogon_prefixes(&mut self) -> &mut Self {
+        self.client_filters.push(ClientFilter::NoBogonPrefix);
+        self
+    }
+
+    /// Skip RIB entries and announcements whose AS path still contains `AS_TRANS` (23456), i.e., a
+    /// 4-byte AS number that libbgpstream could not reconstruct from the `AS4_PATH` attribute. Such
+    /// paths would attribute routes to the wrong AS (see [`crate::element::Update`] for details).
+    /// Withdrawals and peer state changes are kept.
+    ///
+    /// This filter is applied client-side, see [`crate::filter`].
+    pub fn skip_as_trans(&mut self) -> &mut Self {
+        self.client_filters.push(ClientFilter::NoAsTrans);
+        self
+    }
+
+    /// Keep only peer state changes into `state`, e.g., sessions coming up with
+    /// [`PeerState::Established`]. All other elements are skipped. Combine it with
+    /// [`Query::peer_state_from`] to select a specific transition.
+    ///
+    /// This filter is applied client-side, see [`crate::filter`].
+    pub fn peer_state_into(&mut self, state: PeerState) -> &mut Self {
+        self.client_filters.push(ClientFilter::PeerStateInto(state));
+        self
+    }
+
+    /// Keep only peer state changes from `state`, e.g., sessions going down from
+    /// [`PeerState::Established`]. All other elements are skipped. Combine it with
+    /// [`Query::peer_state_into`] to select a specific transition.
+    ///
+    /// This filter is applied client-side, see [`crate::filter`].
+    pub fn peer_state_from(&mut self, state: PeerState) -> &mut Self {
+        self.client_filters.push(ClientFilter::PeerStateFrom(state));
+        self
+    }
+
+    /// Keep only RIB entries and announcements whose communities match `matcher`. This allows
+    /// logical combinations of communities (e.g., has `65000:100` and not `65000:666`), which
+    /// cannot be expressed with [`Query::community`]. See [`CommunityMatcher`] for the exact
+    /// semantics. Withdrawals and peer state changes are skipped.
+    ///
+    /// This filter is applied client-side, see [`crate::filter`].
+    pub fn community_matcher(&mut self, matcher: &CommunityMatcher) -> &mut Self {
+        self.client_filters
+            .push(ClientFilter::Communities(matcher.clone()));
+        self
+    }
+
+    /// Keep only RIB entries and announcements that carry at least one community `asn:value` that
+    /// matches, where `None` is a wildcard. For example, `community_wildcard(None, Some(100))`
+    /// matches `*:100` (any AS number with the value 100), and `community_wildcard(Some(65000),
+    /// None)` matches `65000:*`. With both parts set to `None`, any element with at least one
+    /// community matches. Withdrawals and peer state changes are skipped.
+    ///
+    /// In contrast to the wildcards of [`Query::community`], whose semantics depend on the version
+    /// of libbgpstream, this filter is applied client-side with exactly these semantics. Calling
+    /// it multiple times requires all patterns to match (possibly by different communities), as
+    /// client-side filters are combined using a logical AND.
+    pub fn community_wildcard(&mut self, asn: Option<u16>, value: Option<u16>) -> &mut Self {
+        self.client_filters
+            .push(ClientFilter::CommunityWildcard(asn, value));
+        self
+    }
+
+    /// Skip all RIB entries, announcements, and withdrawals whose prefix is equal to or more
+    /// specific than any of `nets` (e.g., to exclude your own address space from a global scan).
+    /// Peer state changes are kept. The prefixes are stored in a patricia trie, so the cost per
+    /// element does not depend on the number of excluded prefixes. Use
+    /// [`Query::exclude_prefixes_exact`] to only skip the exact prefixes.
+    ///
+    /// This is the negative counterpart to [`Query::prefix`], applied client-side (see
+    /// [`crate::filter`]). Calling it multiple times excludes the prefixes of all calls.
+    pub fn exclude_prefixes(&mut self, nets: impl IntoIterator<Item = IpNet>) -> &mut Self {
+        let exclusion = PrefixExclusion::new(nets, false);
+        self.client_filters
+            .push(ClientFilter::ExcludePrefixes(exclusion));
+        self
+    }
+
+    /// Like [`Query::exclude_prefixes`], but only skip elements whose prefix is exactly one of
+    /// `nets`. More specific prefixes are kept.
+    pub fn exclude_prefixes_exact(&mut self, nets: impl IntoIterator<Item = IpNet>) -> &mut Self {
+        let exclusion = PrefixExclusion::new(nets, true);
+        self.client_filters
+            .push(ClientFilter::ExcludePrefixes(exclusion));
+        self
+    }
+
+    /// Match a given prefix. The kind pf prefix match is given by `kind`. The prefix `s` must be
+    /// a string (either IPv4 or IPv6).
+    pub fn prefix(&mut self, kind: PrefixMatchType, s: impl Into<Vec<u8>>) -> &mut Self {
+        self.filters.push((
+            match kind {
+                PrefixMatchType::Any => FILTER_TYPE_ELEM_PREFIX_ANY,
+                PrefixMatchType::Exact => FILTER_TYPE_ELEM_PREFIX_EXACT,
+                PrefixMatchType::Less => FILTER_TYPE_ELEM_PREFIX_LESS,
+                PrefixMatchType::More => FILTER_TYPE_ELEM_PREFIX_MORE,
+            },
+            CString::new(s).unwrap(),
+        ));
+        self
+    }
+
+    /// The element type filter can be used to limit the stream to only certain element types. See
+    /// [`crate::element::ElementType`] for options.
+    pub fn event_type(&mut self, t: ElementTypeDescr) -> &mut Self {
+        self.filters
+            .push((FILTER_TYPE_ELEM_TYPE, CString::new(t.as_str()).unwrap()));
+        self
+    }
+
+    /// Limit the stream to the given element types, replacing all element type filters added
+    /// before (with [`Query::event_type`]). The types are passed to libbgpstream as element type
+    /// filters, which are combined using a logical OR. Hence, `element_types(&[Announcements,
+    /// Withdrawals])` keeps only announcements and withdrawals. An empty list removes the filter,
+    /// allowing all element types.
+    ///
+    /// RIB dumps only contain RIB entries, and update dumps only contain announcements,
+    /// withdrawals, and peer state changes. If the record type filter ([`Query::record_type`])
+    /// excludes all allowed element types, [`Query::validate`] (and thus [`Query::run`]) fails with
+    /// [`BgpStreamError::RecordTypeExcludesElementTypes`], instead of yielding an empty stream.
+    pub fn element_types(&mut self, types: &[ElementTypeDescr]) -> &mut Self {
+        self.filters.retain(|(t, _)| *t != FILTER_TYPE_ELEM_TYPE);
+        for t in types {
+            self.event_type(*t);
+        }
+        self
+    }
+
+    /// Set the time interval for the stream.
+    pub fn interval(&mut self, interval: FilterInterval) -> &mut Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set the time interval for the stream from a range of timestamps. A bounded range
+    /// (`start..stop`) sets [`FilterInterval::Interval`] with `stop: Some(stop)`. A range without
+    /// an end (`start..`) sets `stop: None`, which enables live mode (see [`Query::live`]).
+    ///
+    /// ```
+    /// # use routeviews::stream::*;
+    /// use time::macros::datetime;
+    ///
+    /// let mut query = Query::new();
+    /// query.time_range(datetime!(2023-11-08 09:55 UTC)..datetime!(2023-11-08 10:05 UTC));
+    /// ```
+    pub fn time_range(&mut self, range: impl Into<FilterInterval>) -> &mut Self {
+        self.interval(range.into())
+    }
+
+    /// Resume an interrupted stream from a checkpoint, i.e., the time of the last record processed
+    /// (see [`BgpStream::last_record_time`]). This sets the start of the interval to `checkpoint`
+    /// and keeps its end, if the interval was set with [`FilterInterval::Interval`]. Any other
+    /// interval is replaced with the open interval `checkpoint..` (live mode).
+    ///
+    /// The start of the interval is inclusive, so all records at the checkpoint are read again.
+    /// Since the elements of a record are not numbered, make the processing idempotent, or skip
+    /// elements up to the last one processed.
+    ///
+    /// ```no_run
+    /// # use routeviews::stream::*;
+    /// # fn load_checkpoint() -> Option<time::OffsetDateTime> { None }
+    /// # fn store_checkpoint(_: time::OffsetDateTime) {}
+    /// use time::macros::datetime;
+    ///
+    /// # fn main() -> Result<(), routeviews::BgpStreamError> {
+    /// let mut query = Query::new();
+    /// query
+    ///     .collector(Collector::RouteView(RouteView::Amsix))
+    ///     .time_range(datetime!(2023-11-01 00:00 UTC)..datetime!(2023-11-08 00:00 UTC));
+    /// if let Some(checkpoint) = load_checkpoint() {
+    ///     query.resume_from(checkpoint);
+    /// }
+    /// let mut stream = query.run()?;
+    /// while let Some(element) = stream.next() {
+    ///     let _ = element?;
+    ///     if let Some(time) = stream.last_record_time() {
+    ///         store_checkpoint(time);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resume_from(&mut self, checkpoint: OffsetDateTime) -> &mut Self {
+        let stop = match self.interval {
+            FilterInterval::Interval { stop, .. } => stop,
+            FilterInterval::Open | FilterInterval::Since { .. } => None,
+        };
+        self.interval(FilterInterval::Interval {
+            start: checkpoint,
+            stop,
+        })
+    }
+
+    /// Set the time interval from strings, e.g., as given on the command line or in a
+    /// configuration file. Each string is parsed with [`parse_time`], which accepts RFC 3339,
+    /// `YYYY-MM-DD HH:MM[:SS]` (assuming UTC), `YYYY-MM-DD`, and Unix timestamps. Without `stop`,
+    /// the interval has no end, which enables live mode (see [`Query::live`]). Returns
+    /// [`BgpStreamError::Parse`] if a string cannot be parsed, without changing the query.
+    ///
+    /// ```
+    /// # use routeviews::stream::*;
+    /// let mut query = Query::new();
+    /// query.interval_parse("2023-11-08 09:55", Some("2023-11-08T10:05:00Z"))?;
+    /// assert!(query.interval_parse("2023-11-08", Some("tomorrow")).is_err());
+    /// # Ok::<(), routeviews::BgpStreamError>(())
+    /// ```
+    pub fn interval_parse(
+        &mut self,
+        start: &str,
+        stop: Option<&str>,
+    ) -> Result<&mut Self, BgpStreamError> {
+        let start = parse_time(start)?;
+        let stop = stop.map(parse_time).transpose()?;
+        Ok(self.interval(FilterInterval::Interval { start, stop }))
+    }
+
+    /// Stream all events from `start` until the moment of this call, and then stop. This sets
+    /// [`FilterInterval::Interval`] with `stop: Some(now)`, where `now` is captured when the query
+    /// is built, not when it runs. Hence, running the same query later yields the same data, which
+    /// makes it reproducible.
+    ///
+    /// In contrast, [`Query::live`] (or an interval with `stop: None`) never stops: it keeps
+    /// waiting for new data after reaching the current time. Note that the collectors publish
+    /// their dumps with a delay, so the most recent minutes before `now` may be missing if the
+    /// query runs right away.
+    pub fn interval_until_now(&mut self, start: OffsetDateTime) -> &mut Self {
+        self.interval(FilterInterval::Interval {
+            start,
+            stop: Some(OffsetDateTime::now_utc()),
+        })
+    }
+
+    /// Stream all events from now on, in live mode. This sets the interval to start at the current
+    /// time without an end (see [`FilterInterval::Interval`]).
+    ///
+    /// **Warning**: The resulting [`BgpStream`] is an infinite iterator. Under normal operation, it
+    /// never returns `None`, but blocks until new data is available. Stop it by dropping the stream
+    /// (e.g., by breaking out of the loop).
+    pub fn live(&mut self) -> &mut Self {
+        self.interval(FilterInterval::Interval {
+            start: OffsetDateTime::now_utc(),
+            stop: None,
+        })
+    }
+
+    /// Set the RIB period filter for the current stream. Configure the minimum BGP time interval
+    /// between two consecutive RIB files that belong to the same collector. This information can
+    /// be modified once the stream has started (see [`BgpStream::set_rib_period`]).
+    pub fn rib_period(&mut self, secs: u32) -> &mut Self {
+        self.rib_period = Some(secs);
         self
     }
 
+    /// Set the RIB period filter (see [`Query::rib_period`]) from a duration, e.g.,
+    /// `Duration::hours(8)` to get at most one RIB dump every 8 hours per collector. Returns
+    /// [`BgpStreamError::InvalidRibPeriod`] if `period` is not positive, contains fractional
+    /// seconds, or exceeds `u32::MAX` seconds.
+    pub fn rib_period_duration(
+        &mut self,
+        period: time::Duration,
+    ) -> Result<&mut Self, BgpStreamError> {
+        if !period.is_positive() || period.subsec_nanoseconds() != 0 {
+            return Err(BgpStreamError::InvalidRibPeriod(period));
+        }
+        let secs = u32::try_from(period.whole_seconds())
+            .map_err(|_| BgpStreamError::InvalidRibPeriod(period))?;
+        Ok(self.rib_period(secs))
+    }
+
     /// Set the directory of where to store the cache.
     pub fn cache(&mut self, dir: impl Into<Vec<u8>>) -> &mut Self {
         self.data_interface_options.push((
@@ -566,6 +1625,43 @@ impl Query {
         self
     }
 
+    /// Set the cache directory (see [`Query::cache`]) from the environment variable
+    /// `BGPSTREAM_CACHE`, if it is set and not empty. Otherwise, the query is left unchanged. This
+    /// lets deployments configure the cache without changing the code.
+    pub fn cache_from_env(&mut self) -> &mut Self {
+        match std::env::var_os("BGPSTREAM_CACHE") {
+            Some(dir) if !dir.is_empty() => self.cache(dir.as_encoded_bytes()),
+            _ => self,
+        }
+    }
+
+    /// Set the directory of where to store the cache (see [`Query::cache`]), taking and returning
+    /// the query by value. This allows building an owned query in a single expression, e.g., to
+    /// store it in a struct or move it into a thread.
+    ///
+    /// ```
+    /// use routeviews::stream::*;
+    ///
+    /// let query: Query = Query::new().cached("/tmp/bgpstream-cache");
+    /// ```
+    pub fn cached(mut self, dir: impl Into<Vec<u8>>) -> Self {
+        self.cache(dir);
+        self
+    }
+
+    /// Set the URL of the BGPStream broker (e.g., a self-hosted broker), instead of the default
+    /// broker hosted by CAIDA. The broker data interface is validated when running the query; if
+    /// libbgpstream was compiled without it, [`Query::run`] returns
+    /// [`BgpStreamError::InterfaceNotFound`].
+    pub fn broker_url(&mut self, url: impl Into<Vec<u8>>) -> &mut Self {
+        self.data_interface_options.push((
+            CString::new("broker").unwrap(),
+            CString::new("url").unwrap(),
+            CString::new(url).unwrap(),
+        ));
+        self
+    }
+
     /// Set the data interface option.
     pub fn set_data_interface_option(
         &mut self,
@@ -580,12 +1676,513 @@ impl Query {
         ))
     }
 
-    /// Create the BGP stream and start the iteration
+    /// Select the data interface from which to read the records (e.g., `broker`, `singlefile`,
+    /// `kafka`, or `csvfile`). By default, libbgpstream uses the `broker` interface. Use
+    /// [`BgpStream::check_library`] to get a list of all available data interfaces.
+    pub fn data_interface(&mut self, interface_name: impl Into<Vec<u8>>) -> &mut Self {
+        self.data_interface = Some(CString::new(interface_name).unwrap());
+        self
+    }
+
+    /// Find the dump files in a local SQLite database using the `sqlite` data interface, instead
+    /// of asking the remote broker. Together with local dump files, this allows fully offline and
+    /// reproducible runs against a pre-built index.
+    ///
+    /// Returns an I/O error of kind [`io::ErrorKind::NotFound`] if `db_path` is not an existing
+    /// file. libbgpstream only includes the `sqlite` interface if it was compiled with SQLite
+    /// support; otherwise, starting the stream fails with [`BgpStreamError::InterfaceNotFound`]
+    /// (use [`BgpStream::check_library`] to check in advance).
+    pub fn sqlite_broker(
+        &mut self,
+        db_path: impl AsRef<Path>,
+    ) -> Result<&mut Self, BgpStreamError> {
+        let db_path = db_path.as_ref();
+        if !db_path.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("SQLite database {} does not exist", db_path.display()),
+            )
+            .into());
+        }
+        self.data_interface("sqlite");
+        self.set_data_interface_option("sqlite", "db-file", db_path.as_os_str().as_encoded_bytes());
+        Ok(self)
+    }
+
+    /// Read records from a local MRT file using the `singlefile` data interface, instead of
+    /// fetching them from the broker. `record_type` specifies whether the file contains RIB dumps
+    /// or updates. The `singlefile` interface can read at most one file of each type; calling this
+    /// function twice for the same record type will only read the last file.
+    ///
+    /// With the `compression` feature enabled, files compressed with gzip or bzip2 are detected
+    /// (by their magic bytes) and decompressed into a temporary file once the stream is started.
+    /// The temporary file is removed once the [`BgpStream`] is dropped. Uncompressed files are
+    /// passed to libbgpstream unchanged.
+    ///
+    /// Once libbgpstream reached the end of the file, the stream checks that the file ends exactly
+    /// after a complete MRT record. If the last record is cut off (e.g., by an interrupted
+    /// download), the stream yields [`BgpStreamError::TruncatedFile`] before it ends, instead of
+    /// silently dropping the partial record.
+    pub fn local_file(&mut self, record_type: RecordType, path: impl Into<PathBuf>) -> &mut Self {
+        self.data_interface("singlefile");
+        self.local_files.push((record_type, path.into()));
+        self
+    }
+
+    /// Read records in MRT format from an arbitrary reader (e.g., [`std::io::stdin`]) using the
+    /// `singlefile` data interface. `record_type` specifies whether the data contains RIB dumps or
+    /// updates. As with [`Query::local_file`], at most one source of each type can be read.
+    ///
+    /// libbgpstream can only read from files. Therefore, the entire reader is copied into a
+    /// temporary file when the stream is started, which is removed once the [`BgpStream`] is
+    /// dropped. With the `compression` feature, compressed data is decompressed as well.
+    ///
+    /// The reader is consumed by the first stream started from this query (or any of its clones).
+    /// Starting another stream from the same query fails with an I/O error.
+    ///
+    /// ```no_run
+    /// use routeviews::{stream::RecordType, Query};
+    ///
+    /// // cat updates.mrt | my-program
+    /// let stream = Query::new()
+    ///     .from_reader(RecordType::Updates, std::io::stdin())
+    ///     .run()
+    ///     .unwrap();
+    /// for elem in stream {
+    ///     println!("{:?}", elem);
+    /// }
+    /// ```
+    pub fn from_reader(
+        &mut self,
+        record_type: RecordType,
+        reader: impl Read + Send + 'static,
+    ) -> &mut Self {
+        self.data_interface("singlefile");
+        let reader: Box<dyn Read + Send> = Box::new(reader);
+        self.readers
+            .push((record_type, Arc::new(Mutex::new(Some(reader)))));
+        self
+    }
+
+    /// Read records in MRT format from an in-memory buffer using the `singlefile` data interface.
+    /// This is a shorthand for [`Query::from_reader`] with a [`std::io::Cursor`]. Malformed data
+    /// never causes a panic: the stream yields [`BgpStreamError::RecordCorrupted`],
+    /// [`BgpStreamError::RecordSourceEmptyOrCorrupted`], or [`BgpStreamError::TruncatedFile`]
+    /// instead.
+    pub fn from_bytes(&mut self, record_type: RecordType, bytes: impl Into<Vec<u8>>) -> &mut Self {
+        self.from_reader(record_type, std::io::Cursor::new(bytes.into()))
+    }
+
+    /// Choose which expensive fields of each element are extracted (see [`ParseOptions`]). By
+    /// default, all fields are extracted. For example, skip the AS path and communities when only
+    /// the prefixes of a full RIB dump are needed:
+    ///
+    /// ```no_run
+    /// use routeviews::{element::ParseOptions, Query};
+    ///
+    /// let stream = Query::new()
+    ///     .parse_options(ParseOptions {
+    ///         as_path: false,
+    ///         communities: false,
+    ///     })
+    ///     .run()
+    ///     .unwrap();
+    /// ```
+    pub fn parse_options(&mut self, options: ParseOptions) -> &mut Self {
+        self.parse_options = options;
+        self
+    }
+
+    /// Skip elements that cannot be parsed (e.g., due to an invalid IP address or an unknown peer
+    /// state) instead of yielding an error, and continue with the next element of the same record.
+    /// Skipped elements are logged as warnings through the [`log`] facade. Errors that affect the
+    /// entire stream or record (e.g., a corrupted record, or a failure to fetch the next element)
+    /// are still yielded. By default, every error is yielded.
+    pub fn tolerate_element_errors(&mut self) -> &mut Self {
+        self.tolerate_element_errors = true;
+        self
+    }
+
+    /// Yield [`BgpStreamError::RecordSourceFiltered`] whenever libbgpstream reports that a source
+    /// (i.e., a dump file) was excluded by the filters. Such sources are benign, so by default,
+    /// they are skipped silently and the stream continues with the next record.
+    pub fn report_filtered_sources(&mut self) -> &mut Self {
+        self.report_filtered_sources = true;
+        self
+    }
+
+    /// Stop the stream after yielding `n` elements. This limits the number of elements, not the
+    /// number of records. Once the limit is reached, the [`BgpStream`] iterator returns `None` and
+    /// no further records are fetched. Errors do not count towards the limit.
+    ///
+    /// In contrast to `stream.take(n)`, this stops fetching records as soon as the limit is reached
+    /// (also when using [`BgpStream::next_record`]).
+    pub fn limit(&mut self, n: usize) -> &mut Self {
+        self.limit = Some(n);
+        self
+    }
+
+    /// Restore the time order of slightly out-of-order data. Elements are held in a buffer until
+    /// they lie at least `window` behind the newest element seen so far, and are then yielded in
+    /// the order of their timestamps (elements with the same timestamp keep their original
+    /// order). Once the stream ends, all buffered elements are yielded in order. Errors are
+    /// yielded immediately, ahead of any buffered elements. [`BgpStream::into_records`] applies
+    /// the same buffer to records, ordered by the record time.
+    ///
+    /// This trades latency and memory for ordering: each element is delayed until the stream has
+    /// advanced by `window` (in data time, not wall-clock time), and the buffer holds all elements
+    /// of that span. Keep the window small (e.g., a few minutes for updates), especially for RIB
+    /// dumps, where a single record time spans a whole dump. Elements that arrive more than
+    /// `window` late are still yielded in order with the rest of the buffer, but after newer
+    /// elements that were already released.
+    ///
+    /// The buffer only applies to the [`BgpStream`] iterator and [`BgpStream::into_records`], not
+    /// to [`BgpStream::next_record`] or [`BgpStream::for_each_element`]. With the iterator,
+    /// [`BgpStream::current_collector`], [`BgpStream::current_record_time`], and
+    /// [`BgpStream::last_record_time`] refer to the record of the element yielded last (not to the
+    /// newest record fetched into the buffer), so `last_record_time` remains a safe checkpoint for
+    /// [`Query::resume_from`].
+    ///
+    /// # Panics
+    /// Panics if `window` is negative.
+    pub fn reorder_window(&mut self, window: time::Duration) -> &mut Self {
+        assert!(
+            !window.is_negative(),
+            "The reorder window must not be negative"
+        );
+        self.reorder_window = Some(window);
+        self
+    }
+
+    /// Keep each element with probability `p`, and drop it otherwise, to reduce the load on
+    /// consumers that only need trends. Elements are sampled uniformly and independently (not
+    /// whole records), after the client-side filters and before the limit and the reordering
+    /// buffer. Sampling only applies to the [`BgpStream`] iterator, not to
+    /// [`BgpStream::next_record`] or [`BgpStream::into_records`]. Requires the `rand` feature.
+    ///
+    /// The random number generator is seeded from the operating system, unless a seed is set with
+    /// [`Query::sample_seed`].
+    ///
+    /// # Panics
+    /// Panics if `p` is not in `[0, 1]`.
+    #[cfg(feature = "rand")]
+    pub fn sample_rate(&mut self, p: f64) -> &mut Self {
+        assert!(
+            (0.0..=1.0).contains(&p),
+            "The sample rate must be between 0 and 1"
+        );
+        self.sample_rate = Some(p);
+        self
+    }
+
+    /// Seed the random number generator of [`Query::sample_rate`]. Two streams with the same seed
+    /// over the same data keep the same elements. Requires the `rand` feature.
+    #[cfg(feature = "rand")]
+    pub fn sample_seed(&mut self, seed: u64) -> &mut Self {
+        self.sample_seed = Some(seed);
+        self
+    }
+
+    /// Check whether the interval is long enough to contain at least one dump. Collectors write
+    /// their data in dumps at a fixed cadence (see [`Project::rib_period`]), and the broker only
+    /// returns dumps that were already published. Hence, a short window usually yields nothing.
+    /// This returns [`BgpStreamError::IntervalShorterThanDumps`] if the window is shorter than the
+    /// shortest dump period of all selected projects and record types.
+    ///
+    /// - For [`FilterInterval::Since`], all selected record types are considered.
+    /// - For [`FilterInterval::Interval`] with a stop time, the window is only checked if RIB
+    ///   dumps are the only selected record type, since update dumps overlapping the window are
+    ///   still read and filtered to it.
+    ///
+    /// Live streams, open intervals, and queries reading from another data interface than the
+    /// broker (e.g., local files) are never reported. The projects are taken from the project and
+    /// collector filters (collectors named `rrc*` belong to RIS, all others to RouteViews). Without
+    /// any such filter, both projects are considered.
+    ///
+    /// [`Query::run`] performs this check and logs a warning if it fails, but still starts the
+    /// stream.
+    pub fn check_dump_duration(&self) -> Result<(), BgpStreamError> {
+        if self.data_interface.is_some() {
+            return Ok(());
+        }
+        let (window, only_ribs) = match self.interval {
+            FilterInterval::Since {
+                amount,
+                unit,
+                live: false,
+            } => (
+                unit.duration()
+                    .saturating_mul(i32::try_from(amount).unwrap_or(i32::MAX)),
+                false,
+            ),
+            FilterInterval::Interval {
+                start,
+                stop: Some(stop),
+            } => (stop - start, true),
+            _ => return Ok(()),
+        };
+
+        let filters = self.named_filters();
+        let values = |name: &str| -> Vec<String> {
+            filters
+                .iter()
+                .filter(|(n, _)| *n == name)
+                .map(|(_, v)| v.to_ascii_lowercase())
+                .collect()
+        };
+        let mut projects: Vec<Project> = values("project")
+            .iter()
+            .filter_map(|p| p.parse().ok())
+            .collect();
+        projects.extend(
+            values("collector")
+                .iter()
+                .map(|c| Project::of_collector_name(c)),
+        );
+        if projects.is_empty() {
+            projects = vec![Project::RouteViews, Project::RIS];
+        }
+        let mut record_types: Vec<RecordType> = values("record-type")
+            .iter()
+            .filter_map(|t| t.parse().ok())
+            .collect();
+        if record_types.is_empty() {
+            record_types = vec![RecordType::RIBs, RecordType::Updates];
+        }
+        if only_ribs && record_types.contains(&RecordType::Updates) {
+            return Ok(());
+        }
+
+        let cadence = projects
+            .iter()
+            .flat_map(|p| record_types.iter().map(|t| p.dump_period(*t)))
+            .min();
+        match cadence {
+            Some(cadence) if window < cadence => {
+                Err(BgpStreamError::IntervalShorterThanDumps { window, cadence })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the query for contradicting filters that would always result in an empty stream.
+    /// Currently, this detects:
+    ///
+    /// - Collector filters combined with project filters that exclude all of them (e.g.,
+    ///   [`Query::only_ris`] together with only RouteViews collectors). As libbgpstream combines
+    ///   multiple collector filters using a logical OR, the query is fine as long as any collector
+    ///   is part of a selected project. Returns [`BgpStreamError::CollectorProjectMismatch`].
+    /// - A record type filter combined with element type filters that the records cannot contain
+    ///   (e.g., only RIB dumps, but only announcements). Returns
+    ///   [`BgpStreamError::RecordTypeExcludesElementTypes`].
+    ///
+    /// [`Query::run`] performs this check before starting the stream.
+    pub fn validate(&self) -> Result<(), BgpStreamError> {
+        let filters = self.named_filters();
+
+        let element_types: Vec<ElementTypeDescr> = filters
+            .iter()
+            .filter(|(name, _)| *name == "elem-type")
+            .filter_map(|(_, t)| t.parse().ok())
+            .collect();
+        let record_type = filters
+            .iter()
+            .find(|(name, _)| *name == "record-type")
+            .and_then(|(_, t)| t.parse::<RecordType>().ok());
+        if let (Some(record_type), false) = (record_type, element_types.is_empty()) {
+            let contained = |t: &ElementTypeDescr| match record_type {
+                RecordType::RIBs => *t == ElementTypeDescr::RIBs,
+                RecordType::Updates => *t != ElementTypeDescr::RIBs,
+            };
+            if !element_types.iter().any(contained) {
+                return Err(BgpStreamError::RecordTypeExcludesElementTypes {
+                    record_type,
+                    element_types,
+                });
+            }
+        }
+
+        let projects: Vec<Project> = filters
+            .iter()
+            .filter(|(name, _)| *name == "project")
+            .filter_map(|(_, p)| p.parse().ok())
+            .collect();
+        let collectors: Vec<String> = filters
+            .iter()
+            .filter(|(name, _)| *name == "collector")
+            .map(|(_, c)| c.clone())
+            .collect();
+        if projects.is_empty() || collectors.is_empty() {
+            return Ok(());
+        }
+        if !collectors
+            .iter()
+            .any(|c| projects.contains(&Project::of_collector_name(c)))
+        {
+            return Err(BgpStreamError::CollectorProjectMismatch {
+                collectors,
+                projects,
+            });
+        }
+        Ok(())
+    }
+
+    /// Create the BGP stream and start the iteration. The query is validated first (see
+    /// [`Query::validate`]). If the interval is too short to contain any dump (see
+    /// [`Query::check_dump_duration`]), a warning is logged.
     pub fn run(&self) -> Result<BgpStream, BgpStreamError> {
-        BgpStream::new(self)
+        self.validate()?;
+        if let Err(e) = self.check_dump_duration() {
+            log_warn!("{e}");
+        }
+        let (mut retries, mut backoff) = self.start_retry.unwrap_or_default();
+        let retry = self.start_retry.is_some() && self.start_is_retryable();
+        loop {
+            let result = BgpStream::new(self).and_then(|mut stream| {
+                if retry {
+                    stream.prefetch()?;
+                }
+                Ok(stream)
+            });
+            match result {
+                Err(e) if retry && retries > 0 && e.is_retryable_at_start() => {
+                    log_warn!("Cannot start the BGP stream ({e}), retrying in {backoff:?}");
+                    thread::sleep(backoff);
+                    retries -= 1;
+                    backoff = backoff.saturating_mul(2);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Retry starting the stream in [`Query::run`] up to `retries` times if it fails because the
+    /// broker is briefly unavailable. The first retry waits for `backoff`, and each further retry
+    /// waits twice as long as the previous one. By default, a failure to start is returned
+    /// immediately.
+    ///
+    /// libbgpstream only queries the broker once the first record is fetched. Hence, with this
+    /// option, [`Query::run`] already fetches the first record (which blocks until it is
+    /// available, e.g., in live mode), and only returns the stream once that succeeded. Only the
+    /// errors for which [`BgpStreamError::is_retryable_at_start`] holds are retried, i.e., a
+    /// failure to start the stream or to fetch the first record. All other errors are not
+    /// transient (e.g., an unknown data interface or option, or an invalid filter) and are
+    /// returned right away, as are errors while fetching any later record.
+    ///
+    /// This only applies if the stream uses the broker (i.e., the default data interface, or
+    /// `broker` set with [`Query::data_interface`]) without local files or readers.
+    pub fn retry_start(&mut self, retries: u32, backoff: Duration) -> &mut Self {
+        self.start_retry = Some((retries, backoff));
+        self
+    }
+
+    /// Whether a failure to start the stream may be caused by a transient broker outage.
+    fn start_is_retryable(&self) -> bool {
+        let broker = self
+            .data_interface
+            .as_ref()
+            .is_none_or(|interface| interface.as_bytes() == b"broker");
+        broker && self.local_files.is_empty() && self.readers.is_empty()
+    }
+
+    /// Stream from [RIS Live](https://ris-live.ripe.net) instead of libbgpstream. RIS Live is a
+    /// websocket feed of RIPE RIS with a lower latency than the broker, but it only delivers live
+    /// data. See [`crate::rislive`] for how RIS Live messages are mapped onto elements. Requires
+    /// the `rislive` feature.
+    ///
+    /// The query is translated into RIS Live subscriptions as follows:
+    ///
+    /// - Each collector filter (e.g., [`Query::collector`] with a [`RipeNcc`] collector) becomes
+    ///   a separate subscription to that host. Without collector filters, all hosts are streamed.
+    /// - A single prefix filter ([`Query::prefix`]) is passed to the subscription, including its
+    ///   match type. If the query has multiple prefix filters, they are evaluated client-side.
+    ///
+    /// All element-level filters (element type, peer and origin AS, prefix, community, and IP
+    /// version), all client-side filters, and [`Query::limit`] are evaluated on each element
+    /// before it is yielded. AS path expressions ([`Query::as_path`]), the project and record type
+    /// filters, the interval, the RIB period, and the data interface (including local files and
+    /// readers) are ignored.
+    #[cfg(feature = "rislive")]
+    pub fn ris_live(&self) -> Result<RisLiveStream, BgpStreamError> {
+        RisLiveStream::connect(
+            self.named_filters(),
+            self.client_filters.clone(),
+            self.limit,
+        )
+    }
+
+    /// Run the query on a background thread, and deliver the elements in batches of up to `batch`
+    /// elements through a channel that buffers at most `buffer` batches. Sending one `Vec` per
+    /// batch amortizes the synchronization cost of the channel, which matters for throughput-bound
+    /// consumers of high-rate streams. Larger batches increase the throughput, but also the
+    /// latency until an element is received.
+    ///
+    /// A partial batch is sent once [`BATCH_FLUSH_TIMEOUT`] has passed since its first element.
+    /// The timeout is only checked whenever libbgpstream yields an element, as the background
+    /// thread blocks while libbgpstream waits for new data. Hence, in live streams with a low rate,
+    /// a partial batch is sent together with the first element that arrives after the timeout.
+    /// The remaining elements are sent once the stream ends.
+    ///
+    /// Errors (including those when starting the stream) are sent through the channel after the
+    /// elements that preceded them. The background thread stops once the receiver is dropped.
+    ///
+    /// # Panics
+    /// Panics if `batch` is zero.
+    pub fn run_into_channel_batched(
+        &self,
+        batch: usize,
+        buffer: usize,
+    ) -> Receiver<Result<Vec<Element>, BgpStreamError>> {
+        assert!(batch > 0, "The batch size must be positive");
+        let (tx, rx) = sync_channel(buffer);
+        let query = self.clone();
+        thread::spawn(move || {
+            let stream = match query.run() {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = tx.send(Err(e));
+                    return;
+                }
+            };
+            let mut elements = Vec::with_capacity(batch);
+            let mut first = Instant::now();
+            for element in stream {
+                let element = match element {
+                    Ok(e) => e,
+                    Err(e) => {
+                        if !elements.is_empty()
+                            && tx.send(Ok(std::mem::take(&mut elements))).is_err()
+                        {
+                            return;
+                        }
+                        if tx.send(Err(e)).is_err() {
+                            return;
+                        }
+                        continue;
+                    }
+                };
+                if elements.is_empty() {
+                    first = Instant::now();
+                }
+                elements.push(element);
+                if elements.len() >= batch || first.elapsed() >= BATCH_FLUSH_TIMEOUT {
+                    let full = std::mem::replace(&mut elements, Vec::with_capacity(batch));
+                    if tx.send(Ok(full)).is_err() {
+                        return;
+                    }
+                }
+            }
+            if !elements.is_empty() {
+                let _ = tx.send(Ok(elements));
+            }
+        });
+        rx
     }
 }
 
+/// The time after which [`Query::run_into_channel_batched`] sends a partial batch.
+pub const BATCH_FLUSH_TIMEOUT: Duration = Duration::from_millis(100);
+
 /// A BGP stream object to fetch new records. Use [`Query`] to construct a new BgpStream.
 ///
 /// A BGP stream iterates over many [`Record`]s. Each `Record` represents data collected at a
@@ -593,22 +2190,239 @@ impl Query {
 /// are the actual RIB entries, BGP updates or peer state changes.
 pub struct BgpStream {
     pub(crate) bs: NonNull<bgpstream_t>,
-    // current record, used for the iterator.
-    current_record: Option<Record<'static>>,
+    // current record, used for the iterator. It is only valid until the next record is fetched.
+    current_record: Option<RawRecord>,
+    // first record, fetched by `Query::run` before returning the stream (see `retry_start`).
+    prefetched: Option<RawRecord>,
+    // time of the last record fetched, kept after the record is exhausted.
+    pub(crate) last_record_time: Option<OffsetDateTime>,
+    // prefix of all metric names, if metrics are enabled.
+    #[cfg(feature = "metrics")]
+    metrics_prefix: Option<String>,
+    // temporary files that are removed once the stream is dropped.
+    temp_files: Vec<PathBuf>,
+    // local files read by libbgpstream, as `(record type, name, path)`, that were not yet checked
+    // for truncation (see `check_local_files`).
+    local_files: Vec<(RecordType, String, PathBuf)>,
+    // number of elements left to yield, if the query is limited.
+    remaining: Option<usize>,
+    // filters applied to each element in the iterator.
+    client_filters: Vec<ClientFilter>,
+    // fields to extract from each element.
+    pub(crate) parse_options: ParseOptions,
+    // skip elements that cannot be parsed.
+    pub(crate) tolerate_element_errors: bool,
+    // yield an error for sources excluded by the filters, instead of skipping them.
+    pub(crate) report_filtered_sources: bool,
+    // whether to record the peers of all elements yielded.
+    track_peers: bool,
+    // all peers of the elements yielded so far.
+    peers_seen: HashSet<Peer>,
+    // window of the reordering buffer, if enabled.
+    reorder_window: Option<time::Duration>,
+    // elements fetched but not yet yielded (with the metadata of their record), if reordering is
+    // enabled.
+    reorder: Option<ReorderBuffer<(Element, Option<Arc<RecordMeta>>)>>,
+    // whether to compute the metadata of each record (see `with_record_meta`).
+    track_record_meta: bool,
+    // metadata of the current record, if tracked.
+    record_meta: Option<Arc<RecordMeta>>,
+    // metadata of the record of the element yielded last, if tracked.
+    yielded_meta: Option<Arc<RecordMeta>>,
+    // counters reported by `health`.
+    pub(crate) health: StreamHealth,
+    // probability of keeping each element, and the random number generator, if sampling.
+    #[cfg(feature = "rand")]
+    sampler: Option<(f64, StdRng)>,
+}
+
+/// List the names of all options of the data interface `interface` (e.g., `broker`, `singlefile`,
+/// or `kafka`). Use these names in [`Query::set_data_interface_option`].
+pub fn list_interface_options(interface: &str) -> Result<Vec<String>, BgpStreamError> {
+    let s = BgpStream::create()?;
+    let interface_name = CString::new(interface)?;
+    unsafe {
+        let if_id = bgpstream_get_data_interface_id_by_name(s.bs.as_ptr(), interface_name.as_ptr());
+        if if_id == 0 {
+            return Err(BgpStreamError::InterfaceNotFound(interface.to_string()));
+        }
+
+        let mut p_opts = null_mut::<bgpstream_data_interface_option_t>();
+        let num = bgpstream_get_data_interface_options(s.bs.as_ptr(), if_id, &mut p_opts);
+        if num <= 0 || p_opts.is_null() {
+            return Ok(Vec::new());
+        }
+
+        Ok(slice::from_raw_parts(p_opts, num as usize)
+            .iter()
+            .map(|opt| CStr::from_ptr(opt.name).to_string_lossy().into_owned())
+            .collect())
+    }
+}
+
+/// Information about the linked libbgpstream library, see [`BgpStream::check_library`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryInfo {
+    /// All data interfaces that libbgpstream was compiled with.
+    pub data_interfaces: Vec<DataInterfaceInfo>,
+    /// Name of the data interface that is used by default.
+    pub default_data_interface: String,
+}
+
+/// A snapshot of the progress of a stream, returned by [`BgpStream::health`]. The counters start at
+/// zero when the stream is created, and are never reset.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StreamHealth {
+    /// Number of valid records fetched from libbgpstream, by any method.
+    pub records_fetched: u64,
+    /// Time of the last record fetched (see [`BgpStream::last_record_time`]). With
+    /// [`Query::reorder_window`], this is the newest record fetched into the reordering buffer.
+    pub last_record_time: Option<OffsetDateTime>,
+    /// Difference between the wall clock at the time of the snapshot and `last_record_time`.
+    pub lag: Option<time::Duration>,
+    /// Number of RIB entries yielded.
+    pub ribs: u64,
+    /// Number of announcements yielded.
+    pub announcements: u64,
+    /// Number of withdrawals yielded.
+    pub withdrawals: u64,
+    /// Number of peer state changes yielded.
+    pub peer_states: u64,
+    /// Number of errors yielded.
+    pub errors: u64,
+}
+
+impl StreamHealth {
+    /// Total number of elements yielded, of any type.
+    pub fn elements(&self) -> u64 {
+        self.ribs + self.announcements + self.withdrawals + self.peer_states
+    }
+}
+
+/// Name and description of a data interface (e.g., `broker`, `singlefile`, or `kafka`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataInterfaceInfo {
+    pub name: String,
+    pub description: String,
 }
 
 /// Iterator over elements.
 impl BgpStream {
-    fn new(query: &Query) -> Result<BgpStream, BgpStreamError> {
+    /// Create a new, unconfigured BGP stream object.
+    fn create() -> Result<BgpStream, BgpStreamError> {
+        let Some(bs) = NonNull::new(unsafe { bgpstream_create() }) else {
+            return Err(BgpStreamError::Create);
+        };
+        Ok(Self {
+            bs,
+            current_record: None,
+            prefetched: None,
+            last_record_time: None,
+            #[cfg(feature = "metrics")]
+            metrics_prefix: None,
+            temp_files: Vec::new(),
+            local_files: Vec::new(),
+            remaining: None,
+            client_filters: Vec::new(),
+            parse_options: ParseOptions::default(),
+            tolerate_element_errors: false,
+            report_filtered_sources: false,
+            track_peers: false,
+            peers_seen: HashSet::new(),
+            reorder_window: None,
+            reorder: None,
+            track_record_meta: false,
+            record_meta: None,
+            yielded_meta: None,
+            health: StreamHealth::default(),
+            #[cfg(feature = "rand")]
+            sampler: None,
+        })
+    }
+
+    /// Check that libbgpstream is usable, and return the data interfaces it was compiled with.
+    /// Use this function to diagnose deployment problems (e.g., missing Kafka support) before
+    /// running a query.
+    ///
+    /// libbgpstream does not report its version at runtime, so the version cannot be checked.
+    /// Further, if the shared libraries are missing entirely, the program fails to load before
+    /// this function can be called.
+    pub fn check_library() -> Result<LibraryInfo, BgpStreamError> {
+        let s = Self::create().map_err(|_| {
+            BgpStreamError::LibraryUnusable("cannot create a BGP stream object".to_string())
+        })?;
+
         unsafe {
-            let Some(bs) = NonNull::new(bgpstream_create()) else {
-                return Err(BgpStreamError::Create);
-            };
-            let s = Self {
-                bs,
-                current_record: None,
+            let mut p_ids = null_mut::<bgpstream_data_interface_id_t>();
+            let num = bgpstream_get_data_interfaces(s.bs.as_ptr(), &mut p_ids);
+            if num <= 0 || p_ids.is_null() {
+                return Err(BgpStreamError::LibraryUnusable(
+                    "libbgpstream was compiled without any data interface".to_string(),
+                ));
+            }
+
+            let mut data_interfaces = Vec::with_capacity(num as usize);
+            let mut default_data_interface = None;
+            let default_id = bgpstream_get_data_interface_id(s.bs.as_ptr());
+            for id in slice::from_raw_parts(p_ids, num as usize) {
+                let info = bgpstream_get_data_interface_info(s.bs.as_ptr(), *id);
+                if info.is_null() {
+                    return Err(BgpStreamError::LibraryUnusable(format!(
+                        "no information available for data interface {id}"
+                    )));
+                }
+                let info = &*info;
+                let name = CStr::from_ptr(info.name).to_string_lossy().into_owned();
+                let description = CStr::from_ptr(info.description)
+                    .to_string_lossy()
+                    .into_owned();
+                if *id == default_id {
+                    default_data_interface = Some(name.clone());
+                }
+                data_interfaces.push(DataInterfaceInfo { name, description });
+            }
+
+            let Some(default_data_interface) = default_data_interface else {
+                return Err(BgpStreamError::LibraryUnusable(
+                    "the default data interface is not available".to_string(),
+                ));
             };
 
+            Ok(LibraryInfo {
+                data_interfaces,
+                default_data_interface,
+            })
+        }
+    }
+
+    fn new(query: &Query) -> Result<BgpStream, BgpStreamError> {
+        #[cfg(feature = "tracing")]
+        let _span = crate::trace::start_span(query);
+        let result = Self::start(query);
+        #[cfg(feature = "tracing")]
+        crate::trace::stream_started(&result);
+        result
+    }
+
+    /// Create the stream, apply all options of `query`, and start it.
+    fn start(query: &Query) -> Result<BgpStream, BgpStreamError> {
+        let mut s = Self::create()?;
+        s.remaining = query.limit;
+        s.client_filters = query.client_filters.clone();
+        s.parse_options = query.parse_options;
+        s.tolerate_element_errors = query.tolerate_element_errors;
+        s.report_filtered_sources = query.report_filtered_sources;
+        s.reorder_window = query.reorder_window;
+        s.reorder = query.reorder_window.map(ReorderBuffer::new);
+        #[cfg(feature = "rand")]
+        if let Some(p) = query.sample_rate {
+            let rng = match query.sample_seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_os_rng(),
+            };
+            s.sampler = Some((p, rng));
+        }
+        unsafe {
             // add all filters
             for (filter, value) in &query.filters {
                 let filter_value = CString::new(value.as_bytes())?;
@@ -651,9 +2465,8 @@ impl BgpStream {
                 }
             }
 
-            // configure the cache
-            for (interface_name, option, value) in &query.data_interface_options {
-                // get the broker data interface id
+            // select the data interface
+            if let Some(interface_name) = &query.data_interface {
                 let if_id =
                     bgpstream_get_data_interface_id_by_name(s.bs.as_ptr(), interface_name.as_ptr());
                 if if_id == 0 {
@@ -661,28 +2474,37 @@ impl BgpStream {
                         interface_name.to_string_lossy().to_string(),
                     ));
                 }
+                bgpstream_set_data_interface(s.bs.as_ptr(), if_id);
+            }
 
-                // get the data interface option
-                let opt = bgpstream_get_data_interface_option_by_name(
-                    s.bs.as_ptr(),
-                    if_id,
-                    option.as_ptr(),
-                );
-                if opt.is_null() {
-                    return Err(BgpStreamError::InterfaceOptionNotFound(
-                        option.to_string_lossy().to_string(),
-                    ));
-                }
+            // configure the cache
+            for (interface_name, option, value) in &query.data_interface_options {
+                s.set_data_interface_option(interface_name, option, value)?;
+            }
 
-                let s_value = CString::new(value.as_bytes())?;
-                let res = bgpstream_set_data_interface_option(
-                    s.bs.as_ptr(),
-                    opt,
-                    s_value.as_ptr() as *const c_char,
-                );
-                if res != 0 {
-                    return Err(BgpStreamError::SetInterfaceOption);
-                }
+            // copy all readers into temporary files
+            let mut local_files: Vec<_> = query
+                .local_files
+                .iter()
+                .map(|(record_type, path)| (*record_type, path.display().to_string(), path.clone()))
+                .collect();
+            for (record_type, reader) in &query.readers {
+                let path = s.spool_reader(reader)?;
+                local_files.push((*record_type, "<reader>".to_string(), path));
+            }
+
+            // configure the local files
+            for (record_type, name, path) in local_files {
+                let path = s.prepare_local_file(&path)?;
+                let option = match record_type {
+                    RecordType::Updates => c"upd-file",
+                    RecordType::RIBs => c"rib-file",
+                };
+                let value = CString::new(path.as_os_str().as_encoded_bytes())?;
+                s.set_data_interface_option(c"singlefile", option, &value)?;
+                // only the last file of each type is read.
+                s.local_files.retain(|(t, _, _)| *t != record_type);
+                s.local_files.push((record_type, name, path));
             }
 
             // start the stream
@@ -695,18 +2517,674 @@ impl BgpStream {
         }
     }
 
-    /// Get the next record.
+    /// Copy the contents of a reader into a temporary file, and return its path.
+    fn spool_reader(&mut self, reader: &SharedReader) -> Result<PathBuf, BgpStreamError> {
+        let Some(mut reader) = reader.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+            return Err(
+                io::Error::other("The reader was already consumed by another stream").into(),
+            );
+        };
+        let (tmp, mut file) = crate::create_temp_file()?;
+        self.temp_files.push(tmp.clone());
+        io::copy(&mut reader, &mut file)?;
+        Ok(tmp)
+    }
+
+    /// Prepare a local file to be read by libbgpstream, decompressing it if necessary.
+    fn prepare_local_file(&mut self, path: &Path) -> Result<PathBuf, BgpStreamError> {
+        #[cfg(feature = "compression")]
+        if let Some(tmp) = crate::compression::decompress(path)? {
+            self.temp_files.push(tmp.clone());
+            return Ok(tmp);
+        }
+        Ok(path.to_path_buf())
+    }
+
+    /// Check that no local file read by libbgpstream ends with a truncated record. This is called
+    /// once libbgpstream reached the end of the stream or of the interval. Each file is checked
+    /// only once, so the stream ends after reporting all truncated files.
+    pub(crate) fn check_local_files(&mut self) -> Result<(), BgpStreamError> {
+        while let Some((_, file, path)) = self.local_files.pop() {
+            if let Some(offset) = crate::mrt::truncated_at(&path)? {
+                return Err(BgpStreamError::TruncatedFile { file, offset });
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the option of a data interface.
+    fn set_data_interface_option(
+        &self,
+        interface_name: &CStr,
+        option: &CStr,
+        value: &CStr,
+    ) -> Result<(), BgpStreamError> {
+        unsafe {
+            // get the data interface id
+            let if_id =
+                bgpstream_get_data_interface_id_by_name(self.bs.as_ptr(), interface_name.as_ptr());
+            if if_id == 0 {
+                return Err(BgpStreamError::InterfaceNotFound(
+                    interface_name.to_string_lossy().to_string(),
+                ));
+            }
+
+            // get the data interface option
+            let opt = bgpstream_get_data_interface_option_by_name(
+                self.bs.as_ptr(),
+                if_id,
+                option.as_ptr(),
+            );
+            if opt.is_null() {
+                return Err(BgpStreamError::InterfaceOptionNotFound(
+                    option.to_string_lossy().to_string(),
+                ));
+            }
+
+            let res = bgpstream_set_data_interface_option(
+                self.bs.as_ptr(),
+                opt,
+                value.as_ptr() as *const c_char,
+            );
+            if res != 0 {
+                return Err(BgpStreamError::SetInterfaceOption);
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the next record. Use [`BgpStream::next_record_detailed`] to learn why libbgpstream
+    /// skipped a record.
     ///
     /// If you are using `self` as `Iterator`, then getting the next record will return the current
-    /// record of the current iterator state.
+    /// record of the current iterator state (without the elements that were already yielded).
+    ///
+    /// The record mutably borrows `self`, so it must be dropped before you can fetch the next
+    /// record or continue iterating over elements. Any elements of the record that you did not
+    /// consume are lost once it is dropped. The following example iterates over all RIB records and
+    /// their elements:
+    ///
+    /// ```no_run
+    /// use routeviews::stream::*;
+    ///
+    /// # fn main() -> Result<(), routeviews::BgpStreamError> {
+    /// let mut stream = Query::new()
+    ///     .collector(Collector::RouteView(RouteView::Amsix))
+    ///     .record_type(RecordType::RIBs)
+    ///     .run()?;
+    ///
+    /// while let Some(mut record) = stream.next_record()? {
+    ///     println!("RIB from {:?} at {}", record.collector_name(), record.time());
+    ///     for element in &mut record {
+    ///         println!("{:?}", element?.prefix());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
     pub fn next_record(&mut self) -> Result<Option<Record<'_>>, BgpStreamError> {
-        // delete the current record. That one is now lost!
-        if let Some(record) = self.current_record.take() {
-            Ok(Some(record))
+        if self.remaining == Some(0) {
+            return Ok(None);
+        }
+        if let Some(raw) = self
+            .current_record
+            .take()
+            .or_else(|| self.prefetched.take())
+        {
+            // safety: `raw` is the last record fetched from `self`.
+            Ok(Some(unsafe { Record::from_raw(raw, self) }))
         } else {
             Record::new(self)
         }
     }
+
+    /// Fetch the first record, such that fetching it can be retried in [`Query::run`]. It is
+    /// yielded by the next call to [`BgpStream::next_record`].
+    fn prefetch(&mut self) -> Result<(), BgpStreamError> {
+        if let Some(record) = Record::new(self)? {
+            self.prefetched = Some(record.into_raw());
+        }
+        Ok(())
+    }
+
+    /// Get the next record, or the reason why libbgpstream skipped it. In contrast to
+    /// [`BgpStream::next_record`], sources and records that libbgpstream marks as invalid (e.g.,
+    /// an empty dump file) are reported as [`RecordOutcome::Skipped`] instead of an error or the
+    /// end of the stream, so the caller can count them by [`crate::record::RecordStatus`] and
+    /// keep going. This also reports sources excluded by the filters, regardless of
+    /// [`Query::report_filtered_sources`].
+    ///
+    /// ```no_run
+    /// use std::collections::HashMap;
+    /// use routeviews::{record::RecordOutcome, stream::*};
+    ///
+    /// # fn main() -> Result<(), routeviews::BgpStreamError> {
+    /// let mut stream = Query::new()
+    ///     .collector(Collector::RouteView(RouteView::Amsix))
+    ///     .record_type(RecordType::Updates)
+    ///     .run()?;
+    ///
+    /// let mut skipped = HashMap::new();
+    /// loop {
+    ///     match stream.next_record_detailed()? {
+    ///         RecordOutcome::Record(record) => println!("record at {}", record.time()),
+    ///         RecordOutcome::Skipped(status) => *skipped.entry(status).or_insert(0) += 1,
+    ///         RecordOutcome::End => break,
+    ///     }
+    /// }
+    /// println!("{skipped:?}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn next_record_detailed(&mut self) -> Result<RecordOutcome<'_>, BgpStreamError> {
+        if self.remaining == Some(0) {
+            return Ok(RecordOutcome::End);
+        }
+        if let Some(raw) = self
+            .current_record
+            .take()
+            .or_else(|| self.prefetched.take())
+        {
+            // safety: `raw` is the last record fetched from `self`.
+            Ok(RecordOutcome::Record(unsafe {
+                Record::from_raw(raw, self)
+            }))
+        } else {
+            Record::new_detailed(self)
+        }
+    }
+
+    /// Discard the remaining elements of the current record, without parsing them. The next call
+    /// to [`Iterator::next`] (or [`BgpStream::next_record`]) fetches a fresh record. This has no
+    /// effect if no record was started, or if the current record is already exhausted.
+    ///
+    /// When iterating over elements, the current record is the one of the element yielded last.
+    /// With [`Query::reorder_window`], its elements that are still in the reordering buffer are
+    /// discarded as well. Skipped elements do not count towards the limit set with
+    /// [`Query::limit`].
+    pub fn skip_current_record(&mut self) {
+        let Some(reorder) = self.reorder.as_mut() else {
+            self.current_record = None;
+            return;
+        };
+        // the metadata identifies the record of each element while reordering.
+        let Some(yielded) = self.yielded_meta.clone() else {
+            return;
+        };
+        reorder.retain(|(_, meta)| !meta.as_ref().is_some_and(|m| Arc::ptr_eq(m, &yielded)));
+        if self
+            .record_meta
+            .as_ref()
+            .is_some_and(|m| Arc::ptr_eq(m, &yielded))
+        {
+            self.current_record = None;
+        }
+    }
+
+    /// Collect all elements of the stream into a `Vec`, stopping at the first error. This is
+    /// equivalent to `stream.collect::<Result<Vec<_>, _>>()`.
+    ///
+    /// libbgpstream does not know the number of elements in advance (the broker only reports the
+    /// dump files, not their contents), so capacity is only reserved up front if the query is
+    /// limited with [`Query::limit`]. Make sure to restrict the query (e.g., with a short
+    /// interval), as all elements are kept in memory.
+    pub fn collect_elements(self) -> Result<Vec<Element>, BgpStreamError> {
+        let mut elements = Vec::with_capacity(self.remaining.unwrap_or(0).min(1 << 20));
+        for element in self {
+            elements.push(element?);
+        }
+        Ok(elements)
+    }
+
+    /// Collect all elements of the stream into two `Vec`s, `(ipv4, ipv6)`, split by the address
+    /// family of their prefix. Peer state changes have no prefix and are dropped. Stops at the
+    /// first error, like [`BgpStream::collect_elements`]. Use
+    /// [`crate::adapter::ElementIteratorExt::with_ip_version`] to split the stream without
+    /// collecting it.
+    pub fn partition_by_family(self) -> Result<(Vec<Element>, Vec<Element>), BgpStreamError> {
+        let mut ipv4 = Vec::new();
+        let mut ipv6 = Vec::new();
+        for element in self {
+            let element = element?;
+            match element.ip_version() {
+                Some(IpVersion::IPv4) => ipv4.push(element),
+                Some(IpVersion::IPv6) => ipv6.push(element),
+                None => {}
+            }
+        }
+        Ok((ipv4, ipv6))
+    }
+
+    /// Call `f` on each element of the stream, without allocating an owned [`Element`] for it.
+    /// This is the fastest way to process a large stream (e.g., a full RIB dump) if only a few
+    /// fields of each element are needed. Iteration stops at the first error, which is returned.
+    ///
+    /// The [`ElementRef`] passed to `f` borrows the underlying libbgpstream record, and is only
+    /// valid for the duration of the callback; the borrow checker prevents it from escaping. Use
+    /// [`ElementRef::to_element`] to keep an element around.
+    ///
+    /// A limit set with [`Query::limit`] is honored. Client-side filters operate on owned elements,
+    /// so if any are configured, each element is parsed (according to [`Query::parse_options`])
+    /// before deciding whether to call `f`. With [`Query::tolerate_element_errors`], elements that
+    /// cannot be parsed are then logged and skipped.
+    ///
+    /// ```no_run
+    /// # use routeviews::{stream::*, BgpStreamError};
+    /// # fn main() -> Result<(), BgpStreamError> {
+    /// let mut announcements = 0;
+    /// Query::new()
+    ///     .collector(Collector::RouteView(RouteView::Amsix))
+    ///     .event_type(ElementTypeDescr::Announcements)
+    ///     .interval(FilterInterval::Since {
+    ///         amount: 15,
+    ///         unit: TimeUnit::Minutes,
+    ///         live: false,
+    ///     })
+    ///     .run()?
+    ///     .for_each_element(|e| {
+    ///         if e.as_path().count() > 4 {
+    ///             announcements += 1;
+    ///         }
+    ///     })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn for_each_element<F>(mut self, mut f: F) -> Result<(), BgpStreamError>
+    where
+        F: FnMut(&ElementRef<'_>),
+    {
+        let filters = std::mem::take(&mut self.client_filters);
+        let mut remaining = self.remaining;
+        while remaining != Some(0) {
+            let Some(mut record) = self.next_record()? else {
+                break;
+            };
+            let (parse_options, tolerate) = (record.parse_options, record.tolerate_element_errors);
+            while let Some(elem) = record.next_element_ref()? {
+                if !filters.is_empty() {
+                    let e = match elem.to_element_with(parse_options) {
+                        Ok(e) => e,
+                        Err(e) if tolerate && e.is_element_error() => {
+                            log_warn!("Skipping an element that cannot be parsed: {e}");
+                            continue;
+                        }
+                        Err(e) => {
+                            #[cfg(feature = "tracing")]
+                            crate::trace::error(&e);
+                            return Err(e);
+                        }
+                    };
+                    if !filters.iter().all(|filter| filter.matches(&e)) {
+                        continue;
+                    }
+                }
+                f(&elem);
+                if let Some(remaining) = remaining.as_mut() {
+                    *remaining -= 1;
+                    if *remaining == 0 {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Turn the stream into an iterator over owned records (see [`OwnedRecord`]), each containing
+    /// all of its elements. In contrast to [`BgpStream::next_record`], the yielded records do not
+    /// borrow the stream, so they can be kept around, sent to other threads, or collected.
+    ///
+    /// Records are yielded in the order in which libbgpstream produces them (i.e., sorted by time
+    /// across all collectors), and the elements of each record keep their original order. If
+    /// elements were already yielded from the current record, the first record only contains the
+    /// remaining elements. Client-side filters are applied to the elements of each record; records
+    /// whose elements are all filtered out are still yielded (with no elements). A limit set with
+    /// [`Query::limit`] counts elements, so the last record may be truncated. If an element cannot
+    /// be parsed, the error is yielded in place of its record.
+    ///
+    /// Each record is materialized in memory, which can be large for RIB dumps.
+    ///
+    /// With [`Query::reorder_window`], records are reordered by their time. Elements that were
+    /// already fetched into the reordering buffer of the element iterator are lost.
+    pub fn into_records(self) -> RecordStream {
+        RecordStream {
+            reorder: self.reorder_window.map(ReorderBuffer::new),
+            stream: self,
+        }
+    }
+
+    /// Turn the stream into an iterator over elements paired with the metadata of their record
+    /// (collector, router, project, and record time). The metadata is computed once per record
+    /// and shared among all of its elements, so this is much cheaper than looking it up for each
+    /// element. Client-side filters, the limit, and [`Query::reorder_window`] apply as for the
+    /// element iterator; reordered elements keep the metadata of their own record.
+    ///
+    /// No element is lost when calling this on a stream that was already iterated over: the
+    /// metadata of the current record is computed right away, and elements in the reordering
+    /// buffer already carry the metadata of their record.
+    pub fn with_record_meta(mut self) -> RecordMetaStream {
+        self.track_record_meta = true;
+        if let Some(raw) = self.current_record {
+            // safety: `raw` is the last record fetched from `self`.
+            let record = unsafe { Record::from_raw(raw, &mut self) };
+            self.record_meta = Some(Arc::new(record.meta()));
+        }
+        RecordMetaStream { stream: self }
+    }
+
+    /// Export metrics about the stream through the [`metrics`] facade while iterating over it. All
+    /// metric names start with `prefix`:
+    ///
+    /// - `{prefix}_records_total`: Counter of all records fetched.
+    /// - `{prefix}_elements_total`: Counter of all elements yielded, labelled by `type` (one of
+    ///   `rib`, `announcement`, `withdrawal`, or `peerstate`).
+    /// - `{prefix}_errors_total`: Counter of all errors yielded, labelled by `error` (the
+    ///   snake-case name of the [`BgpStreamError`] variant).
+    /// - `{prefix}_lag_seconds`: Gauge of the difference between the wall clock and the time of
+    ///   the last record fetched.
+    ///
+    /// Metrics are only updated when using `self` as an `Iterator`. You need to install a
+    /// recorder (e.g., a Prometheus exporter) for the metrics to be observable.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, prefix: &str) -> Self {
+        self.metrics_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Record every distinct peer of the elements yielded by the iterator, and make them available
+    /// through [`BgpStream::peers_seen`]. This does not change the elements that are yielded.
+    /// Only elements yielded through the `Iterator` implementation are recorded (not those read
+    /// with [`BgpStream::next_record`], [`BgpStream::into_records`], or
+    /// [`BgpStream::for_each_element`]).
+    ///
+    /// ```no_run
+    /// # use routeviews::{stream::*, BgpStreamError};
+    /// # fn main() -> Result<(), BgpStreamError> {
+    /// let mut stream = Query::new()
+    ///     .collector(Collector::RouteView(RouteView::Amsix))
+    ///     .record_type(RecordType::Updates)
+    ///     .run()?
+    ///     .track_peers();
+    /// for element in &mut stream {
+    ///     element?;
+    /// }
+    /// for peer in stream.peers_seen() {
+    ///     println!("{peer}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn track_peers(mut self) -> Self {
+        self.track_peers = true;
+        self
+    }
+
+    /// All distinct peers of the elements yielded so far. This is empty unless peers are tracked
+    /// (see [`BgpStream::track_peers`]).
+    pub fn peers_seen(&self) -> &HashSet<Peer> {
+        &self.peers_seen
+    }
+
+    /// Change the RIB period filter (see [`Query::rib_period`]) of the running stream, e.g., to
+    /// sample RIB dumps less often during a long live run. Returns
+    /// [`BgpStreamError::AddRibPeriod`] if libbgpstream rejects the filter.
+    ///
+    /// libbgpstream checks the period whenever a new RIB dump starts. Hence, the new period takes
+    /// effect with the next RIB dump of each collector; a RIB dump that is currently being read
+    /// (and any records already prefetched) is not affected.
+    pub fn set_rib_period(&mut self, secs: u32) -> Result<(), BgpStreamError> {
+        let res = unsafe { bgpstream_add_rib_period_filter(self.bs.as_ptr(), secs) };
+        if res != 1 {
+            return Err(BgpStreamError::AddRibPeriod);
+        }
+        Ok(())
+    }
+
+    /// Name of the collector of the current record, i.e., the record of the element yielded last.
+    /// Returns `None` before the first record was fetched, once the current record is exhausted or
+    /// skipped (see [`BgpStream::skip_current_record`]), or if the name is not valid UTF-8.
+    ///
+    /// With [`Query::reorder_window`], this is the collector of the element yielded last, which is
+    /// kept once its record is exhausted (and invalid UTF-8 is replaced, see
+    /// [`RecordMeta::collector_name`]).
+    pub fn current_collector(&self) -> Option<&str> {
+        if self.reorder.is_some() {
+            return self
+                .yielded_meta
+                .as_deref()
+                .map(|m| m.collector_name.as_str());
+        }
+        let raw = self.current_record?;
+        // safety: the record stays valid until the stream fetches the next one, which requires a
+        // mutable borrow of `self`.
+        let bytes = unsafe { array_to_bytes(&(*raw.p_record).collector_name) };
+        std::str::from_utf8(bytes).ok()
+    }
+
+    /// Time of the current record, i.e., the record of the element yielded last. Returns `None`
+    /// in the same cases as [`BgpStream::current_collector`].
+    pub fn current_record_time(&self) -> Option<OffsetDateTime> {
+        if self.reorder.is_some() {
+            return self.yielded_meta.as_ref().map(|m| m.time);
+        }
+        self.current_record.map(|raw| raw.time)
+    }
+
+    /// Time of the last record fetched from the stream. Unlike [`BgpStream::current_record_time`],
+    /// this is kept once the record is exhausted, so it can serve as a checkpoint to resume an
+    /// interrupted run with [`Query::resume_from`].
+    ///
+    /// With [`Query::reorder_window`], newer records may already be fetched while their elements
+    /// wait in the reordering buffer. Therefore, this is the time of the record of the element
+    /// yielded last instead (or `None` before the first element), such that resuming from it
+    /// reads all buffered elements again.
+    pub fn last_record_time(&self) -> Option<OffsetDateTime> {
+        if self.reorder.is_some() {
+            return self.yielded_meta.as_ref().map(|m| m.time);
+        }
+        self.last_record_time
+    }
+
+    /// Take a snapshot of the progress of the stream, e.g., for a liveness check of a live stream.
+    /// Records are counted whenever they are fetched, while elements and errors are only counted
+    /// when using `self` as an `Iterator` (like [`BgpStream::with_metrics`], but always enabled
+    /// and without any dependencies).
+    pub fn health(&self) -> StreamHealth {
+        StreamHealth {
+            last_record_time: self.last_record_time,
+            lag: self
+                .last_record_time
+                .map(|time| OffsetDateTime::now_utc() - time),
+            ..self.health
+        }
+    }
+
+    /// Decide whether to keep the next element, according to [`Query::sample_rate`].
+    fn sample(&mut self) -> bool {
+        #[cfg(feature = "rand")]
+        if let Some((p, rng)) = self.sampler.as_mut() {
+            return rng.random_bool(*p);
+        }
+        true
+    }
+
+    /// Get the next element through the reordering buffer, if enabled, and remember the metadata
+    /// of its record.
+    fn next_ordered(&mut self) -> Option<Result<Element, BgpStreamError>> {
+        let Some(mut reorder) = self.reorder.take() else {
+            let item = self.next_element();
+            if let Some(Ok(_)) = item {
+                self.yielded_meta = self.record_meta.clone();
+            }
+            return item;
+        };
+        let item = loop {
+            if let Some((e, meta)) = reorder.pop_ready() {
+                self.yielded_meta = meta;
+                break Some(Ok(e));
+            }
+            match self.next_element() {
+                Some(Ok(e)) => reorder.push(e.time, (e, self.record_meta.clone())),
+                Some(Err(e)) => break Some(Err(e)),
+                None => {
+                    break reorder.pop().map(|(e, meta)| {
+                        self.yielded_meta = meta;
+                        Ok(e)
+                    })
+                }
+            }
+        };
+        self.reorder = Some(reorder);
+        item
+    }
+
+    /// Get the next element, fetching a new record if the current one is exhausted.
+    fn next_element(&mut self) -> Option<Result<Element, BgpStreamError>> {
+        // the reordering buffer needs the metadata to tell which record each element belongs to.
+        let track_record_meta = self.track_record_meta || self.reorder_window.is_some();
+        loop {
+            let raw = match self.current_record {
+                Some(raw) => raw,
+                None => match self.next_record() {
+                    Ok(Some(r)) => {
+                        let meta = track_record_meta.then(|| Arc::new(r.meta()));
+                        let raw = r.into_raw();
+                        self.record_meta = meta;
+                        #[cfg(feature = "metrics")]
+                        if let Some(prefix) = &self.metrics_prefix {
+                            crate::metrics::record_fetched(prefix, raw.time);
+                        }
+                        self.current_record = Some(raw);
+                        raw
+                    }
+                    Ok(None) => return None,
+                    Err(e) => return Some(Err(e)),
+                },
+            };
+            // safety: `raw` is the last record fetched from `self`.
+            let mut record = unsafe { Record::from_raw(raw, self) };
+            match record.next_element() {
+                Ok(Some(e))
+                    if self.client_filters.iter().all(|f| f.matches(&e)) && self.sample() =>
+                {
+                    return Some(Ok(e))
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    self.current_record = None;
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Iterator over owned records, returned by [`BgpStream::into_records`].
+pub struct RecordStream {
+    stream: BgpStream,
+    // records fetched but not yet yielded, if reordering is enabled.
+    reorder: Option<ReorderBuffer<OwnedRecord>>,
+}
+
+impl RecordStream {
+    /// Get back the underlying stream.
+    pub fn into_inner(self) -> BgpStream {
+        self.stream
+    }
+}
+
+impl RecordStream {
+    /// Fetch the next record from the stream, and apply the client-side filters and the limit.
+    fn fetch(&mut self) -> Option<Result<OwnedRecord, BgpStreamError>> {
+        #[cfg(feature = "metrics")]
+        let fresh = self.stream.current_record.is_none();
+        let record = match self.stream.next_record() {
+            Ok(Some(record)) => record.into_owned(),
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        #[cfg(feature = "metrics")]
+        if let (true, Some(prefix), Ok(record)) = (fresh, &self.stream.metrics_prefix, &record) {
+            crate::metrics::record_fetched(prefix, record.time);
+        }
+
+        let mut record = match record {
+            Ok(record) => record,
+            Err(e) => return Some(Err(e)),
+        };
+        let filters = &self.stream.client_filters;
+        record
+            .elements
+            .retain(|e| filters.iter().all(|f| f.matches(e)));
+        if let Some(remaining) = self.stream.remaining.as_mut() {
+            record.elements.truncate(*remaining);
+            *remaining -= record.elements.len();
+        }
+
+        #[cfg(feature = "metrics")]
+        if let Some(prefix) = &self.stream.metrics_prefix {
+            for e in &record.elements {
+                crate::metrics::element_yielded(prefix, e);
+            }
+        }
+
+        Some(Ok(record))
+    }
+}
+
+impl Iterator for RecordStream {
+    type Item = Result<OwnedRecord, BgpStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some(mut reorder) = self.reorder.take() else {
+            return self.fetch();
+        };
+        let item = loop {
+            if let Some(record) = reorder.pop_ready() {
+                break Some(Ok(record));
+            }
+            match self.fetch() {
+                Some(Ok(record)) => reorder.push(record.time, record),
+                Some(Err(e)) => break Some(Err(e)),
+                None => break reorder.pop().map(Ok),
+            }
+        };
+        self.reorder = Some(reorder);
+        item
+    }
+}
+
+/// Iterator over elements and the metadata of their record, returned by
+/// [`BgpStream::with_record_meta`].
+pub struct RecordMetaStream {
+    stream: BgpStream,
+}
+
+impl RecordMetaStream {
+    /// Get back the underlying stream.
+    pub fn into_inner(mut self) -> BgpStream {
+        self.stream.track_record_meta = false;
+        self.stream
+    }
+}
+
+impl Iterator for RecordMetaStream {
+    type Item = Result<(Arc<RecordMeta>, Element), BgpStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = match self.stream.next()? {
+            Ok(e) => e,
+            Err(e) => return Some(Err(e)),
+        };
+        // `with_record_meta` computes the metadata of the current record, and the reordering
+        // buffer always keeps the metadata of each element, so no element is without it.
+        let meta = self
+            .stream
+            .yielded_meta
+            .clone()
+            .expect("the metadata of each yielded element is tracked");
+        Some(Ok((meta, element)))
+    }
 }
 
 impl Drop for BgpStream {
@@ -714,6 +3192,9 @@ impl Drop for BgpStream {
         unsafe {
             bgpstream_destroy(self.bs.as_ptr());
         }
+        for path in &self.temp_files {
+            let _ = fs::remove_file(path);
+        }
     }
 }
 
@@ -721,27 +3202,44 @@ impl Iterator for BgpStream {
     type Item = Result<Element, BgpStreamError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            // safety: There does not exist a different record, because `self.stream`
-            // is a mutable reference.
-            if self.current_record.is_none() {
-                // safety: self.record is None.
-                match self.next_record() {
-                    Ok(Some(r)) => unsafe {
-                        self.current_record = Some(r.detach());
-                    },
-                    Ok(None) => return None,
-                    Err(e) => return Some(Err(e)),
-                }
+        if self.remaining == Some(0) {
+            return None;
+        }
+
+        let item = self.next_ordered();
+
+        if let (Some(Ok(e)), true) = (&item, self.track_peers) {
+            self.peers_seen.insert(e.peer());
+        }
+
+        if let (Some(Ok(_)), Some(remaining)) = (&item, self.remaining.as_mut()) {
+            *remaining -= 1;
+            if *remaining == 0 {
+                // the current record will never be used again.
+                self.current_record = None;
             }
-            let record = self.current_record.as_mut().unwrap();
-            match record.next_element() {
-                Ok(Some(e)) => return Some(Ok(e)),
-                Ok(None) => {
-                    self.current_record = None;
-                }
-                Err(e) => return Some(Err(e)),
+        }
+
+        match &item {
+            Some(Ok(e)) => match e.e {
+                ElementType::RIB(_) => self.health.ribs += 1,
+                ElementType::Announcement(_) => self.health.announcements += 1,
+                ElementType::Withdrawal(_) => self.health.withdrawals += 1,
+                ElementType::PeerState { .. } => self.health.peer_states += 1,
+            },
+            Some(Err(_)) => self.health.errors += 1,
+            None => {}
+        }
+
+        #[cfg(feature = "metrics")]
+        if let Some(prefix) = &self.metrics_prefix {
+            match &item {
+                Some(Ok(e)) => crate::metrics::element_yielded(prefix, e),
+                Some(Err(e)) => crate::metrics::error_yielded(prefix, e),
+                None => {}
             }
         }
+
+        item
     }
 }
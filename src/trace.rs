@@ -0,0 +1,49 @@
+//! Spans and events for the lifecycle of a [`crate::stream::BgpStream`], emitted through the
+//! [`tracing`] facade. Enable them with the `tracing` feature.
+//!
+//! - Starting a stream runs within the span `bgpstream_start` (with the `interval`, `filters`,
+//!   and `data_interface` of the query), and emits an `INFO` event once the stream is started.
+//! - Each fetched record emits a `DEBUG` event with its `project`, `collector`, `record_type`, and
+//!   `time`.
+//! - Each error emits a `WARN` event with the `error` variant (the snake-case name of the
+//!   [`BgpStreamError`] variant) and its message.
+
+use tracing::{debug, info, info_span, span::EnteredSpan, warn};
+
+use crate::{record::Record, stream::Query, BgpStreamError};
+
+/// Enter the span in which the stream of `query` is started.
+pub(crate) fn start_span(query: &Query) -> EnteredSpan {
+    let plan = query.describe();
+    info_span!(
+        "bgpstream_start",
+        interval = ?plan.interval,
+        filters = ?plan.filters,
+        data_interface = plan.data_interface.as_deref().unwrap_or("broker"),
+    )
+    .entered()
+}
+
+/// Called once the stream was started, or failed to start.
+pub(crate) fn stream_started<T>(result: &Result<T, BgpStreamError>) {
+    match result {
+        Ok(_) => info!("BGP stream started"),
+        Err(e) => error(e),
+    }
+}
+
+/// Called whenever a record is fetched.
+pub(crate) fn record_fetched(record: &Record<'_>) {
+    debug!(
+        project = record.project_name_lossy(),
+        collector = record.collector_name_lossy(),
+        record_type = %record.record_type(),
+        time = %record.time(),
+        "fetched record",
+    );
+}
+
+/// Called whenever an error occurs.
+pub(crate) fn error(e: &BgpStreamError) {
+    warn!(error = e.name(), "{e}");
+}
@@ -0,0 +1,118 @@
+//! Watchlists of prefixes, to find all entries that overlap with a given prefix.
+
+use ipnet::IpNet;
+use prefix_trie::joint::JointPrefixSet;
+
+/// A watchlist of prefixes (e.g., the address space of an organization), backed by a patricia
+/// trie. In contrast to the prefix filters of libbgpstream, which compare against a single prefix,
+/// a watchlist finds all of its entries that overlap with a prefix, i.e., that are equal to it,
+/// cover it (less specific), or are covered by it (more specific).
+///
+/// Entries are normalized (i.e., host bits are cleared) when inserted. Use
+/// [`crate::adapter::ElementIteratorExt::watch_prefixes`] to annotate a stream of elements.
+///
+/// ```
+/// use ipnet::IpNet;
+/// use routeviews::watch::PrefixWatch;
+///
+/// let net = |s: &str| s.parse::<IpNet>().unwrap();
+/// let watch: PrefixWatch = [net("10.0.0.0/8"), net("10.1.1.0/24"), net("192.0.2.0/24")]
+///     .into_iter()
+///     .collect();
+///
+/// assert_eq!(watch.matches(&net("10.1.0.0/16")), vec![net("10.0.0.0/8"), net("10.1.1.0/24")]);
+/// assert_eq!(watch.matches(&net("192.0.2.128/25")), vec![net("192.0.2.0/24")]);
+/// assert!(watch.matches(&net("198.51.100.0/24")).is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct PrefixWatch {
+    set: JointPrefixSet<IpNet>,
+}
+
+impl Default for PrefixWatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PrefixWatch {
+    /// Create an empty watchlist.
+    pub fn new() -> Self {
+        Self {
+            set: JointPrefixSet::new(),
+        }
+    }
+
+    /// Add `net` to the watchlist. Returns `false` if it was already present.
+    pub fn insert(&mut self, net: IpNet) -> bool {
+        self.set.insert(net.trunc())
+    }
+
+    /// Remove `net` from the watchlist. Returns `false` if it was not present.
+    pub fn remove(&mut self, net: &IpNet) -> bool {
+        self.set.remove(&net.trunc())
+    }
+
+    /// Whether `net` is an entry of the watchlist.
+    pub fn contains(&self, net: &IpNet) -> bool {
+        self.set.contains(&net.trunc())
+    }
+
+    /// All entries that overlap with `net`: first those that cover it (including `net` itself),
+    /// ordered from less to more specific, and then those covered by it, in lexicographic order.
+    pub fn matches(&self, net: &IpNet) -> Vec<IpNet> {
+        let mut matches: Vec<IpNet> = self.covering(net).collect();
+        matches.extend(self.covered(net));
+        matches
+    }
+
+    /// All entries that are equal to or less specific than `net`, ordered from less to more
+    /// specific.
+    pub fn covering<'a>(&'a self, net: &IpNet) -> impl Iterator<Item = IpNet> + 'a {
+        let net = net.trunc();
+        // the iterator borrows `net`, so collect the (few) covering entries.
+        self.set.cover(&net).collect::<Vec<_>>().into_iter()
+    }
+
+    /// All entries that are strictly more specific than `net`, in lexicographic order.
+    pub fn covered<'a>(&'a self, net: &IpNet) -> impl Iterator<Item = IpNet> + 'a {
+        let net = net.trunc();
+        self.set.children(&net).filter(move |entry| *entry != net)
+    }
+
+    /// Whether any entry overlaps with `net`.
+    pub fn overlaps(&self, net: &IpNet) -> bool {
+        self.covering(net).next().is_some() || self.covered(net).next().is_some()
+    }
+
+    /// Iterate over all entries.
+    pub fn iter(&self) -> impl Iterator<Item = IpNet> + '_ {
+        self.set.iter()
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    /// Whether the watchlist has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+}
+
+impl FromIterator<IpNet> for PrefixWatch {
+    fn from_iter<T: IntoIterator<Item = IpNet>>(iter: T) -> Self {
+        let mut watch = Self::new();
+        watch.extend(iter);
+        watch
+    }
+}
+
+impl Extend<IpNet> for PrefixWatch {
+    fn extend<T: IntoIterator<Item = IpNet>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|net| {
+            self.insert(net);
+        });
+    }
+}
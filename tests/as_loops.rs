@@ -0,0 +1,28 @@
+//! Module to test the detection of AS path loops.
+
+mod common;
+
+use common::update;
+
+#[test]
+fn prepending_is_no_loop() {
+    assert!(!update("1 2 3").has_as_loop());
+    assert!(!update("1 2 2 2 3").has_as_loop());
+    assert!(!update("1 1 2 3 3").has_as_loop());
+    assert!(!update("").has_as_loop());
+}
+
+#[test]
+fn loops() {
+    assert_eq!(update("1 2 3 2").loop_asns(), vec![2]);
+    assert_eq!(update("1 2 2 3 2 2").loop_asns(), vec![2]);
+    assert_eq!(update("1 2 1 2").loop_asns(), vec![1, 2]);
+}
+
+#[test]
+fn as_sets() {
+    assert!(!update("1 2 {2,3}").has_as_loop());
+    assert!(!update("1 {2,2,3}").has_as_loop());
+    assert_eq!(update("2 1 {2,3}").loop_asns(), vec![2]);
+    assert_eq!(update("1 {2,3} 4 3").loop_asns(), vec![3]);
+}
@@ -0,0 +1,83 @@
+//! Module to test parsing AS paths from strings.
+
+mod common;
+
+use routeviews::element::{parse_as_path, AsSegment};
+
+#[test]
+fn parse_sequence() {
+    assert_eq!(
+        parse_as_path("3356 174  64512").unwrap(),
+        vec![
+            AsSegment::Num(3356),
+            AsSegment::Num(174),
+            AsSegment::Num(64512)
+        ]
+    );
+    assert_eq!(parse_as_path("").unwrap(), vec![]);
+}
+
+#[test]
+fn parse_sets() {
+    assert_eq!(
+        parse_as_path("3356 {64512,64513}").unwrap(),
+        vec![AsSegment::Num(3356), AsSegment::Set(vec![64512, 64513])]
+    );
+    assert_eq!(
+        parse_as_path("3356{1, 2} 3").unwrap(),
+        vec![
+            AsSegment::Num(3356),
+            AsSegment::Set(vec![1, 2]),
+            AsSegment::Num(3)
+        ]
+    );
+}
+
+#[test]
+fn round_trip() {
+    let path = vec![
+        AsSegment::Num(3356),
+        AsSegment::Set(vec![1, 2]),
+        AsSegment::Num(3),
+    ];
+    let s = path
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    assert_eq!(parse_as_path(&s).unwrap(), path);
+}
+
+#[test]
+fn parse_malformed() {
+    assert!(parse_as_path("3356 foo").is_err());
+    assert!(parse_as_path("3356 -1").is_err());
+    assert!(parse_as_path("3356 4294967296").is_err());
+    assert!(parse_as_path("3356 {1,2").is_err());
+    assert!(parse_as_path("3356 {}").is_err());
+    assert!(parse_as_path("3356 {1,x}").is_err());
+}
+
+fn neighbor_asn(path: &str) -> Option<u32> {
+    common::update(path).neighbor_asn()
+}
+
+#[test]
+fn neighbor_asn_normal_path() {
+    assert_eq!(neighbor_asn("3356 174 64512"), Some(3356));
+    assert_eq!(neighbor_asn("3356 3356 174"), Some(3356));
+    assert_eq!(neighbor_asn("64512"), Some(64512));
+    assert_eq!(neighbor_asn(""), None);
+}
+
+#[test]
+fn neighbor_asn_confederation_prefixed() {
+    // confederation segments are reported as sets
+    assert_eq!(neighbor_asn("{65001} {65002,65003} 3356 174"), Some(3356));
+}
+
+#[test]
+fn neighbor_asn_set_leading() {
+    assert_eq!(neighbor_asn("{64512,64513} 174"), Some(174));
+    assert_eq!(neighbor_asn("{64512,64513}"), None);
+}
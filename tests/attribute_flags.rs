@@ -0,0 +1,58 @@
+//! Module to test the path attributes reported by an owned update.
+
+mod common;
+
+use common::{PEER_IP, PREFIX};
+use routeviews::element::{AttributeFlags, OriginType, Update, UpdateAttributeFlags};
+
+#[test]
+fn empty_update() {
+    let update = common::update("3356 174");
+    assert_eq!(update.attribute_flags(), UpdateAttributeFlags::default());
+}
+
+#[test]
+fn kept_attributes() {
+    let update = Update::builder(PREFIX.parse().unwrap(), PEER_IP.parse().unwrap())
+        .origin_type(OriginType::Igp)
+        .local_pref(100)
+        .community(3356, 2)
+        .build();
+    assert_eq!(
+        update.attribute_flags(),
+        UpdateAttributeFlags {
+            origin: true,
+            med: false,
+            local_pref: true,
+            communities: true,
+        }
+    );
+
+    let update = Update::builder(PREFIX.parse().unwrap(), PEER_IP.parse().unwrap())
+        .med(10)
+        .build();
+    let flags = update.attribute_flags();
+    assert!(flags.med);
+    assert!(!flags.origin && !flags.local_pref && !flags.communities);
+}
+
+#[test]
+fn from_record_flags() {
+    let flags = AttributeFlags {
+        origin: true,
+        med: true,
+        local_pref: false,
+        communities: true,
+        atomic_aggregate: true,
+        aggregator: true,
+    };
+    assert_eq!(
+        UpdateAttributeFlags::from(flags),
+        UpdateAttributeFlags {
+            origin: true,
+            med: true,
+            local_pref: false,
+            communities: true,
+        }
+    );
+}
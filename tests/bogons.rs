@@ -0,0 +1,52 @@
+//! Module to test the classification of reserved prefixes and AS numbers.
+
+use ipnet::IpNet;
+use routeviews::element::{is_bogon_prefix, is_private_asn, is_reserved_asn};
+
+fn bogon(prefix: &str) -> bool {
+    is_bogon_prefix(&prefix.parse::<IpNet>().unwrap())
+}
+
+#[test]
+fn ipv4_prefixes() {
+    assert!(bogon("10.0.0.0/8"));
+    assert!(bogon("10.1.2.0/24"));
+    assert!(bogon("192.168.1.0/24"));
+    assert!(bogon("100.64.0.0/10"));
+    assert!(bogon("192.0.2.0/24"));
+    assert!(bogon("224.0.0.0/4"));
+    assert!(!bogon("0.0.0.0/0"));
+    assert!(!bogon("8.8.8.0/24"));
+    assert!(!bogon("172.32.0.0/16"));
+    assert!(!bogon("100.128.0.0/10"));
+}
+
+#[test]
+fn ipv6_prefixes() {
+    assert!(bogon("2001:db8::/32"));
+    assert!(bogon("2001:db8:1::/48"));
+    assert!(bogon("fc00::/7"));
+    assert!(bogon("fd12:3456::/32"));
+    assert!(bogon("fe80::/64"));
+    assert!(!bogon("::/0"));
+    assert!(!bogon("2001:4860::/32"));
+    assert!(!bogon("2a00::/12"));
+}
+
+#[test]
+fn asns() {
+    assert!(is_private_asn(64512));
+    assert!(is_private_asn(65534));
+    assert!(is_private_asn(4_200_000_000));
+    assert!(!is_private_asn(65535));
+    assert!(!is_private_asn(3356));
+
+    assert!(is_reserved_asn(0));
+    assert!(is_reserved_asn(23456));
+    assert!(is_reserved_asn(64496));
+    assert!(is_reserved_asn(65535));
+    assert!(is_reserved_asn(u32::MAX));
+    assert!(!is_reserved_asn(64512));
+    assert!(!is_reserved_asn(3356));
+    assert!(!is_reserved_asn(131072));
+}
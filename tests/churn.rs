@@ -0,0 +1,67 @@
+//! Module to test the detection of duplicate announcements and implicit withdrawals.
+
+mod common;
+
+use common::{announcement, element};
+use routeviews::{
+    analysis::{ChurnDetector, ChurnKind},
+    element::{ElementType, PeerState},
+};
+
+#[test]
+fn classify() {
+    let mut detector = ChurnDetector::new();
+    assert_eq!(
+        detector.classify(&announcement(3356, "3356 64512")),
+        Some(ChurnKind::New)
+    );
+    assert_eq!(
+        detector.classify(&announcement(3356, "3356 64512")),
+        Some(ChurnKind::Duplicate)
+    );
+    assert_eq!(
+        detector.classify(&announcement(3356, "3356 174 64512")),
+        Some(ChurnKind::Changed)
+    );
+    assert_eq!(
+        detector.classify(&announcement(3356, "3356 174 64512")),
+        Some(ChurnKind::Duplicate)
+    );
+    // a different peer has its own state
+    assert_eq!(
+        detector.classify(&announcement(174, "174 64512")),
+        Some(ChurnKind::New)
+    );
+    assert_eq!(detector.len(), 2);
+}
+
+#[test]
+fn withdrawal_and_session_reset() {
+    let mut detector = ChurnDetector::new();
+    detector.classify(&announcement(3356, "3356 64512"));
+    detector.classify(&announcement(174, "174 64512"));
+
+    let withdrawal = element(
+        3356,
+        ElementType::Withdrawal("192.0.2.0/24".parse().unwrap()),
+    );
+    assert_eq!(detector.classify(&withdrawal), None);
+    assert_eq!(
+        detector.classify(&announcement(3356, "3356 64512")),
+        Some(ChurnKind::New)
+    );
+
+    let reset = element(
+        174,
+        ElementType::PeerState {
+            from: PeerState::Established,
+            to: PeerState::Idle,
+        },
+    );
+    assert_eq!(detector.classify(&reset), None);
+    assert_eq!(detector.len(), 1);
+    assert_eq!(
+        detector.classify(&announcement(174, "174 64512")),
+        Some(ChurnKind::New)
+    );
+}
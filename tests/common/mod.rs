@@ -0,0 +1,55 @@
+//! Constructors for the elements used in the tests. Unless given otherwise, all elements are
+//! received at the Unix epoch from the peer `198.51.100.1` (which is also the next hop), and all
+//! updates are for the prefix `192.0.2.0/24`.
+#![allow(dead_code)]
+
+use routeviews::element::{parse_as_path, Element, ElementType, Update};
+use time::{Duration, OffsetDateTime};
+
+/// The IP address of the peer (and the next hop) of all elements.
+pub const PEER_IP: &str = "198.51.100.1";
+
+/// The prefix of all updates.
+pub const PREFIX: &str = "192.0.2.0/24";
+
+/// An update of `prefix` with the AS path `path` (see [`parse_as_path`]).
+pub fn update_of(prefix: &str, path: &str) -> Update {
+    Update::builder(prefix.parse().unwrap(), PEER_IP.parse().unwrap())
+        .as_path(parse_as_path(path).unwrap())
+        .build()
+}
+
+/// An update of [`PREFIX`] with the AS path `path` (see [`parse_as_path`]).
+pub fn update(path: &str) -> Update {
+    update_of(PREFIX, path)
+}
+
+/// An element received from the peer with `peer_asn`.
+pub fn element(peer_asn: u32, e: ElementType) -> Element {
+    element_at(0, peer_asn, e)
+}
+
+/// An element received `secs` seconds after the Unix epoch from the peer with `peer_asn`.
+pub fn element_at(secs: i64, peer_asn: u32, e: ElementType) -> Element {
+    Element {
+        time: OffsetDateTime::UNIX_EPOCH + Duration::seconds(secs),
+        peer_ip: PEER_IP.parse().unwrap(),
+        peer_asn,
+        e,
+    }
+}
+
+/// An announcement of [`PREFIX`] with the AS path `path` from the peer with `peer_asn`.
+pub fn announcement(peer_asn: u32, path: &str) -> Element {
+    element(peer_asn, ElementType::Announcement(update(path)))
+}
+
+/// A withdrawal of `prefix` received `secs` seconds after the Unix epoch from the peer with
+/// `peer_asn`.
+pub fn withdrawal_at(secs: i64, peer_asn: u32, prefix: &str) -> Element {
+    element_at(
+        secs,
+        peer_asn,
+        ElementType::Withdrawal(prefix.parse().unwrap()),
+    )
+}
@@ -0,0 +1,33 @@
+//! Module to test the formatting and parsing of communities.
+
+use routeviews::element::{Community, LargeCommunity};
+
+#[test]
+fn round_trip() {
+    let c: Community = "65000:100".parse().unwrap();
+    assert_eq!(c, Community(65000, 100));
+    assert_eq!(c.to_string(), "65000:100");
+    assert_eq!(<(u16, u16)>::from(c), (65000, 100));
+
+    let lc: LargeCommunity = "4200000000:1:2".parse().unwrap();
+    assert_eq!(lc, LargeCommunity(4200000000, 1, 2));
+    assert_eq!(lc.to_string(), "4200000000:1:2");
+}
+
+#[test]
+fn malformed() {
+    for s in [
+        "",
+        "65000",
+        "65000:",
+        "65000:100:1",
+        "65536:1",
+        "a:b",
+        "-1:1",
+    ] {
+        assert!(s.parse::<Community>().is_err(), "{s}");
+    }
+    for s in ["65000:1", "65000:1:2:3", "1::2", "4294967296:1:2"] {
+        assert!(s.parse::<LargeCommunity>().is_err(), "{s}");
+    }
+}
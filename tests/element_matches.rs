@@ -0,0 +1,105 @@
+//! Module to test evaluating the filters of a query on single elements.
+#![cfg(feature = "ffi")]
+
+mod common;
+
+use ipnet::IpNet;
+use routeviews::{
+    element::{Element, ElementType},
+    stream::*,
+};
+
+/// An announcement of `192.0.2.0/24` from AS3356 with the community `3356:2`.
+fn announcement() -> Element {
+    let mut update = common::update("3356 64512");
+    update.communities = vec![(3356, 2)];
+    common::element(3356, ElementType::Announcement(update))
+}
+
+#[test]
+fn element_filters() {
+    let e = announcement();
+
+    assert!(e.matches(&Query::new()));
+    assert!(e.matches(Query::new().peer_asn("3356").origin_asn("64512")));
+    assert!(!e.matches(Query::new().peer_asn("174")));
+    // filters of the same type are combined with OR
+    assert!(e.matches(Query::new().peer_asn("174").peer_asn("3356")));
+    assert!(!e.matches(Query::new().not_peer_asn("3356")));
+
+    assert!(e.matches(Query::new().prefix(PrefixMatchType::More, "192.0.0.0/16")));
+    assert!(!e.matches(Query::new().prefix(PrefixMatchType::Less, "192.0.0.0/16")));
+    assert!(e.matches(Query::new().prefix(PrefixMatchType::Exact, "192.0.2.0/24")));
+
+    assert!(e.matches(Query::new().community("3356:*")));
+    assert!(!e.matches(Query::new().community("*:3")));
+    assert!(e.matches(Query::new().ip_version(IpVersion::IPv4)));
+    assert!(!e.matches(Query::new().event_type(ElementTypeDescr::Withdrawals)));
+
+    // record-level filters are ignored, client-side filters are applied
+    assert!(e.matches(Query::new().collector(Collector::RouteView(RouteView::Amsix))));
+    assert!(!e.matches(Query::new().peer_asn_range(64512..=65534)));
+}
+
+#[test]
+fn community_wildcard() {
+    let e = announcement();
+
+    assert!(e.matches(Query::new().community_wildcard(Some(3356), Some(2))));
+    assert!(e.matches(Query::new().community_wildcard(None, Some(2))));
+    assert!(e.matches(Query::new().community_wildcard(Some(3356), None)));
+    assert!(e.matches(Query::new().community_wildcard(None, None)));
+    assert!(!e.matches(Query::new().community_wildcard(None, Some(3))));
+    assert!(!e.matches(Query::new().community_wildcard(Some(174), None)));
+    // multiple patterns are combined with AND
+    assert!(!e.matches(
+        Query::new()
+            .community_wildcard(Some(3356), None)
+            .community_wildcard(Some(174), None)
+    ));
+}
+
+#[test]
+fn matched_filters() {
+    let e = announcement();
+    let mut query = Query::new();
+    query
+        .collector(Collector::RouteView(RouteView::Amsix))
+        .peer_asn("174")
+        .peer_asn("3356")
+        .prefix(PrefixMatchType::More, "192.0.0.0/16")
+        .prefix(PrefixMatchType::Exact, "198.51.100.0/24");
+
+    assert_eq!(
+        query.matched_filters(&e),
+        vec![
+            ("peer-asn", "3356".to_string()),
+            ("prefix-more", "192.0.0.0/16".to_string())
+        ]
+    );
+}
+
+#[test]
+fn peer_and_next_hop_in() {
+    let e = announcement();
+    assert!(e.peer_in("198.51.100.0/24".parse().unwrap()));
+    assert!(!e.peer_in("203.0.113.0/24".parse().unwrap()));
+    assert!(!e.peer_in("::/0".parse().unwrap()));
+
+    let update = e.as_update().unwrap();
+    assert!(update.next_hop_in("198.51.100.0/30".parse().unwrap()));
+    assert!(!update.next_hop_in("198.51.100.4/30".parse().unwrap()));
+}
+
+#[test]
+fn exclude_prefixes() {
+    let e = announcement();
+    let nets = |s: &[&str]| s.iter().map(|n| n.parse().unwrap()).collect::<Vec<IpNet>>();
+
+    assert!(!e.matches(Query::new().exclude_prefixes(nets(&["192.0.0.0/16"]))));
+    assert!(!e.matches(Query::new().exclude_prefixes(nets(&["192.0.2.0/24"]))));
+    assert!(e.matches(Query::new().exclude_prefixes(nets(&["192.0.2.0/25", "10.0.0.0/8"]))));
+
+    assert!(e.matches(Query::new().exclude_prefixes_exact(nets(&["192.0.0.0/16"]))));
+    assert!(!e.matches(Query::new().exclude_prefixes_exact(nets(&["192.0.2.1/24"]))));
+}
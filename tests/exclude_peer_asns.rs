@@ -0,0 +1,49 @@
+//! Module to test excluding peers from the stream.
+#![cfg(feature = "ffi")]
+
+use std::collections::BTreeSet;
+
+use routeviews::{stream::*, BgpStreamError};
+use time::{Duration, OffsetDateTime};
+
+/// Collect the peer AS numbers of all updates from AMSIX between `start` and `stop`, excluding the
+/// peers in `exclude`.
+fn peer_asns(
+    start: OffsetDateTime,
+    stop: OffsetDateTime,
+    exclude: &[u32],
+) -> Result<BTreeSet<u32>, BgpStreamError> {
+    Query::new()
+        .collector(Collector::RouteView(RouteView::Amsix))
+        .record_type(RecordType::Updates)
+        .interval(FilterInterval::Interval {
+            start,
+            stop: Some(stop),
+        })
+        .exclude_peer_asns(exclude.iter().copied())
+        .run()?
+        .map(|element| element.map(|element| element.peer_asn))
+        .collect()
+}
+
+#[test]
+fn exclude_peer_asns() {
+    for hour in 1..=10 {
+        std::thread::sleep(std::time::Duration::from_millis(1000));
+        let start = OffsetDateTime::now_utc() - Duration::days(1) - Duration::hours(hour);
+        let stop = start + Duration::minutes(5);
+        let peers = match peer_asns(start, stop, &[]) {
+            Ok(peers) if peers.len() > 2 => peers,
+            Ok(_) | Err(BgpStreamError::RecordSourceEmptyOrCorrupted) => continue,
+            Err(e) => panic!("Received an unexpected error: {e:?}"),
+        };
+
+        let excluded: Vec<u32> = peers.iter().copied().take(2).collect();
+        let remaining = peer_asns(start, stop, &excluded).unwrap();
+        assert!(!remaining.is_empty());
+        assert!(remaining.iter().all(|asn| !excluded.contains(asn)));
+        return;
+    }
+
+    panic!("None of the 10 tries resulted in a usable result!")
+}
@@ -0,0 +1,60 @@
+//! Module to test the histogram of update inter-arrival times.
+
+mod common;
+
+use routeviews::{
+    analysis::{ExpHistogram, InterArrival},
+    element::Element,
+};
+use time::Duration;
+
+fn withdrawal(peer_asn: u32, secs: i64) -> Element {
+    common::withdrawal_at(secs, peer_asn, common::PREFIX)
+}
+
+#[test]
+fn buckets() {
+    let mut h = ExpHistogram::new();
+    h.record(Duration::ZERO);
+    h.record(Duration::milliseconds(1));
+    h.record(Duration::milliseconds(5));
+    h.record(Duration::milliseconds(-5));
+    assert_eq!(h.count(), 4);
+    let buckets: Vec<_> = h.buckets().map(|(_, _, c)| c).collect();
+    assert_eq!(buckets, vec![2, 1, 0, 1]);
+    let (lower, upper, _) = h.buckets().nth(3).unwrap();
+    assert_eq!(lower, Duration::milliseconds(4));
+    assert_eq!(upper, Duration::milliseconds(8));
+}
+
+#[test]
+fn buckets_overflow() {
+    let mut h = ExpHistogram::new();
+    h.record(Duration::MAX);
+    h.record(Duration::MIN);
+    let buckets: Vec<_> = h.buckets().collect();
+    assert_eq!(buckets.len(), 65);
+    assert_eq!(buckets[0].2, 1);
+    let (lower, upper, count) = buckets[64];
+    assert_eq!(count, 1);
+    assert_eq!(lower.whole_milliseconds(), 1 << 63);
+    assert_eq!(upper.whole_milliseconds(), 1 << 64);
+}
+
+#[test]
+fn per_peer() {
+    let mut ia = InterArrival::new();
+    for e in [
+        withdrawal(1, 0),
+        withdrawal(2, 0),
+        withdrawal(1, 1),
+        withdrawal(1, 2),
+        withdrawal(2, 60),
+    ] {
+        ia.record(&e);
+    }
+    let peer = withdrawal(1, 0).peer();
+    assert_eq!(ia.histogram(&peer).unwrap().count(), 2);
+    assert_eq!(ia.iter().count(), 2);
+    assert_eq!(ia.merged().count(), 3);
+}
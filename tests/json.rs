@@ -0,0 +1,42 @@
+//! Module to test the JSON representation of elements.
+#![cfg(feature = "json")]
+
+mod common;
+
+use routeviews::element::{ElementType, PeerState};
+use serde_json::json;
+use time::macros::datetime;
+
+#[test]
+fn to_json() {
+    let mut update = common::update("3356 {64512,64513}");
+    update.communities = vec![(3356, 2)];
+    update.med = Some(10);
+    let time = datetime!(2023-11-08 10:00 UTC).unix_timestamp();
+    let element = |e| common::element_at(time, 3356, e);
+
+    assert_eq!(
+        element(ElementType::Announcement(update)).to_json(),
+        json!({
+            "type": "announcement",
+            "time": "2023-11-08T10:00:00Z",
+            "peer_ip": "198.51.100.1",
+            "peer_asn": 3356,
+            "prefix": "192.0.2.0/24",
+            "next_hop": "198.51.100.1",
+            "as_path": [3356, [64512, 64513]],
+            "communities": [[3356, 2]],
+            "origin_type": null,
+            "med": 10,
+            "local_pref": null,
+        })
+    );
+    assert_eq!(
+        element(ElementType::PeerState {
+            from: PeerState::OpenSent,
+            to: PeerState::Established
+        })
+        .to_json()["from"],
+        "open_sent"
+    );
+}
@@ -0,0 +1,50 @@
+//! Module to test streaming from multiple collectors at once.
+#![cfg(feature = "ffi")]
+
+use std::collections::HashSet;
+
+use routeviews::{stream::*, BgpStreamError};
+use time::{Duration, OffsetDateTime};
+
+#[test]
+fn two_collectors() {
+    for hour in 1..=10 {
+        std::thread::sleep(std::time::Duration::from_millis(1000));
+        let start = OffsetDateTime::now_utc() - Duration::days(1) - Duration::hours(hour);
+        let stop = Some(start + Duration::minutes(15));
+        let mut stream = match Query::new()
+            .add_collectors([
+                Collector::RouteView(RouteView::Amsix),
+                Collector::RouteView(RouteView::Linx),
+            ])
+            .record_type(RecordType::Updates)
+            .interval(FilterInterval::Interval { start, stop })
+            .run()
+        {
+            Ok(stream) => stream,
+            Err(BgpStreamError::RecordSourceEmptyOrCorrupted) => continue,
+            Err(e) => panic!("Received an unexpected error: {e:?}"),
+        };
+
+        let mut collectors = HashSet::new();
+        loop {
+            match stream.next_record() {
+                Ok(Some(record)) => {
+                    collectors.insert(record.collector_name().unwrap());
+                }
+                Ok(None) => break,
+                Err(BgpStreamError::RecordSourceEmptyOrCorrupted) => continue,
+                Err(e) => panic!("Received an unexpected error: {e:?}"),
+            }
+        }
+
+        if collectors.len() < 2 {
+            continue;
+        }
+        assert!(collectors.contains("route-views.amsix"));
+        assert!(collectors.contains("route-views.linx"));
+        return;
+    }
+
+    panic!("None of the 10 tries resulted in records from both collectors!")
+}
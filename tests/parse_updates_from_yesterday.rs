@@ -1,4 +1,5 @@
 //! Module to test each collector.
+#![cfg(feature = "ffi")]
 
 use routeviews::{stream::*, BgpStreamError};
 use time::{Duration, OffsetDateTime};
@@ -0,0 +1,42 @@
+//! Module to test the edit distance between AS paths.
+
+mod common;
+
+use common::update;
+
+#[test]
+fn identical() {
+    let a = update("3356 174 64512");
+    assert_eq!(a.path_distance(&a), 0);
+    assert_eq!(update("").path_distance(&update("")), 0);
+}
+
+#[test]
+fn prepend_only() {
+    let a = update("3356 174 64512");
+    let b = update("3356 174 64512 64512 64512");
+    assert_eq!(a.path_distance(&b), 2);
+    assert_eq!(b.path_distance(&a), 2);
+}
+
+#[test]
+fn reroute() {
+    let a = update("3356 174 64512");
+    let b = update("3356 1299 64512");
+    assert_eq!(a.path_distance(&b), 1);
+
+    let c = update("6939 2914 1299 64512");
+    assert_eq!(a.path_distance(&c), 3);
+    assert_eq!(c.path_distance(&a), 3);
+
+    assert_eq!(a.path_distance(&update("")), 3);
+}
+
+#[test]
+fn sets() {
+    let a = update("3356 {64512,64513}");
+    assert_eq!(a.path_distance(&update("3356 {64513,64512}")), 0);
+    assert_eq!(a.path_distance(&update("3356 {64512}")), 1);
+    assert_eq!(a.path_distance(&update("3356 64512")), 1);
+    assert_eq!(a.path_distance(&update("3356 64512 64513")), 2);
+}
@@ -0,0 +1,51 @@
+//! Module to test watchlists of prefixes.
+
+mod common;
+
+use ipnet::IpNet;
+use routeviews::{adapter::ElementIteratorExt, watch::PrefixWatch};
+
+fn net(s: &str) -> IpNet {
+    s.parse().unwrap()
+}
+
+#[test]
+fn matches() {
+    let watch: PrefixWatch = [net("10.0.0.0/8"), net("10.1.1.0/24"), net("2001:db8::/32")]
+        .into_iter()
+        .collect();
+    assert_eq!(watch.len(), 3);
+
+    // covering entries first, then covered ones
+    assert_eq!(
+        watch.matches(&net("10.1.0.0/16")),
+        vec![net("10.0.0.0/8"), net("10.1.1.0/24")]
+    );
+    assert_eq!(
+        watch.matches(&net("10.1.1.0/24")),
+        vec![net("10.0.0.0/8"), net("10.1.1.0/24")]
+    );
+    assert_eq!(watch.matches(&net("0.0.0.0/0")).len(), 2);
+    assert_eq!(
+        watch.matches(&net("2001:db8:1::/48")),
+        vec![net("2001:db8::/32")]
+    );
+    assert!(watch.matches(&net("11.0.0.0/8")).is_empty());
+    assert!(!watch.overlaps(&net("2001:db9::/32")));
+}
+
+#[test]
+fn adapter() {
+    let watch: PrefixWatch = [net("192.0.2.0/24")].into_iter().collect();
+    let withdrawal = |p: &str| Ok(common::withdrawal_at(0, 3356, p));
+    let matched: Vec<_> = [
+        withdrawal("192.0.2.0/25"),
+        withdrawal("198.51.100.0/24"),
+        withdrawal("192.0.0.0/16"),
+    ]
+    .into_iter()
+    .watch_prefixes(&watch)
+    .map(|x| x.unwrap().0.prefix().unwrap())
+    .collect();
+    assert_eq!(matched, vec![net("192.0.2.0/25"), net("192.0.0.0/16")]);
+}
@@ -0,0 +1,63 @@
+//! Module to test building queries from command-line arguments.
+#![cfg(feature = "clap")]
+
+use clap::Parser;
+use routeviews::{cli::QueryArgs, stream::Query};
+
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    query: QueryArgs,
+}
+
+fn filters(args: &[&str]) -> Vec<(&'static str, String)> {
+    let cli = Cli::try_parse_from(std::iter::once("test").chain(args.iter().copied())).unwrap();
+    Query::from_args(cli.query).unwrap().describe().filters
+}
+
+#[test]
+fn all_filters() {
+    assert_eq!(
+        filters(&[
+            "-c",
+            "route-views.amsix",
+            "--project",
+            "ris",
+            "-t",
+            "ribs",
+            "--prefix",
+            "192.0.2.0/24",
+            "--prefix-match",
+            "more",
+            "--peer-asn",
+            "3356",
+            "--origin-asn",
+            "174",
+            "--community",
+            "65000:100",
+        ]),
+        vec![
+            ("collector", "route-views.amsix".to_string()),
+            ("project", "ris".to_string()),
+            ("record-type", "ribs".to_string()),
+            ("prefix-more", "192.0.2.0/24".to_string()),
+            ("peer-asn", "3356".to_string()),
+            ("origin-asn", "174".to_string()),
+            ("community", "65000:100".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn invalid_values() {
+    let parse =
+        |args: &[&str]| Cli::try_parse_from(std::iter::once("test").chain(args.iter().copied()));
+    assert!(parse(&["-t", "everything"]).is_err());
+    assert!(parse(&["--community", "65000"]).is_err());
+    assert!(parse(&["--stop", "2023-11-08"]).is_err());
+
+    let cli = parse(&["-c", "nonexistent*"]).unwrap();
+    assert!(cli.query.into_query().is_err());
+    let cli = parse(&["-s", "yesterday"]).unwrap();
+    assert!(cli.query.into_query().is_err());
+}
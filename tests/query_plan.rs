@@ -0,0 +1,360 @@
+//! Module to test the description of queries, without running them.
+#![cfg(feature = "ffi")]
+
+use routeviews::stream::*;
+
+#[test]
+fn exclude_peer_asns() {
+    let plan = Query::new()
+        .peer_asn("3356")
+        .exclude_peer_asns([174, 1299])
+        .describe();
+    assert_eq!(
+        plan.filters,
+        vec![
+            ("peer-asn", "3356".to_string()),
+            ("not-peer-asn", "174".to_string()),
+            ("not-peer-asn", "1299".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn local_file_selects_singlefile() {
+    let plan = Query::new()
+        .local_file(RecordType::Updates, "updates.mrt")
+        .record_type(RecordType::Updates)
+        .describe();
+    assert_eq!(plan.data_interface.as_deref(), Some("singlefile"));
+    assert_eq!(
+        plan.local_files,
+        vec![(RecordType::Updates, "updates.mrt".into())]
+    );
+    assert_eq!(plan.filters, vec![("record-type", "updates".to_string())]);
+}
+
+#[test]
+fn client_filters() {
+    let plan = Query::new()
+        .peer_asn_range(64512..=65534)
+        .limit(10)
+        .describe();
+    assert_eq!(plan.client_filters, vec!["peer-asn in 64512..=65534"]);
+    assert_eq!(plan.limit, Some(10));
+    assert_eq!(plan.filters, vec![]);
+}
+
+#[test]
+fn replace_semantics() {
+    let plan = Query::new()
+        .project(Project::RouteViews)
+        .project(Project::RIS)
+        .record_type(RecordType::RIBs)
+        .record_type(RecordType::Updates)
+        .collector(Collector::RouteView(RouteView::Amsix))
+        .collector_name("rrc00")
+        .describe();
+    assert_eq!(
+        plan.filters,
+        vec![
+            ("project", "ris".to_string()),
+            ("record-type", "updates".to_string()),
+            ("collector", "rrc00".to_string()),
+        ]
+    );
+
+    let plan = Query::new()
+        .only_routeviews()
+        .add_project(Project::RIS)
+        .describe();
+    assert_eq!(
+        plan.filters,
+        vec![
+            ("project", "routeviews".to_string()),
+            ("project", "ris".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn rib_period_duration() {
+    use time::Duration;
+
+    let mut query = Query::new();
+    query.rib_period_duration(Duration::minutes(15)).unwrap();
+    assert_eq!(query.describe().rib_period, Some(900));
+
+    assert!(query.rib_period_duration(Duration::ZERO).is_err());
+    assert!(query.rib_period_duration(Duration::seconds(-60)).is_err());
+    assert!(query
+        .rib_period_duration(Duration::milliseconds(1500))
+        .is_err());
+    assert!(query.rib_period_duration(Duration::days(100_000)).is_err());
+    assert_eq!(query.describe().rib_period, Some(900));
+}
+
+#[test]
+fn add_collectors() {
+    let plan = Query::new()
+        .collector(Collector::RouteView(RouteView::Amsix))
+        .add_collectors([
+            Collector::RouteView(RouteView::Linx),
+            Collector::RipeNcc(RipeNcc::Amsterdam),
+        ])
+        .describe();
+    assert_eq!(
+        plan.filters,
+        vec![
+            ("collector", "route-views.amsix".to_string()),
+            ("collector", "route-views.linx".to_string()),
+            ("collector", "rrc00".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn time_range() {
+    use time::macros::datetime;
+
+    let start = datetime!(2023-11-08 09:55 UTC);
+    let stop = datetime!(2023-11-08 10:05 UTC);
+
+    let plan = Query::new().time_range(start..stop).describe();
+    assert_eq!(
+        plan.interval,
+        FilterInterval::Interval {
+            start,
+            stop: Some(stop)
+        }
+    );
+
+    let plan = Query::new().time_range(start..).describe();
+    assert_eq!(
+        plan.interval,
+        FilterInterval::Interval { start, stop: None }
+    );
+}
+
+#[test]
+fn resume_from() {
+    use time::macros::datetime;
+
+    let start = datetime!(2023-11-08 09:55 UTC);
+    let checkpoint = datetime!(2023-11-08 10:00 UTC);
+    let stop = datetime!(2023-11-08 10:05 UTC);
+
+    let plan = Query::new()
+        .time_range(start..stop)
+        .resume_from(checkpoint)
+        .describe();
+    assert_eq!(
+        plan.interval,
+        FilterInterval::Interval {
+            start: checkpoint,
+            stop: Some(stop)
+        }
+    );
+}
+
+#[test]
+fn check_dump_duration() {
+    use time::macros::datetime;
+
+    let since = |amount, unit| FilterInterval::Since {
+        amount,
+        unit,
+        live: false,
+    };
+
+    let mut query = Query::new();
+    query.interval(since(1, TimeUnit::Seconds));
+    assert!(query.check_dump_duration().is_err());
+
+    // RIS writes updates every 5 minutes
+    query
+        .collector(Collector::RipeNcc(RipeNcc::Amsterdam))
+        .record_type(RecordType::Updates)
+        .interval(since(10, TimeUnit::Minutes));
+    assert!(query.check_dump_duration().is_ok());
+
+    // RouteViews writes updates every 15 minutes
+    query.collector(Collector::RouteView(RouteView::Amsix));
+    assert!(query.check_dump_duration().is_err());
+
+    // live streams are never reported
+    query.interval(FilterInterval::Since {
+        amount: 1,
+        unit: TimeUnit::Seconds,
+        live: true,
+    });
+    assert!(query.check_dump_duration().is_ok());
+
+    // fixed intervals are only checked for RIB dumps alone
+    query.time_range(datetime!(2023-11-08 09:55 UTC)..datetime!(2023-11-08 09:56 UTC));
+    assert!(query.check_dump_duration().is_ok());
+    query.record_type(RecordType::RIBs);
+    assert!(query.check_dump_duration().is_err());
+
+    // a short fixed interval without a record type filter includes the updates
+    let mut query = Query::new();
+    query.time_range(datetime!(2023-11-08 09:55 UTC)..datetime!(2023-11-08 10:05 UTC));
+    assert!(query.check_dump_duration().is_ok());
+    query.only_routeviews();
+    assert!(query.check_dump_duration().is_ok());
+}
+
+#[test]
+fn validate_collector_project() {
+    let mut query = Query::new();
+    query
+        .only_ris()
+        .collector(Collector::RouteView(RouteView::Amsix));
+    let err = query.validate().unwrap_err();
+    assert!(err.to_string().contains("route-views.amsix"));
+    assert!(err.to_string().contains("RIS"));
+
+    query.add_project(Project::RouteViews);
+    assert!(query.validate().is_ok());
+
+    query
+        .only_routeviews()
+        .collector(Collector::RipeNcc(RipeNcc::Amsterdam));
+    assert!(query.validate().is_err());
+
+    // Collector filters are combined using a logical OR, so one matching collector suffices.
+    let mut query = Query::new();
+    query
+        .add_collectors([
+            Collector::RipeNcc(RipeNcc::Amsterdam),
+            Collector::RouteView(RouteView::Amsix),
+        ])
+        .only_ris();
+    assert!(query.validate().is_ok());
+}
+
+#[test]
+fn validate_element_types() {
+    use ElementTypeDescr::*;
+
+    let mut query = Query::new();
+    query
+        .event_type(RIBs)
+        .element_types(&[Announcements, Withdrawals]);
+    assert_eq!(
+        query.describe().filters,
+        vec![
+            ("elem-type", "announcements".to_string()),
+            ("elem-type", "withdrawals".to_string())
+        ]
+    );
+    assert!(query.validate().is_ok());
+
+    query.record_type(RecordType::RIBs);
+    assert!(matches!(
+        query.validate(),
+        Err(routeviews::BgpStreamError::RecordTypeExcludesElementTypes { .. })
+    ));
+    query.record_type(RecordType::Updates);
+    assert!(query.validate().is_ok());
+    query.element_types(&[RIBs]);
+    assert!(query.validate().is_err());
+    query.element_types(&[]);
+    assert!(query.validate().is_ok());
+}
+
+#[test]
+fn sqlite_broker() {
+    assert!(Query::new().sqlite_broker("does/not/exist.sqlite").is_err());
+
+    let plan = Query::new().sqlite_broker("Cargo.toml").unwrap().describe();
+    assert_eq!(plan.data_interface.as_deref(), Some("sqlite"));
+    assert_eq!(
+        plan.data_interface_options,
+        vec![("sqlite".into(), "db-file".into(), "Cargo.toml".into())]
+    );
+}
+
+#[test]
+fn collector_pattern() {
+    let plan = Query::new().collector_pattern("RRC*").unwrap().describe();
+    assert_eq!(plan.filters.len(), RipeNcc::ALL.len());
+    assert!(plan.filters.iter().all(|(kind, name)| *kind == "collector"
+        && name.parse::<Collector>().unwrap().project() == Project::RIS));
+
+    for collector in Collector::all() {
+        assert_eq!(collector.to_string().parse::<Collector>(), Ok(collector));
+    }
+    assert!("rrc02".parse::<Collector>().is_err());
+}
+
+#[test]
+fn path_filters() {
+    let plan = Query::new()
+        .path_contains(3356)
+        .path_origin(4_200_000_000)
+        .describe();
+    assert_eq!(
+        plan.filters,
+        vec![
+            ("aspath", "_3356_".to_string()),
+            ("aspath", "_4200000000$".to_string())
+        ]
+    );
+}
+
+#[test]
+fn interval_until_now() {
+    let start = time::macros::datetime!(2023-11-08 00:00 UTC);
+    let before = time::OffsetDateTime::now_utc();
+    let plan = Query::new().interval_until_now(start).describe();
+    let FilterInterval::Interval {
+        start: s,
+        stop: Some(stop),
+    } = plan.interval
+    else {
+        panic!("expected a bounded interval, got {:?}", plan.interval);
+    };
+    assert_eq!(s, start);
+    assert!(before <= stop && stop <= time::OffsetDateTime::now_utc());
+}
+
+#[test]
+fn start_retry() {
+    let backoff = std::time::Duration::from_secs(1);
+    let plan = Query::new().retry_start(3, backoff).describe();
+    assert_eq!(plan.start_retry, Some((3, backoff)));
+
+    let plan = Query::new()
+        .data_interface("broker")
+        .retry_start(3, backoff)
+        .describe();
+    assert_eq!(plan.start_retry, Some((3, backoff)));
+
+    let plan = Query::new()
+        .local_file(RecordType::Updates, "updates.mrt")
+        .retry_start(3, backoff)
+        .describe();
+    assert_eq!(plan.start_retry, None);
+
+    let plan = Query::new()
+        .from_bytes(RecordType::RIBs, Vec::new())
+        .retry_start(3, backoff)
+        .describe();
+    assert_eq!(plan.start_retry, None);
+
+    assert_eq!(Query::new().describe().start_retry, None);
+}
+
+#[test]
+fn start_retry_errors() {
+    use routeviews::BgpStreamError;
+
+    assert!(BgpStreamError::Start.is_retryable_at_start());
+    assert!(BgpStreamError::GetNextRecord.is_retryable_at_start());
+    assert!(BgpStreamError::RecordSourceEmptyOrCorrupted.is_retryable_at_start());
+
+    assert!(!BgpStreamError::Create.is_retryable_at_start());
+    assert!(!BgpStreamError::AddFilter.is_retryable_at_start());
+    assert!(!BgpStreamError::RecordCorrupted.is_retryable_at_start());
+    assert!(!BgpStreamError::InterfaceNotFound("kafka".into()).is_retryable_at_start());
+}
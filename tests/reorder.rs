@@ -0,0 +1,103 @@
+//! Module to test restoring the time order of elements with a bounded buffer.
+
+mod common;
+
+use std::cell::Cell;
+
+use common::withdrawal_at;
+use routeviews::{adapter::ElementIteratorExt, element::Element, BgpStreamError};
+use time::Duration;
+
+/// The time (in seconds) of each element, or `None` for an error.
+fn times(items: impl Iterator<Item = Result<Element, BgpStreamError>>) -> Vec<Option<i64>> {
+    items
+        .map(|e| e.ok().map(|e| e.time.unix_timestamp()))
+        .collect()
+}
+
+fn stream(secs: &[i64]) -> Vec<Result<Element, BgpStreamError>> {
+    secs.iter()
+        .map(|s| Ok(withdrawal_at(*s, 3356, "192.0.2.0/24")))
+        .collect()
+}
+
+#[test]
+fn ordering() {
+    let items = stream(&[3, 1, 2, 10, 8, 9, 20]);
+    assert_eq!(
+        times(items.into_iter().reorder(Duration::seconds(5))),
+        [1, 2, 3, 8, 9, 10, 20].map(Some)
+    );
+}
+
+#[test]
+fn equal_times_keep_their_order() {
+    let items = [(5, 1), (3, 2), (5, 3), (3, 4)]
+        .map(|(secs, asn)| Ok(withdrawal_at(secs, asn, "192.0.2.0/24")));
+    let asns: Vec<u32> = items
+        .into_iter()
+        .reorder(Duration::seconds(10))
+        .map(|e| e.unwrap().peer_asn)
+        .collect();
+    assert_eq!(asns, vec![2, 4, 1, 3]);
+}
+
+#[test]
+fn late_elements_are_kept() {
+    // the element at 1 arrives after the window of 2 seconds has passed.
+    let items = stream(&[2, 5, 1, 9]);
+    assert_eq!(
+        times(items.into_iter().reorder(Duration::seconds(2))),
+        [2, 1, 5, 9].map(Some)
+    );
+}
+
+#[test]
+fn window_flush() {
+    // count the elements pulled from the source before each element is yielded.
+    let pulled = Cell::new(0);
+    let source = stream(&[0, 1, 4, 5, 6]).into_iter().inspect(|_| {
+        pulled.set(pulled.get() + 1);
+    });
+    let mut reordered = source.reorder(Duration::seconds(5));
+
+    // the element at 0 is held back until the element at 5 arrives.
+    assert_eq!(reordered.next().unwrap().unwrap().time.unix_timestamp(), 0);
+    assert_eq!(pulled.get(), 4);
+    // the element at 1 is held back until the element at 6 arrives.
+    assert_eq!(reordered.next().unwrap().unwrap().time.unix_timestamp(), 1);
+    assert_eq!(pulled.get(), 5);
+}
+
+#[test]
+fn end_of_stream_drain() {
+    let items = stream(&[30, 10, 20]);
+    assert_eq!(
+        times(items.into_iter().reorder(Duration::hours(1))),
+        [10, 20, 30].map(Some)
+    );
+    assert_eq!(
+        times(Vec::new().into_iter().reorder(Duration::hours(1))),
+        Vec::<Option<i64>>::new()
+    );
+}
+
+#[test]
+fn errors_pass_through() {
+    let mut items = stream(&[2, 1]);
+    items.insert(1, Err(BgpStreamError::RecordCorrupted));
+    // the error is yielded immediately, while the element at 2 is still buffered.
+    assert_eq!(
+        times(items.into_iter().reorder(Duration::seconds(5))),
+        vec![None, Some(1), Some(2)]
+    );
+}
+
+#[test]
+fn zero_window_keeps_order() {
+    let items = stream(&[3, 1, 2]);
+    assert_eq!(
+        times(items.into_iter().reorder(Duration::ZERO)),
+        [3, 1, 2].map(Some)
+    );
+}
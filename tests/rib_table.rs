@@ -0,0 +1,102 @@
+//! Module to test maintaining a routing table from RIB entries and updates.
+
+mod common;
+
+use common::{element, update};
+use routeviews::{
+    adapter::ElementIteratorExt,
+    analysis::{RibChange, RibTable},
+    element::{Element, ElementType, PeerState},
+};
+
+#[test]
+fn apply() {
+    let mut table = RibTable::new();
+    let rib = element(3356, ElementType::RIB(update("3356 64512")));
+    let peer = rib.peer();
+    let prefix = "192.0.2.0/24".parse().unwrap();
+
+    assert!(matches!(table.apply(&rib), Some(RibChange::Added { .. })));
+    // the same route again does not change the table
+    let same = element(3356, ElementType::Announcement(update("3356 64512")));
+    assert_eq!(table.apply(&same), None);
+
+    let new = element(3356, ElementType::Announcement(update("3356 174 64512")));
+    assert!(matches!(
+        table.apply(&new),
+        Some(RibChange::Replaced { .. })
+    ));
+    assert_eq!(table.route(&peer, &prefix), Some(&update("3356 174 64512")));
+
+    table.apply(&element(174, ElementType::RIB(update("174 64512"))));
+    assert_eq!(table.len(), 2);
+    assert_eq!(table.routes(&prefix).count(), 2);
+
+    let withdrawal = element(3356, ElementType::Withdrawal(prefix));
+    assert!(matches!(
+        table.apply(&withdrawal),
+        Some(RibChange::Withdrawn { .. })
+    ));
+    assert_eq!(table.apply(&withdrawal), None);
+
+    let down = element(
+        174,
+        ElementType::PeerState {
+            from: PeerState::Established,
+            to: PeerState::Idle,
+        },
+    );
+    assert!(matches!(
+        table.apply(&down),
+        Some(RibChange::PeerDown { routes: 1, .. })
+    ));
+    assert!(table.is_empty());
+}
+
+#[test]
+fn synthetic_withdrawals() {
+    let mut table = RibTable::new();
+    table.apply(&element(3356, ElementType::RIB(update("3356 64512"))));
+    table.apply(&element(174, ElementType::RIB(update("174 64512"))));
+
+    let mut other = update("3356 64513");
+    other.prefix = "198.51.100.0/24".parse().unwrap();
+    let down = element(
+        3356,
+        ElementType::PeerState {
+            from: PeerState::Established,
+            to: PeerState::Idle,
+        },
+    );
+    let stream = vec![
+        element(3356, ElementType::Announcement(other)),
+        down.clone(),
+    ];
+
+    let mut iter = stream.into_iter().map(Ok).synthetic_withdrawals(table);
+    let elements: Vec<Element> = iter.by_ref().map(Result::unwrap).collect();
+    assert_eq!(elements.len(), 4);
+    assert!(matches!(
+        elements[1].e,
+        ElementType::PeerState {
+            to: PeerState::Idle,
+            ..
+        }
+    ));
+    let withdrawn: Vec<_> = elements[2..]
+        .iter()
+        .map(|e| match e.e {
+            ElementType::Withdrawal(prefix) => (e.peer(), prefix.to_string()),
+            _ => panic!("expected a withdrawal, got {e:?}"),
+        })
+        .collect();
+    assert_eq!(
+        withdrawn,
+        vec![
+            (down.peer(), "192.0.2.0/24".to_string()),
+            (down.peer(), "198.51.100.0/24".to_string())
+        ]
+    );
+    // only the routes of the other peer are left
+    assert_eq!(iter.table().len(), 1);
+}
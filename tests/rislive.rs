@@ -0,0 +1,180 @@
+//! Module to test parsing RIS Live messages and building the subscriptions.
+#![cfg(feature = "rislive")]
+
+use routeviews::{
+    element::{AsSegment, ElementType, PeerState},
+    rislive::{parse_message, subscriptions},
+    stream::{Collector, PrefixMatchType, Query, RipeNcc},
+    BgpStreamError,
+};
+use serde_json::json;
+
+const UPDATE: &str = r#"{
+    "type": "ris_message",
+    "data": {
+        "timestamp": 1699437600.5,
+        "peer": "192.0.2.1",
+        "peer_asn": "64512",
+        "id": "1699437600.5-192.0.2.1-1",
+        "host": "rrc00.ripe.net",
+        "type": "UPDATE",
+        "path": [64512, 3356, [64600, 64601]],
+        "community": [[64512, 100]],
+        "origin": "IGP",
+        "med": 10,
+        "announcements": [
+            {"next_hop": "192.0.2.1", "prefixes": ["203.0.113.0/24", "198.51.100.0/24"]},
+            {"next_hop": "2001:db8::1,fe80::1", "prefixes": ["2001:db8:1::/48"]}
+        ],
+        "withdrawals": ["192.0.2.128/25"]
+    }
+}"#;
+
+#[test]
+fn announcements_and_withdrawals() {
+    let elements = parse_message(UPDATE).unwrap();
+    assert_eq!(elements.len(), 4);
+    assert!(elements.iter().all(|e| e.peer_asn == 64512));
+    assert_eq!(elements[0].time.unix_timestamp(), 1699437600);
+
+    let updates: Vec<_> = elements[..3]
+        .iter()
+        .map(|e| match &e.e {
+            ElementType::Announcement(update) => update,
+            e => panic!("expected an announcement, got {e:?}"),
+        })
+        .collect();
+    assert_eq!(updates[0].prefix, "203.0.113.0/24".parse().unwrap());
+    assert_eq!(updates[1].prefix, "198.51.100.0/24".parse().unwrap());
+    assert_eq!(
+        updates[2].next_hop,
+        "2001:db8::1".parse::<std::net::IpAddr>().unwrap()
+    );
+    assert_eq!(
+        updates[0].as_path,
+        vec![
+            AsSegment::Num(64512),
+            AsSegment::Num(3356),
+            AsSegment::Set(vec![64600, 64601])
+        ]
+    );
+    assert_eq!(updates[0].communities, vec![(64512, 100)]);
+    assert_eq!(updates[0].med, Some(10));
+    assert_eq!(updates[0].local_pref, None);
+
+    assert!(matches!(
+        elements[3].e,
+        ElementType::Withdrawal(prefix) if prefix == "192.0.2.128/25".parse().unwrap()
+    ));
+}
+
+#[test]
+fn peer_state() {
+    let msg = json!({
+        "type": "ris_message",
+        "data": {
+            "timestamp": 1699437600.0,
+            "peer": "192.0.2.1",
+            "peer_asn": "64512",
+            "type": "RIS_PEER_STATE",
+            "state": "down"
+        }
+    });
+    let elements = parse_message(&msg.to_string()).unwrap();
+    assert_eq!(elements.len(), 1);
+    assert!(matches!(
+        elements[0].e,
+        ElementType::PeerState {
+            from: PeerState::Unknown,
+            to: PeerState::Idle
+        }
+    ));
+}
+
+#[test]
+fn error_message() {
+    let msg = r#"{"type": "ris_error", "data": {"message": "Unknown host"}}"#;
+    assert!(matches!(
+        parse_message(msg),
+        Err(BgpStreamError::RisLive(message)) if message == "Unknown host"
+    ));
+}
+
+#[test]
+fn other_messages_are_ignored() {
+    let messages = [
+        json!({
+            "type": "ris_subscribe_ok",
+            "data": {"subscription": {"host": "rrc00"}, "socketOptions": {}}
+        }),
+        json!({"type": "pong", "data": null}),
+        json!({"type": "pong"}),
+        json!({"type": "unknown", "data": [1, 2, 3]}),
+        json!({
+            "type": "ris_message",
+            "data": {
+                "timestamp": 1699437600.0,
+                "peer": "192.0.2.1",
+                "peer_asn": "64512",
+                "type": "KEEPALIVE"
+            }
+        }),
+    ];
+    for msg in messages {
+        assert!(parse_message(&msg.to_string()).unwrap().is_empty(), "{msg}");
+    }
+}
+
+#[test]
+fn malformed_messages() {
+    assert!(matches!(
+        parse_message("not json"),
+        Err(BgpStreamError::InvalidRisLiveMessage(_))
+    ));
+    let msg = json!({
+        "type": "ris_message",
+        "data": {"timestamp": 0.0, "peer": "192.0.2.1", "peer_asn": "AS64512", "type": "UPDATE"}
+    });
+    assert!(matches!(
+        parse_message(&msg.to_string()),
+        Err(BgpStreamError::RisLive(_))
+    ));
+}
+
+#[test]
+fn subscription_per_collector() {
+    let filters = Query::new()
+        .add_collector(Collector::RipeNcc(RipeNcc::Amsterdam))
+        .add_collector(Collector::RipeNcc(RipeNcc::London))
+        .prefix(PrefixMatchType::More, "192.0.2.0/24")
+        .describe()
+        .filters;
+    let subscription = |host: &str| {
+        json!({
+            "type": "ris_subscribe",
+            "data": {
+                "host": host,
+                "prefix": "192.0.2.0/24",
+                "moreSpecific": true,
+                "lessSpecific": false
+            }
+        })
+    };
+    assert_eq!(
+        subscriptions(&filters),
+        vec![subscription("rrc00"), subscription("rrc01")]
+    );
+}
+
+#[test]
+fn single_subscription_without_collector() {
+    let filters = Query::new()
+        .prefix(PrefixMatchType::Exact, "192.0.2.0/24")
+        .prefix(PrefixMatchType::Exact, "198.51.100.0/24")
+        .describe()
+        .filters;
+    assert_eq!(
+        subscriptions(&filters),
+        vec![json!({"type": "ris_subscribe", "data": {}})]
+    );
+}
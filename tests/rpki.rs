@@ -0,0 +1,61 @@
+//! Module to test route origin validation.
+
+use ipnet::IpNet;
+use routeviews::rpki::{Roa, RoaSet, RouteValidity};
+
+fn net(s: &str) -> IpNet {
+    s.parse().unwrap()
+}
+
+#[test]
+fn validate_origin() {
+    let roas: RoaSet = [
+        (net("10.0.0.0/8"), 8, 64500),
+        (net("10.1.0.0/16"), 24, 64501),
+        (net("10.2.0.0/16"), 16, 0),
+        (net("2001:db8::/32"), 48, 64502),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(roas.len(), 4);
+
+    // any covering ROA may validate the route
+    assert_eq!(
+        roas.validate_origin(&net("10.1.1.0/24"), Some(64501)),
+        RouteValidity::Valid
+    );
+    // the length error takes precedence over other ROAs with a different origin
+    assert_eq!(
+        roas.validate_origin(&net("10.0.0.0/9"), Some(64500)),
+        RouteValidity::InvalidLength
+    );
+    assert_eq!(
+        roas.validate_origin(&net("10.1.1.0/24"), Some(64500)),
+        RouteValidity::InvalidLength
+    );
+    // AS 0 and unknown origins never match
+    assert_eq!(
+        roas.validate_origin(&net("10.2.0.0/16"), Some(0)),
+        RouteValidity::InvalidOrigin
+    );
+    assert_eq!(
+        roas.validate_origin(&net("10.1.0.0/16"), None),
+        RouteValidity::InvalidOrigin
+    );
+    assert_eq!(
+        roas.validate_origin(&net("2001:db8:1::/48"), Some(64502)),
+        RouteValidity::Valid
+    );
+    assert_eq!(
+        roas.validate_origin(&net("11.0.0.0/8"), Some(64500)),
+        RouteValidity::NotFound
+    );
+
+    assert_eq!(
+        roas.covering(&net("10.1.2.0/24")),
+        vec![
+            Roa::from((net("10.0.0.0/8"), 8, 64500)),
+            Roa::from((net("10.1.0.0/16"), 24, 64501)),
+        ]
+    );
+}
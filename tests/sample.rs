@@ -0,0 +1,52 @@
+//! Module to test the reservoir sampler.
+#![cfg(feature = "rand")]
+
+use routeviews::analysis::ReservoirSampler;
+
+#[test]
+fn small_stream() {
+    let mut sampler = ReservoirSampler::seeded(10, 0);
+    sampler.ingest_all(0..5);
+    let mut sample = sampler.into_sample();
+    sample.sort();
+    assert_eq!(sample, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn reproducible() {
+    let sample = |seed| {
+        let mut sampler = ReservoirSampler::seeded(10, seed);
+        sampler.ingest_all(0..1000);
+        sampler.into_sample()
+    };
+    assert_eq!(sample(42), sample(42));
+    assert_ne!(sample(42), sample(43));
+}
+
+#[test]
+fn uniform() {
+    // Sample 10 out of 100 items in 2000 trials. Each item is expected to be sampled 200 times,
+    // with a standard deviation of about 13.4.
+    let mut counts = [0usize; 100];
+    for seed in 0..2000 {
+        let mut sampler = ReservoirSampler::seeded(10, seed);
+        sampler.ingest_all(0..100);
+        assert_eq!(sampler.seen(), 100);
+        for x in sampler.into_sample() {
+            counts[x] += 1;
+        }
+    }
+    for (x, count) in counts.iter().enumerate() {
+        assert!(
+            (140..=260).contains(count),
+            "item {x} sampled {count} times"
+        );
+    }
+}
+
+#[cfg(feature = "ffi")]
+#[test]
+#[should_panic]
+fn sample_rate_out_of_range() {
+    routeviews::Query::new().sample_rate(1.5);
+}
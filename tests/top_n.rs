@@ -0,0 +1,56 @@
+//! Module to test the approximate top-N counts.
+
+mod common;
+
+use routeviews::{analysis::TopN, element::Element};
+
+fn announcement(origin: u32) -> Element {
+    common::announcement(3356, &format!("3356 {origin}"))
+}
+
+#[test]
+fn exact_below_capacity() {
+    let mut top = TopN::origin_asn(2);
+    for origin in [1, 2, 2, 3, 3, 3] {
+        top.record(&announcement(origin));
+    }
+    assert_eq!(top.results(), vec![(3, 3), (2, 2)]);
+    assert_eq!(top.total(), 6);
+
+    top.clear();
+    assert!(top.results().is_empty());
+}
+
+#[test]
+fn skewed() {
+    // Three heavy hitters (1000, 500, and 250 occurrences) hidden among 5000 keys that occur only
+    // once each, with a capacity of only 30 keys.
+    let mut top = TopN::with_capacity(3, 30, |e: &Element| Some(e.peer_asn));
+    let mut keys = Vec::new();
+    for i in 0..5000 {
+        keys.push(100_000 + i);
+        if i % 5 == 0 {
+            keys.push(1);
+        }
+        if i % 10 == 0 {
+            keys.push(2);
+        }
+        if i % 20 == 0 {
+            keys.push(3);
+        }
+    }
+    for key in keys {
+        top.record_key(key);
+    }
+
+    let results = top.results();
+    assert_eq!(
+        results.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+    // counts are overestimated by at most total / capacity.
+    let max_error = top.total() / 30;
+    for ((_, count), exact) in results.iter().zip([1000, 500, 250]) {
+        assert!((exact..=exact + max_error).contains(count));
+    }
+}
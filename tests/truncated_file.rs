@@ -0,0 +1,92 @@
+//! Module to test that a truncated local MRT file is reported, while a complete one ends cleanly.
+#![cfg(feature = "ffi")]
+
+use routeviews::{
+    element::{ElementType, PeerState},
+    stream::{FilterInterval, Query, RecordType},
+    BgpStreamError,
+};
+use time::OffsetDateTime;
+
+/// A BGP4MP_STATE_CHANGE record (RFC 6396, Section 4.4.1) of an IPv4 session from Idle to
+/// Established.
+fn state_change(timestamp: u32) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend(64512u16.to_be_bytes()); // peer AS
+    body.extend(64513u16.to_be_bytes()); // local AS
+    body.extend(0u16.to_be_bytes()); // interface index
+    body.extend(1u16.to_be_bytes()); // AFI (IPv4)
+    body.extend([192, 0, 2, 1]); // peer IP
+    body.extend([192, 0, 2, 2]); // local IP
+    body.extend(1u16.to_be_bytes()); // old state (Idle)
+    body.extend(6u16.to_be_bytes()); // new state (Established)
+
+    let mut record = Vec::new();
+    record.extend(timestamp.to_be_bytes());
+    record.extend(16u16.to_be_bytes()); // type BGP4MP
+    record.extend(0u16.to_be_bytes()); // subtype STATE_CHANGE
+    record.extend((body.len() as u32).to_be_bytes());
+    record.extend(body);
+    record
+}
+
+fn read(bytes: Vec<u8>) -> Vec<Result<ElementType, BgpStreamError>> {
+    Query::new()
+        .from_bytes(RecordType::Updates, bytes)
+        .run()
+        .unwrap()
+        .map(|e| e.map(|e| e.e))
+        .collect()
+}
+
+#[test]
+fn complete_file_ends_cleanly() {
+    let mut bytes = state_change(1699437600);
+    bytes.extend(state_change(1699437601));
+    let elements = read(bytes);
+    assert_eq!(elements.len(), 2);
+    for e in elements {
+        assert!(matches!(
+            e,
+            Ok(ElementType::PeerState {
+                to: PeerState::Established,
+                ..
+            })
+        ));
+    }
+}
+
+#[test]
+fn truncated_file_is_reported() {
+    let mut bytes = state_change(1699437600);
+    let second = state_change(1699437601);
+    bytes.extend(&second[..second.len() - 5]);
+    let elements = read(bytes);
+    assert!(matches!(elements[0], Ok(ElementType::PeerState { .. })));
+    assert!(elements
+        .iter()
+        .any(|e| matches!(e, Err(BgpStreamError::TruncatedFile { offset: 32, .. }))));
+}
+
+#[test]
+fn truncated_file_after_interval_is_reported() {
+    let mut bytes = state_change(1699437600);
+    bytes.extend(state_change(1699437700));
+    let third = state_change(1699437701);
+    bytes.extend(&third[..third.len() - 5]);
+    let elements: Vec<_> = Query::new()
+        .from_bytes(RecordType::Updates, bytes)
+        .interval(FilterInterval::Interval {
+            start: OffsetDateTime::from_unix_timestamp(1699437500).unwrap(),
+            stop: Some(OffsetDateTime::from_unix_timestamp(1699437650).unwrap()),
+        })
+        .run()
+        .unwrap()
+        .collect();
+    let in_interval: Vec<_> = elements.iter().filter_map(|e| e.as_ref().ok()).collect();
+    assert_eq!(in_interval.len(), 1);
+    assert_eq!(in_interval[0].time.unix_timestamp(), 1699437600);
+    assert!(elements
+        .iter()
+        .any(|e| matches!(e, Err(BgpStreamError::TruncatedFile { offset: 64, .. }))));
+}